@@ -21,4 +21,26 @@ pub fn softmax(x: &[f32]) -> Vec<f32> {
     exp_x.iter()
         .map(|&exp_val| exp_val / sum_exp_x)
         .collect()
-}
\ No newline at end of file
+}
+
+/// activation_from_type が知っている活性化関数の名前
+pub const ACTIVATION_TYPES: [&str; 3] = ["identity", "relu", "sigmoid"];
+
+/// activation_type に対応する活性化関数（未知の名前は identity）
+pub fn activation_from_type(activation_type: &str) -> fn(f32) -> f32 {
+    match activation_type {
+        "relu" => relu,
+        "sigmoid" => sigmoid,
+        _ => identity,
+    }
+}
+
+/// 活性化関数の勾配を出力 y = f(z) から計算する
+pub fn activation_backward(activation_type: &str, y: f32, grad: f32) -> f32 {
+    match activation_type {
+        "relu" => if y > 0.0 { grad } else { 0.0 },
+        // σ'(z) = σ(z) * (1 - σ(z)) = y * (1 - y)
+        "sigmoid" => grad * y * (1.0 - y),
+        _ => grad,  // identity
+    }
+}
@@ -0,0 +1,2 @@
+pub mod functional;
+pub mod tape;
@@ -0,0 +1,47 @@
+//! 既存のレイヤーと損失関数を autograd の演算で書いたもの
+//!
+//! 勾配は手書きせず、`Tape::backward` で求める。
+
+use crate::autograd::tape::Var;
+use crate::layers::fc_layer::FcLayer;
+use crate::layers::base_layer::AbstractLayerTrait;
+
+/// activation_type に対応する活性化関数（FcLayer と同じ名前を使う）
+pub fn activation<'t>(x: Var<'t>, activation_type: &str) -> Var<'t> {
+    match activation_type {
+        "relu" => x.relu(),
+        "sigmoid" => x.sigmoid(),
+        _ => x,  // identity
+    }
+}
+
+/// 全結合層: f(X W^T + b)（X: [batch, i_size], W: [o_size, i_size], b: [o_size]）
+pub fn linear<'t>(x: Var<'t>, w: Var<'t>, b: Var<'t>, activation_type: &str) -> Var<'t> {
+    activation(x.matmul(w.transpose()) + b, activation_type)
+}
+
+/// FcLayer のパラメータをテープに載せて forward する
+/// 戻り値は (出力, w, b) で、w / b は Gradients::wrt で勾配を取り出すのに使う
+pub fn fc_layer<'t>(layer: &FcLayer, x: Var<'t>) -> (Var<'t>, Var<'t>, Var<'t>) {
+    let tape = x.tape();
+    let w = tape.var(layer.w().expect("FcLayer always has weights").clone());
+    let b = tape.var(layer.b().expect("FcLayer always has biases").clone());
+    let y = linear(x, w, b, layer.activation_type());
+    (y, w, b)
+}
+
+/// 行ごとの softmax（SoftmaxLayer と同じく最大値を引いて安定化する）
+pub fn softmax<'t>(x: Var<'t>) -> Var<'t> {
+    let shape = x.shape();
+    let max = x.tape().constant(x.value().max_axis(1).into_reshape(&[shape[0], 1]));
+    let exp_x = (x - max).exp();
+    exp_x / exp_x.sum_axis(1, true)
+}
+
+/// CrossEntropyLoss と同じ定義（確率を [EPSILON, 1 - EPSILON] 付近にクリップしたバッチ平均）
+pub fn cross_entropy<'t>(y_true: Var<'t>, y_pred: Var<'t>) -> Var<'t> {
+    const EPSILON: f32 = 1e-7;
+    let batch_size = y_pred.shape()[0].max(1) as f32;
+    let y_pred_clipped = y_pred.add_scalar(EPSILON).min_scalar(1.0 - EPSILON);
+    (y_true * y_pred_clipped.ln()).sum().scale(-1.0 / batch_size)
+}
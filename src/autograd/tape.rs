@@ -0,0 +1,311 @@
+use std::cell::RefCell;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::tensor::Tensor;
+
+/// 出力側の勾配から各入力（parents）への勾配を計算する関数
+type BackwardFn = Box<dyn Fn(&Tensor) -> Vec<Tensor>>;
+
+struct Node {
+    value: Tensor,
+    parents: Vec<usize>,
+    backward: Option<BackwardFn>,
+    requires_grad: bool,
+}
+
+/// forward で行った演算を記録するテープ
+///
+/// `var` / `constant` で葉を作り、`Var` の演算で計算グラフを組み立てる。
+/// `backward` は記録を逆順に辿り、スカラーの出力に対する各ノードの勾配を返す。
+#[derive(Default)]
+pub struct Tape {
+    nodes: RefCell<Vec<Node>>,
+}
+
+/// テープ上のノードへの参照
+#[derive(Clone, Copy)]
+pub struct Var<'t> {
+    tape: &'t Tape,
+    index: usize,
+}
+
+/// backward の結果（ノードごとの勾配）
+pub struct Gradients {
+    grads: Vec<Option<Tensor>>,
+}
+
+impl Gradients {
+    /// var に対する勾配（var が損失に寄与していなければ None）
+    pub fn wrt(&self, var: Var<'_>) -> Option<&Tensor> {
+        self.grads.get(var.index).and_then(|g| g.as_ref())
+    }
+}
+
+/// ブロードキャストで広がった軸を足し合わせて、勾配を元の shape に戻す
+fn reduce_to_shape(grad: &Tensor, shape: &[usize]) -> Tensor {
+    let mut grad = grad.clone();
+    while grad.ndim() > shape.len() {
+        grad = grad.sum_axis(0);
+    }
+    for (axis, &dim) in shape.iter().enumerate() {
+        if dim == 1 && grad.dim(axis) != 1 {
+            let mut kept = grad.shape().to_vec();
+            kept[axis] = 1;
+            grad = grad.sum_axis(axis).into_reshape(&kept);
+        }
+    }
+    grad
+}
+
+impl Tape {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(&self, value: Tensor, parents: Vec<usize>, backward: Option<BackwardFn>) -> Var<'_> {
+        let mut nodes = self.nodes.borrow_mut();
+        let requires_grad = parents.iter().any(|&p| nodes[p].requires_grad);
+        let backward = if requires_grad { backward } else { None };
+        nodes.push(Node { value, parents, backward, requires_grad });
+        Var { tape: self, index: nodes.len() - 1 }
+    }
+
+    /// 勾配を求めたいパラメータや入力
+    pub fn var(&self, value: Tensor) -> Var<'_> {
+        let mut nodes = self.nodes.borrow_mut();
+        nodes.push(Node { value, parents: Vec::new(), backward: None, requires_grad: true });
+        Var { tape: self, index: nodes.len() - 1 }
+    }
+
+    /// 勾配を追跡しない定数（正解ラベルなど）
+    pub fn constant(&self, value: Tensor) -> Var<'_> {
+        self.push(value, Vec::new(), None)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.borrow().is_empty()
+    }
+
+    /// スカラー（要素数 1）の output から逆伝播する
+    pub fn backward(&self, output: Var<'_>) -> Gradients {
+        let nodes = self.nodes.borrow();
+        assert_eq!(nodes[output.index].value.len(), 1, "backward() needs a scalar output");
+
+        let mut grads: Vec<Option<Tensor>> = vec![None; nodes.len()];
+        grads[output.index] = Some(Tensor::ones(nodes[output.index].value.shape()));
+
+        for index in (0..=output.index).rev() {
+            let node = &nodes[index];
+            let (Some(backward), Some(grad)) = (&node.backward, &grads[index]) else {
+                continue;
+            };
+            let parent_grads = backward(grad);
+            for (&parent, parent_grad) in node.parents.iter().zip(parent_grads) {
+                if !nodes[parent].requires_grad {
+                    continue;
+                }
+                match &mut grads[parent] {
+                    Some(acc) => *acc += &parent_grad,
+                    slot @ None => *slot = Some(parent_grad),
+                }
+            }
+        }
+        Gradients { grads }
+    }
+}
+
+impl<'t> Var<'t> {
+    pub fn tape(&self) -> &'t Tape {
+        self.tape
+    }
+
+    pub fn value(&self) -> Tensor {
+        self.tape.nodes.borrow()[self.index].value.clone()
+    }
+
+    pub fn shape(&self) -> Vec<usize> {
+        self.tape.nodes.borrow()[self.index].value.shape().to_vec()
+    }
+
+    fn unary(self, value: Tensor, backward: BackwardFn) -> Var<'t> {
+        self.tape.push(value, vec![self.index], Some(backward))
+    }
+
+    fn binary(self, other: Var<'t>, value: Tensor, backward: BackwardFn) -> Var<'t> {
+        assert!(std::ptr::eq(self.tape, other.tape), "variables belong to different tapes");
+        self.tape.push(value, vec![self.index, other.index], Some(backward))
+    }
+
+    /// 2 次元の行列積
+    pub fn matmul(self, other: Var<'t>) -> Var<'t> {
+        let (a, b) = (self.value(), other.value());
+        let value = a.matmul(&b);
+        self.binary(other, value, Box::new(move |g| {
+            // dA = G B^T, dB = A^T G
+            vec![g.matmul(&b.transpose()), a.transpose().matmul(g)]
+        }))
+    }
+
+    /// 最後の 2 軸を入れ替える
+    pub fn transpose(self) -> Var<'t> {
+        let value = self.value().transpose().contiguous();
+        self.unary(value, Box::new(|g| vec![g.transpose().contiguous()]))
+    }
+
+    pub fn reshape(self, shape: &[usize]) -> Var<'t> {
+        let input_shape = self.shape();
+        let value = self.value().into_reshape(shape);
+        self.unary(value, Box::new(move |g| vec![g.reshape(&input_shape)]))
+    }
+
+    pub fn scale(self, factor: f32) -> Var<'t> {
+        let value = self.value().scale(factor);
+        self.unary(value, Box::new(move |g| vec![g.scale(factor)]))
+    }
+
+    pub fn add_scalar(self, c: f32) -> Var<'t> {
+        let value = self.value().add_scalar(c);
+        self.unary(value, Box::new(|g| vec![g.clone()]))
+    }
+
+    /// min(x, c)（c を超えた要素には勾配を流さない）
+    pub fn min_scalar(self, c: f32) -> Var<'t> {
+        let x = self.value();
+        let value = x.map(|v| v.min(c));
+        self.unary(value, Box::new(move |g| vec![x.zip_map(g, |v, g| if v < c { g } else { 0.0 })]))
+    }
+
+    pub fn relu(self) -> Var<'t> {
+        let x = self.value();
+        let value = x.map(|v| v.max(0.0));
+        self.unary(value, Box::new(move |g| vec![x.zip_map(g, |v, g| if v > 0.0 { g } else { 0.0 })]))
+    }
+
+    pub fn sigmoid(self) -> Var<'t> {
+        let value = self.value().map(|v| 1.0 / (1.0 + (-v).exp()));
+        let s = value.clone();
+        self.unary(value, Box::new(move |g| vec![s.zip_map(g, |s, g| g * s * (1.0 - s))]))
+    }
+
+    pub fn exp(self) -> Var<'t> {
+        let value = self.value().map(f32::exp);
+        let e = value.clone();
+        self.unary(value, Box::new(move |g| vec![g * &e]))
+    }
+
+    pub fn ln(self) -> Var<'t> {
+        let x = self.value();
+        let value = x.map(f32::ln);
+        self.unary(value, Box::new(move |g| vec![g / &x]))
+    }
+
+    /// 全要素の和（スカラー）
+    pub fn sum(self) -> Var<'t> {
+        let input_shape = self.shape();
+        let value = Tensor::scalar(self.value().sum());
+        self.unary(value, Box::new(move |g| vec![Tensor::full(&input_shape, g.as_slice()[0])]))
+    }
+
+    pub fn mean(self) -> Var<'t> {
+        let n = self.value().len().max(1) as f32;
+        self.sum().scale(1.0 / n)
+    }
+
+    /// axis に沿った和（keepdim: その軸を長さ 1 で残す）
+    pub fn sum_axis(self, axis: usize, keepdim: bool) -> Var<'t> {
+        let input_shape = self.shape();
+        let mut value = self.value().sum_axis(axis);
+        if keepdim {
+            let mut kept = input_shape.clone();
+            kept[axis] = 1;
+            value = value.into_reshape(&kept);
+        }
+        self.unary(value, Box::new(move |g| {
+            let mut kept = input_shape.clone();
+            kept[axis] = 1;
+            vec![g.reshape(&kept).broadcast_to(&input_shape)]
+        }))
+    }
+
+    /// 勾配を流さないコピー（数値安定化のためのシフト量など）
+    pub fn detach(self) -> Var<'t> {
+        self.tape.constant(self.value())
+    }
+}
+
+impl<'t> Var<'t> {
+    /// ブロードキャストを伴う二項演算。backward は (dA, dB) をブロードキャスト後の shape で返せばよい
+    fn broadcast_binary<F>(self, rhs: Var<'t>, value: Tensor, backward: F) -> Var<'t>
+    where
+        F: Fn(&Tensor) -> (Tensor, Tensor) + 'static,
+    {
+        let (shape_a, shape_b) = (self.shape(), rhs.shape());
+        self.binary(rhs, value, Box::new(move |g| {
+            let (grad_a, grad_b) = backward(g);
+            vec![reduce_to_shape(&grad_a, &shape_a), reduce_to_shape(&grad_b, &shape_b)]
+        }))
+    }
+}
+
+impl<'t> Add for Var<'t> {
+    type Output = Var<'t>;
+    fn add(self, rhs: Var<'t>) -> Var<'t> {
+        let value = &self.value() + &rhs.value();
+        self.broadcast_binary(rhs, value, |g| (g.clone(), g.clone()))
+    }
+}
+
+impl<'t> Sub for Var<'t> {
+    type Output = Var<'t>;
+    fn sub(self, rhs: Var<'t>) -> Var<'t> {
+        let value = &self.value() - &rhs.value();
+        self.broadcast_binary(rhs, value, |g| (g.clone(), g.scale(-1.0)))
+    }
+}
+
+impl<'t> Mul for Var<'t> {
+    type Output = Var<'t>;
+    fn mul(self, rhs: Var<'t>) -> Var<'t> {
+        let (a, b) = (self.value(), rhs.value());
+        let value = &a * &b;
+        self.broadcast_binary(rhs, value, move |g| (g * &b, g * &a))
+    }
+}
+
+impl<'t> Div for Var<'t> {
+    type Output = Var<'t>;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, rhs: Var<'t>) -> Var<'t> {
+        let (a, b) = (self.value(), rhs.value());
+        let value = &a / &b;
+        // d(a / b)/db = -a / b^2
+        self.broadcast_binary(rhs, value, move |g| (g / &b, (&(g * &a) / &(&b * &b)).scale(-1.0)))
+    }
+}
+
+impl<'t> Neg for Var<'t> {
+    type Output = Var<'t>;
+    fn neg(self) -> Var<'t> {
+        self.scale(-1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn autograd_ignores_constants() {
+        let tape = Tape::new();
+        let (x, k) = (tape.var(Tensor::new(vec![1.0, 2.0], vec![2])), tape.constant(Tensor::new(vec![3.0, 4.0], vec![2])));
+        let shift = x.detach();
+        let grads = tape.backward(((x * k) - shift).sum());
+        assert_eq!(grads.wrt(x).unwrap().to_vec(), vec![3.0, 4.0]);
+        assert!(grads.wrt(k).is_none());
+        assert!(grads.wrt(shift).is_none());
+    }
+}
@@ -0,0 +1,549 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::activation::ACTIVATION_TYPES;
+use crate::layers::base_layer::AbstractLayerTrait;
+use crate::layers::avg_pool2d_layer::AvgPool2dLayer;
+use crate::layers::batch_norm_layer::{BatchNormLayer, BatchNormParams};
+use crate::layers::conv2d_layer::{Conv2dLayer, Conv2dParams};
+use crate::layers::dropout_layer::DropoutLayer;
+use crate::layers::fc_layer::FcLayer;
+use crate::layers::global_avg_pool_layer::GlobalAvgPoolLayer;
+use crate::layers::layer_norm_layer::LayerNormLayer;
+use crate::layers::max_pool2d_layer::MaxPool2dLayer;
+use crate::layers::pooling::Pool2dParams;
+use crate::layers::rms_norm_layer::RmsNormLayer;
+use crate::layers::softmax_layer::SoftmaxLayer;
+
+/// チェックポイントファイルの先頭に置くマジックナンバー
+pub const CHECKPOINT_MAGIC: [u8; 4] = *b"NNRC";
+
+/// チェックポイントのフォーマットバージョン
+/// レイアウトを変えたら必ずインクリメントする
+pub const CHECKPOINT_VERSION: u32 = 2;
+
+/// オプティマイザの状態ファイルのマジックナンバーとバージョン
+pub const OPTIMIZER_STATE_MAGIC: [u8; 4] = *b"NNRO";
+pub const OPTIMIZER_STATE_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    Serialize(bincode::Error),
+    Deserialize(bincode::Error),
+    InvalidMagic([u8; 4]),
+    UnsupportedVersion { found: u32, expected: u32 },
+    InvalidLayer { index: usize, reason: String },
+    InvalidOptimizerState(String),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(e) => write!(f, "checkpoint I/O error: {}", e),
+            CheckpointError::Serialize(e) => write!(f, "failed to encode checkpoint: {}", e),
+            CheckpointError::Deserialize(e) => write!(f, "corrupt or truncated checkpoint: {}", e),
+            CheckpointError::InvalidMagic(magic) => write!(f, "not a checkpoint file (magic bytes {:?})", magic),
+            CheckpointError::UnsupportedVersion { found, expected } => {
+                write!(f, "unsupported checkpoint version {} (expected {})", found, expected)
+            }
+            CheckpointError::InvalidLayer { index, reason } => write!(f, "invalid layer #{} in checkpoint: {}", index, reason),
+            CheckpointError::InvalidOptimizerState(reason) => write!(f, "invalid optimizer state: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CheckpointError::Io(e) => Some(e),
+            CheckpointError::Serialize(e) | CheckpointError::Deserialize(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CheckpointError {
+    fn from(e: io::Error) -> Self {
+        CheckpointError::Io(e)
+    }
+}
+
+/// 1 レイヤー分の保存内容（種類・サイズ・活性化関数・パラメータ）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LayerState {
+    Fc {
+        name: String,
+        i_size: usize,
+        o_size: usize,
+        activation_type: String,
+        w: Vec<f32>,
+        b: Vec<f32>,
+    },
+    Softmax {
+        name: String,
+        i_size: usize,
+        o_size: usize,
+    },
+    Conv2d {
+        name: String,
+        in_channels: usize,
+        out_channels: usize,
+        input_height: usize,
+        input_width: usize,
+        kernel_size: (usize, usize),
+        stride: (usize, usize),
+        padding: (usize, usize),
+        dilation: (usize, usize),
+        activation_type: String,
+        w: Vec<f32>,
+        b: Vec<f32>,
+    },
+    MaxPool2d {
+        name: String,
+        channels: usize,
+        input_height: usize,
+        input_width: usize,
+        kernel_size: (usize, usize),
+        stride: (usize, usize),
+        padding: (usize, usize),
+    },
+    AvgPool2d {
+        name: String,
+        channels: usize,
+        input_height: usize,
+        input_width: usize,
+        kernel_size: (usize, usize),
+        stride: (usize, usize),
+        padding: (usize, usize),
+    },
+    GlobalAvgPool {
+        name: String,
+        channels: usize,
+        input_height: usize,
+        input_width: usize,
+    },
+    Dropout {
+        name: String,
+        size: usize,
+        p: f32,
+        seed: u64,
+        /// マスク用の乱数がどこまで進んだか（ChaCha の word 位置）
+        rng_position: u128,
+    },
+    BatchNorm {
+        name: String,
+        num_features: usize,
+        spatial_size: (usize, usize),
+        momentum: f32,
+        epsilon: f32,
+        gamma: Vec<f32>,
+        beta: Vec<f32>,
+        running_mean: Vec<f32>,
+        running_var: Vec<f32>,
+    },
+    LayerNorm {
+        name: String,
+        size: usize,
+        epsilon: f32,
+        gamma: Vec<f32>,
+        beta: Vec<f32>,
+    },
+    RmsNorm {
+        name: String,
+        size: usize,
+        epsilon: f32,
+        gamma: Vec<f32>,
+    },
+}
+
+/// 掛け算のオーバーフローを検出しながら積を求める
+fn checked_product(values: &[usize]) -> Option<usize> {
+    values.iter().try_fold(1usize, |acc, &v| acc.checked_mul(v))
+}
+
+/// 畳み込みの出力サイズ（不正な設定なら None）
+fn checked_conv_output(input: usize, kernel: usize, stride: usize, padding: usize, dilation: usize) -> Option<usize> {
+    if kernel == 0 || stride == 0 || dilation == 0 {
+        return None;
+    }
+    let effective_kernel = dilation.checked_mul(kernel - 1)?.checked_add(1)?;
+    let padded = padding.checked_mul(2)?.checked_add(input)?;
+    Some(padded.checked_sub(effective_kernel)? / stride + 1)
+}
+
+impl LayerState {
+    pub fn i_size(&self) -> usize {
+        match self {
+            LayerState::Fc { i_size, .. } | LayerState::Softmax { i_size, .. } => *i_size,
+            LayerState::Conv2d { in_channels, input_height, input_width, .. } => {
+                in_channels * input_height * input_width
+            }
+            LayerState::MaxPool2d { channels, input_height, input_width, .. }
+            | LayerState::AvgPool2d { channels, input_height, input_width, .. }
+            | LayerState::GlobalAvgPool { channels, input_height, input_width, .. } => {
+                channels * input_height * input_width
+            }
+            LayerState::Dropout { size, .. }
+            | LayerState::LayerNorm { size, .. }
+            | LayerState::RmsNorm { size, .. } => *size,
+            LayerState::BatchNorm { num_features, spatial_size, .. } => {
+                num_features * spatial_size.0 * spatial_size.1
+            }
+        }
+    }
+
+    pub fn o_size(&self) -> usize {
+        match self {
+            LayerState::Fc { o_size, .. } | LayerState::Softmax { o_size, .. } => *o_size,
+            LayerState::Conv2d { out_channels, .. } => {
+                let (out_h, out_w) = self.conv2d_params().map(|p| p.output_size()).unwrap_or((0, 0));
+                out_channels * out_h * out_w
+            }
+            LayerState::MaxPool2d { .. } | LayerState::AvgPool2d { .. } => {
+                self.pool2d_params().map(|p| p.o_size()).unwrap_or(0)
+            }
+            LayerState::GlobalAvgPool { channels, .. } => *channels,
+            LayerState::Dropout { .. }
+            | LayerState::BatchNorm { .. }
+            | LayerState::LayerNorm { .. }
+            | LayerState::RmsNorm { .. } => self.i_size(),
+        }
+    }
+
+    fn conv2d_params(&self) -> Option<Conv2dParams> {
+        match self {
+            LayerState::Conv2d {
+                in_channels, out_channels, input_height, input_width,
+                kernel_size, stride, padding, dilation, activation_type, ..
+            } => Some(
+                Conv2dParams::new(*in_channels, *out_channels, *input_height, *input_width)
+                    .kernel_size(*kernel_size)
+                    .stride(*stride)
+                    .padding(*padding)
+                    .dilation(*dilation)
+                    .activation_type(activation_type.clone())
+            ),
+            _ => None,
+        }
+    }
+
+    fn pool2d_params(&self) -> Option<Pool2dParams> {
+        match self {
+            LayerState::MaxPool2d { channels, input_height, input_width, kernel_size, stride, padding, .. }
+            | LayerState::AvgPool2d { channels, input_height, input_width, kernel_size, stride, padding, .. } => Some(
+                Pool2dParams::new(*channels, *input_height, *input_width, *kernel_size)
+                    .stride(*stride)
+                    .padding(*padding)
+            ),
+            _ => None,
+        }
+    }
+
+    /// 保存内容の整合性を検査する（index はエラーメッセージ用）
+    fn validate(&self, index: usize) -> Result<(), CheckpointError> {
+        let invalid = |reason: String| Err(CheckpointError::InvalidLayer { index, reason });
+        // 未知の名前を読み込むと黙って identity になってしまう
+        if let LayerState::Fc { activation_type, .. } | LayerState::Conv2d { activation_type, .. } = self {
+            if !ACTIVATION_TYPES.contains(&activation_type.as_str()) {
+                return invalid(format!("unknown activation type {:?}", activation_type));
+            }
+        }
+        match self {
+            LayerState::Fc { i_size, o_size, w, b, .. } => {
+                let expected_w = match i_size.checked_mul(*o_size) {
+                    Some(n) => n,
+                    None => return invalid(format!("size {}x{} overflows", i_size, o_size)),
+                };
+                if w.len() != expected_w {
+                    return invalid(format!("expected {} weights, found {}", expected_w, w.len()));
+                }
+                if b.len() != *o_size {
+                    return invalid(format!("expected {} biases, found {}", o_size, b.len()));
+                }
+            }
+            LayerState::Softmax { i_size, o_size, .. } => {
+                if i_size != o_size {
+                    return invalid(format!("softmax input size {} != output size {}", i_size, o_size));
+                }
+            }
+            LayerState::Conv2d {
+                in_channels, out_channels, input_height, input_width,
+                kernel_size, stride, padding, dilation, w, b, ..
+            } => {
+                let out_h = checked_conv_output(*input_height, kernel_size.0, stride.0, padding.0, dilation.0);
+                let out_w = checked_conv_output(*input_width, kernel_size.1, stride.1, padding.1, dilation.1);
+                let (Some(out_h), Some(out_w)) = (out_h, out_w) else {
+                    return invalid("invalid convolution geometry".to_string());
+                };
+                if checked_product(&[*in_channels, *input_height, *input_width]).is_none()
+                    || checked_product(&[*out_channels, out_h, out_w]).is_none()
+                {
+                    return invalid("feature map size overflows".to_string());
+                }
+                let expected_w = match checked_product(&[*out_channels, *in_channels, kernel_size.0, kernel_size.1]) {
+                    Some(n) => n,
+                    None => return invalid("kernel size overflows".to_string()),
+                };
+                if w.len() != expected_w {
+                    return invalid(format!("expected {} weights, found {}", expected_w, w.len()));
+                }
+                if b.len() != *out_channels {
+                    return invalid(format!("expected {} biases, found {}", out_channels, b.len()));
+                }
+            }
+            LayerState::MaxPool2d { channels, input_height, input_width, kernel_size, stride, padding, .. }
+            | LayerState::AvgPool2d { channels, input_height, input_width, kernel_size, stride, padding, .. } => {
+                let out_h = checked_conv_output(*input_height, kernel_size.0, stride.0, padding.0, 1);
+                let out_w = checked_conv_output(*input_width, kernel_size.1, stride.1, padding.1, 1);
+                let (Some(out_h), Some(out_w)) = (out_h, out_w) else {
+                    return invalid("invalid pooling geometry".to_string());
+                };
+                if padding.0 > kernel_size.0 / 2 || padding.1 > kernel_size.1 / 2 {
+                    return invalid(format!("padding {:?} exceeds half of kernel_size {:?}", padding, kernel_size));
+                }
+                if checked_product(&[*channels, *input_height, *input_width]).is_none()
+                    || checked_product(&[*channels, out_h, out_w]).is_none()
+                {
+                    return invalid("feature map size overflows".to_string());
+                }
+            }
+            LayerState::GlobalAvgPool { channels, input_height, input_width, .. } => {
+                if *input_height == 0 || *input_width == 0 {
+                    return invalid("empty feature map".to_string());
+                }
+                if checked_product(&[*channels, *input_height, *input_width]).is_none() {
+                    return invalid("feature map size overflows".to_string());
+                }
+            }
+            LayerState::Dropout { p, .. } => {
+                if !(0.0..1.0).contains(p) {
+                    return invalid(format!("dropout probability {} is not in [0, 1)", p));
+                }
+            }
+            LayerState::BatchNorm {
+                num_features, spatial_size, momentum, epsilon,
+                gamma, beta, running_mean, running_var, ..
+            } => {
+                if *num_features == 0 || spatial_size.0 == 0 || spatial_size.1 == 0 {
+                    return invalid("empty batch norm layer".to_string());
+                }
+                if checked_product(&[*num_features, spatial_size.0, spatial_size.1]).is_none() {
+                    return invalid("feature map size overflows".to_string());
+                }
+                if !(0.0..=1.0).contains(momentum) || epsilon.is_nan() || *epsilon <= 0.0 {
+                    return invalid(format!("invalid momentum {} or epsilon {}", momentum, epsilon));
+                }
+                for (label, values) in [("gamma", gamma), ("beta", beta), ("running_mean", running_mean), ("running_var", running_var)] {
+                    if values.len() != *num_features {
+                        return invalid(format!("expected {} values for {}, found {}", num_features, label, values.len()));
+                    }
+                }
+            }
+            LayerState::LayerNorm { size, epsilon, gamma, beta, .. } => {
+                if *size == 0 || epsilon.is_nan() || *epsilon <= 0.0 {
+                    return invalid(format!("invalid size {} or epsilon {}", size, epsilon));
+                }
+                if gamma.len() != *size || beta.len() != *size {
+                    return invalid(format!("expected {} values for gamma and beta, found {} and {}", size, gamma.len(), beta.len()));
+                }
+            }
+            LayerState::RmsNorm { size, epsilon, gamma, .. } => {
+                if *size == 0 || epsilon.is_nan() || *epsilon <= 0.0 {
+                    return invalid(format!("invalid size {} or epsilon {}", size, epsilon));
+                }
+                if gamma.len() != *size {
+                    return invalid(format!("expected {} values for gamma, found {}", size, gamma.len()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 保存内容からレイヤーを復元する（build() は不要）
+    pub fn into_layer(self) -> Box<dyn AbstractLayerTrait> {
+        match self {
+            LayerState::Fc { name, i_size, o_size, activation_type, w, b } => {
+                Box::new(FcLayer::from_parameters(name, i_size, o_size, activation_type, w, b))
+            }
+            LayerState::Softmax { name, i_size, o_size } => {
+                Box::new(SoftmaxLayer::new(name, i_size, o_size))
+            }
+            LayerState::Conv2d { .. } => {
+                let params = self.conv2d_params().unwrap();
+                let LayerState::Conv2d { name, w, b, .. } = self else { unreachable!() };
+                Box::new(Conv2dLayer::from_parameters(name, params, w, b))
+            }
+            LayerState::MaxPool2d { .. } => {
+                let params = self.pool2d_params().unwrap();
+                let LayerState::MaxPool2d { name, .. } = self else { unreachable!() };
+                Box::new(MaxPool2dLayer::new(name, params))
+            }
+            LayerState::AvgPool2d { .. } => {
+                let params = self.pool2d_params().unwrap();
+                let LayerState::AvgPool2d { name, .. } = self else { unreachable!() };
+                Box::new(AvgPool2dLayer::new(name, params))
+            }
+            LayerState::GlobalAvgPool { name, channels, input_height, input_width } => {
+                Box::new(GlobalAvgPoolLayer::new(name, channels, input_height, input_width))
+            }
+            LayerState::Dropout { name, size, p, seed, rng_position } => {
+                Box::new(DropoutLayer::new(name, size, p, seed).rng_position(rng_position))
+            }
+            LayerState::BatchNorm {
+                name, num_features, spatial_size, momentum, epsilon,
+                gamma, beta, running_mean, running_var,
+            } => {
+                let params = BatchNormParams::new(num_features)
+                    .spatial_size(spatial_size)
+                    .momentum(momentum)
+                    .epsilon(epsilon);
+                Box::new(BatchNormLayer::from_parameters(name, params, gamma, beta, running_mean, running_var))
+            }
+            LayerState::LayerNorm { name, size, epsilon, gamma, beta } => {
+                Box::new(LayerNormLayer::from_parameters(name, size, epsilon, gamma, beta))
+            }
+            LayerState::RmsNorm { name, size, epsilon, gamma } => {
+                Box::new(RmsNormLayer::from_parameters(name, size, epsilon, gamma))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelCheckpoint {
+    pub layers: Vec<LayerState>,
+}
+
+impl ModelCheckpoint {
+    /// 各レイヤーの整合性と、隣接するレイヤーのサイズが繋がっているかを検査する
+    pub fn validate(&self) -> Result<(), CheckpointError> {
+        for (index, layer) in self.layers.iter().enumerate() {
+            layer.validate(index)?;
+            if index > 0 && self.layers[index - 1].o_size() != layer.i_size() {
+                return Err(CheckpointError::InvalidLayer {
+                    index,
+                    reason: format!(
+                        "input size {} does not match previous output size {}",
+                        layer.i_size(),
+                        self.layers[index - 1].o_size()
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn bincode_options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .with_little_endian()
+}
+
+/// ファイル構成: [magic (4 bytes)] [version (u32 LE)] [bincode payload]
+fn write_file<P: AsRef<Path>, T: Serialize>(path: P, magic: [u8; 4], version: u32, value: &T) -> Result<(), CheckpointError> {
+    let payload = bincode_options()
+        .serialize(value)
+        .map_err(CheckpointError::Serialize)?;
+
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(&magic)?;
+    file.write_all(&version.to_le_bytes())?;
+    file.write_all(&payload)?;
+    file.flush()?;
+    Ok(())
+}
+
+fn read_file<P: AsRef<Path>, T: DeserializeOwned>(path: P, expected_magic: [u8; 4], expected_version: u32) -> Result<T, CheckpointError> {
+    let mut file = BufReader::new(File::open(path)?);
+
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic)?;
+    if magic != expected_magic {
+        return Err(CheckpointError::InvalidMagic(magic));
+    }
+
+    let mut version = [0u8; 4];
+    file.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != expected_version {
+        return Err(CheckpointError::UnsupportedVersion { found: version, expected: expected_version });
+    }
+
+    let mut payload = Vec::new();
+    file.read_to_end(&mut payload)?;
+
+    // 壊れた長さフィールドで巨大な確保をしないよう、ペイロード長を上限にする
+    bincode_options()
+        .with_limit(payload.len() as u64)
+        .reject_trailing_bytes()
+        .deserialize(&payload)
+        .map_err(CheckpointError::Deserialize)
+}
+
+pub fn write_checkpoint<P: AsRef<Path>>(path: P, checkpoint: &ModelCheckpoint) -> Result<(), CheckpointError> {
+    write_file(path, CHECKPOINT_MAGIC, CHECKPOINT_VERSION, checkpoint)
+}
+
+pub fn read_checkpoint<P: AsRef<Path>>(path: P) -> Result<ModelCheckpoint, CheckpointError> {
+    let checkpoint: ModelCheckpoint = read_file(path, CHECKPOINT_MAGIC, CHECKPOINT_VERSION)?;
+    checkpoint.validate()?;
+    Ok(checkpoint)
+}
+
+/// どのパラメータのバッファか（レイヤーの番号と w / b）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ParamKey {
+    Weight(usize),
+    Bias(usize),
+}
+
+/// 1 パラメータ分のバッファ（Adam なら [m, v] の 2 つ）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParamBufferState {
+    pub key: ParamKey,
+    pub shape: Vec<usize>,
+    pub slots: Vec<Vec<f32>>,
+}
+
+/// オプティマイザの保存内容（種類・ステップ数・パラメータごとのバッファ）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptimizerState {
+    pub kind: String,
+    pub step: u64,
+    pub buffers: Vec<ParamBufferState>,
+}
+
+impl OptimizerState {
+    /// バッファの長さが shape と一致しているかを検査する
+    pub fn validate(&self) -> Result<(), CheckpointError> {
+        for buffer in &self.buffers {
+            let Some(numel) = checked_product(&buffer.shape) else {
+                return Err(CheckpointError::InvalidOptimizerState(format!("shape {:?} of {:?} overflows", buffer.shape, buffer.key)));
+            };
+            if let Some(slot) = buffer.slots.iter().find(|slot| slot.len() != numel) {
+                return Err(CheckpointError::InvalidOptimizerState(format!(
+                    "buffer of {:?} has {} values, expected {}", buffer.key, slot.len(), numel
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn write_optimizer_state<P: AsRef<Path>>(path: P, state: &OptimizerState) -> Result<(), CheckpointError> {
+    write_file(path, OPTIMIZER_STATE_MAGIC, OPTIMIZER_STATE_VERSION, state)
+}
+
+pub fn read_optimizer_state<P: AsRef<Path>>(path: P) -> Result<OptimizerState, CheckpointError> {
+    let state: OptimizerState = read_file(path, OPTIMIZER_STATE_MAGIC, OPTIMIZER_STATE_VERSION)?;
+    state.validate()?;
+    Ok(state)
+}
@@ -2,22 +2,133 @@
 use std::fs::File;
 use std::io::{BufReader, Read};
 
+use crate::tensor::Tensor;
+
+mod csv;
+mod dataset;
+mod idx;
+mod loader;
+mod mmap;
+mod nnrb;
+mod npy;
+mod split;
+mod streaming;
+
+pub use self::csv::{CsvColumn, CsvOptions};
+pub use self::dataset::{Dataset, Sample, Target};
+pub use self::loader::{Batch, Batches, DataLoader, Sampling};
+pub use self::mmap::MmapDataset;
+pub use self::split::{KFold, SplitOptions};
+pub use self::streaming::StreamingDataset;
+
+/// 読み込んだファイルの中身がおかしいときのエラー
+pub(crate) fn invalid_data(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// サンプルごとの正解
+#[derive(Debug, Clone, PartialEq)]
+pub enum Targets {
+    /// クラス番号（モデルの出力は [num_samples, num_classes] のスコア）
+    Classes(Vec<u32>),
+    /// 回帰などの実数ベクトル [num_samples, dim]
+    Dense(Tensor),
+    /// 多ラベル分類の 0 / 1 ベクトル [num_samples, num_labels]
+    MultiHot(Tensor),
+}
+
+impl Targets {
+    pub fn len(&self) -> usize {
+        match self {
+            Targets::Classes(classes) => classes.len(),
+            Targets::Dense(values) | Targets::MultiHot(values) => values.dim(0),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// indices の順に取り出す
+    pub fn select(&self, indices: &[usize]) -> Targets {
+        match self {
+            Targets::Classes(classes) => Targets::Classes(indices.iter().map(|&i| classes[i]).collect()),
+            Targets::Dense(values) => Targets::Dense(values.select_rows(indices)),
+            Targets::MultiHot(values) => Targets::MultiHot(values.select_rows(indices)),
+        }
+    }
+
+    /// start..end のサンプルを取り出す
+    pub fn slice(&self, start: usize, end: usize) -> Targets {
+        match self {
+            Targets::Classes(classes) => Targets::Classes(classes[start..end].to_vec()),
+            Targets::Dense(values) => Targets::Dense(values.slice_rows(start, end)),
+            Targets::MultiHot(values) => Targets::MultiHot(values.slice_rows(start, end)),
+        }
+    }
+
+    /// 損失関数に渡す [num_samples, num_outputs] のテンソル（クラス番号は one-hot にする）
+    pub fn to_tensor(&self, num_outputs: usize) -> Tensor {
+        match self {
+            Targets::Classes(classes) => {
+                let mut one_hot = Tensor::zeros(&[classes.len(), num_outputs]);
+                let data = one_hot.as_mut_slice();
+                for (row, &class) in classes.iter().enumerate() {
+                    assert!((class as usize) < num_outputs, "class {} is out of range for {} outputs", class, num_outputs);
+                    data[row * num_outputs + class as usize] = 1.0;
+                }
+                one_hot
+            }
+            Targets::Dense(values) | Targets::MultiHot(values) => {
+                let values = values.flatten_batch();
+                assert_eq!(values.dim(1), num_outputs, "targets have {} values per sample, but the model outputs {}", values.dim(1), num_outputs);
+                values
+            }
+        }
+    }
+
+    /// index 番目のクラス番号（クラス番号以外の正解なら None）
+    pub fn class(&self, index: usize) -> Option<u32> {
+        match self {
+            Targets::Classes(classes) => classes.get(index).copied(),
+            Targets::Dense(_) | Targets::MultiHot(_) => None,
+        }
+    }
+}
+
 pub struct DataSet {
-    pub images: Vec<f32>,  // フラットな配列
-    pub labels: Vec<u8>,
-    pub num_samples: usize,
-    pub num_features: usize,
+    pub images: Tensor,  // [num_samples, num_features]
+    pub targets: Targets,
+    /// クラス番号ごとの名前（なければ空）
+    pub class_names: Vec<String>,
 }
 
 impl DataSet {
 
-    pub fn new(images: Vec<f32>, labels: Vec<u8>, num_features: usize) -> Self {
-        let num_samples = images.len() / num_features as usize;
-        Self { images, labels, num_samples, num_features }
+    pub fn new(images: Tensor, targets: Targets) -> Self {
+        assert_eq!(images.dim(0), targets.len(), "number of images and targets must match");
+        Self { images, targets, class_names: Vec::new() }
+    }
+
+    pub fn with_class_names(mut self, class_names: Vec<String>) -> Self {
+        self.class_names = class_names;
+        self
     }
 
+    pub fn num_samples(&self) -> usize {
+        self.images.dim(0)
+    }
+
+    /// 1 サンプルあたりの要素数（先頭の軸以外の積）
+    pub fn num_features(&self) -> usize {
+        self.images.shape()[1..].iter().product()
+    }
+
+    /// load_mnist.py が書き出す旧形式を読み込む（新しく保存するなら save_binary / load_binary を使う）
     pub fn load_from_binary(filename: &str) -> std::io::Result<Self> {
-        let mut file = BufReader::new(File::open(filename)?);
+        let file = File::open(filename)?;
+        let file_len = file.metadata()?.len();
+        let mut file = BufReader::new(file);
         
         // ヘッダー読み込み: [num_samples (u64), num_features (u64)]
         let mut header = [0u8; 16];
@@ -33,50 +144,62 @@ impl DataSet {
             header[12], header[13], header[14], header[15]
         ]) as usize;
         
+        // 確保する前に、ヘッダーの大きさとファイルの長さが合っているか確かめる
+        let image_size = num_samples.checked_mul(num_features).and_then(|n| n.checked_mul(4));
+        let expected_len = image_size.and_then(|n| n.checked_add(num_samples)).and_then(|n| n.checked_add(16));
+        if expected_len.map(|n| n as u64) != Some(file_len) {
+            return Err(invalid_data(format!(
+                "{}: header says {} samples x {} features, which does not match the file size of {} bytes",
+                filename, num_samples, num_features, file_len
+            )));
+        }
+        let image_size = image_size.unwrap();
+
         // 画像データ読み込み (f32 = 4 bytes)
-        let image_size = num_samples * num_features * 4;
-        let mut image_bytes = vec![0u8; image_size];
-        file.read_exact(&mut image_bytes)?;
-        
-        let images: Vec<f32> = image_bytes
-            .chunks_exact(4)
-            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-            .collect();
+        // 少しずつ読んで変換するので、バイト列と Vec<f32> を同時に全部持つことはない
+        let mut images: Vec<f32> = Vec::with_capacity(image_size / 4);
+        let mut chunk = vec![0u8; 256 << 10];
+        let mut left = image_size;
+        while left > 0 {
+            let n = left.min(chunk.len());
+            file.read_exact(&mut chunk[..n])?;
+            images.extend(chunk[..n].chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])));
+            left -= n;
+        }
         
         // ラベル読み込み
         let mut labels = vec![0u8; num_samples];
         file.read_exact(&mut labels)?;
         
-        Ok(DataSet {
-            images,
-            labels,
-            num_samples,
-            num_features,
-        })
+        let targets = Targets::Classes(labels.into_iter().map(u32::from).collect());
+        Ok(DataSet::new(Tensor::new(images, vec![num_samples, num_features]), targets))
     }
     
     pub fn get_image(&self, index: usize) -> Option<&[f32]> {
-        if index < self.num_samples {
-            let start = index * self.num_features;
-            let end = start + self.num_features;
-            Some(&self.images[start..end])
+        if index < self.num_samples() {
+            Some(self.images.row(index))
         } else {
             None
         }
     }
     
-    pub fn get_label(&self, index: usize) -> Option<u8> {
-        self.labels.get(index).copied()
+    /// index 番目のクラス番号（正解がクラス番号でなければ None）
+    pub fn get_label(&self, index: usize) -> Option<u32> {
+        self.targets.class(index)
     }
 
+    /// 先頭の round(num_samples * ratio) 個と残りに分ける（ratio は 0 以上 1 以下）
+    ///
+    /// ファイルの順のまま分けるので、並べ替えたいときやクラスの割合をそろえたいときは split_with を使う。
     pub fn split_dataset(&self, ratio: f32) -> (DataSet, DataSet) {
-        let num_samples = self.num_samples;
-        let num_train_samples = (num_samples as f32 * ratio) as usize;
-        let (train_images, test_images) = self.images.split_at(num_train_samples * self.num_features);
-        let (train_labels, test_labels) = self.labels.split_at(num_train_samples);
+        assert!((0.0..=1.0).contains(&ratio), "split ratio must be in [0, 1], got {}", ratio);
+        let num_samples = self.num_samples();
+        let num_train_samples = ((num_samples as f64 * ratio as f64).round() as usize).min(num_samples);
         (
-            DataSet::new(train_images.to_vec(), train_labels.to_vec(), self.num_features), 
-            DataSet::new(test_images.to_vec(), test_labels.to_vec(), self.num_features)
+            DataSet::new(self.images.slice_rows(0, num_train_samples), self.targets.slice(0, num_train_samples))
+                .with_class_names(self.class_names.clone()),
+            DataSet::new(self.images.slice_rows(num_train_samples, num_samples), self.targets.slice(num_train_samples, num_samples))
+                .with_class_names(self.class_names.clone()),
         )
     }
     
@@ -85,7 +208,7 @@ impl DataSet {
     /// # Arguments
     /// * `image` - 28x28の画像データ（784要素の配列）
     /// * `label` - オプションのラベル（表示される数字）
-    pub fn display_ascii_art(image: &[f32], label: Option<u8>) {
+    pub fn display_ascii_art(image: &[f32], label: Option<u32>) {
         const WIDTH: usize = 28;
         const HEIGHT: usize = 28;
         
@@ -133,4 +256,30 @@ impl DataSet {
             println!("Invalid index: {}", index);
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// 一時ファイルのパス（各データ形式のテストで使う）
+    pub(super) fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("nn_rust_{}_{}", std::process::id(), name))
+    }
+
+    /// 特徴量 = index の 1 次元データ
+    pub(super) fn indexed_dataset(classes: Vec<u32>) -> DataSet {
+        let n = classes.len();
+        DataSet::new(Tensor::new((0..n).map(|i| i as f32).collect(), vec![n, 1]), Targets::Classes(classes))
+    }
+
+    #[test]
+    fn split_dataset_rounds_and_keeps_class_names() {
+        let dataset = DataSet::new(Tensor::zeros(&[3, 1]), Targets::Classes(vec![0, 1, 2]))
+            .with_class_names(vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+        let (train, test) = dataset.split_dataset(0.7);
+        assert_eq!((train.num_samples(), test.num_samples()), (2, 1));
+        assert_eq!(test.class_names, dataset.class_names);
+    }
+}
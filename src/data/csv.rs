@@ -0,0 +1,237 @@
+// src/data/csv.rs
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::data::{invalid_data, DataSet, Targets};
+use crate::tensor::Tensor;
+
+/// ラベルの列の指定
+#[derive(Debug, Clone, PartialEq)]
+pub enum CsvColumn {
+    /// 0 から数えた列番号
+    Index(usize),
+    /// ヘッダーの列名（has_header が必要）
+    Name(String),
+    /// 最後の列
+    Last,
+}
+
+/// DataSet::from_csv の設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    pub has_header: bool,
+    pub label_column: CsvColumn,
+    pub delimiter: char,
+    /// ラベルを実数（Targets::Dense）として読む。false ならクラス番号（Targets::Classes）
+    pub dense_labels: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self { has_header: true, label_column: CsvColumn::Last, delimiter: ',', dense_labels: false }
+    }
+}
+
+impl CsvOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 先頭行を列名として読み飛ばす（既定値は true）
+    pub fn has_header(mut self, has_header: bool) -> Self {
+        self.has_header = has_header;
+        self
+    }
+
+    /// 既定値は最後の列
+    pub fn label_column(mut self, label_column: CsvColumn) -> Self {
+        self.label_column = label_column;
+        self
+    }
+
+    /// 既定値は ','（TSV なら '\t'）
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn dense_labels(mut self, dense_labels: bool) -> Self {
+        self.dense_labels = dense_labels;
+        self
+    }
+}
+
+/// 1 行を delimiter で区切る（"..." で囲んだフィールドと "" のエスケープに対応）
+fn split_record(line: &str, delimiter: char) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut in_quotes = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                in_quotes = true;
+            }
+            c if c == delimiter && !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("unterminated quoted field".to_string());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+impl DataSet {
+    /// CSV を読み込む（ラベル以外の列を特徴量 [num_samples, num_columns - 1] にする）
+    ///
+    /// 空行は読み飛ばす。値が数値でない、列の数が揃っていない、ラベルが負や小数、のときは
+    /// 行番号と列番号を含む `ErrorKind::InvalidData` を返す。
+    pub fn from_csv<P: AsRef<Path>>(path: P, options: &CsvOptions) -> io::Result<Self> {
+        let path = path.as_ref();
+        let reader = BufReader::new(File::open(path)?);
+        let error = |line_no: usize, message: String| invalid_data(format!("{}:{}: {}", path.display(), line_no, message));
+
+        let mut features = Vec::new();
+        let mut dense_labels = Vec::new();
+        let mut classes = Vec::new();
+        let mut num_columns: Option<usize> = None;
+        let mut label_index: Option<usize> = None;
+        let mut header_pending = options.has_header;
+
+        for (line_index, line) in reader.lines().enumerate() {
+            let line_no = line_index + 1;
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields = split_record(line, options.delimiter).map_err(|e| error(line_no, e))?;
+
+            match num_columns {
+                None => {
+                    if fields.len() < 2 {
+                        return Err(error(line_no, format!("expected at least 2 columns (features and a label), found {}", fields.len())));
+                    }
+                    num_columns = Some(fields.len());
+                }
+                Some(n) if n != fields.len() => {
+                    return Err(error(line_no, format!("expected {} columns, found {}", n, fields.len())));
+                }
+                Some(_) => {}
+            }
+            let num_columns = fields.len();
+
+            if label_index.is_none() {
+                let index = match &options.label_column {
+                    CsvColumn::Index(index) => *index,
+                    CsvColumn::Last => num_columns - 1,
+                    CsvColumn::Name(name) => {
+                        if !header_pending {
+                            return Err(error(line_no, format!("label column {:?} is given by name, but the file has no header", name)));
+                        }
+                        fields.iter().position(|f| f.trim() == name)
+                            .ok_or_else(|| error(line_no, format!("no column named {:?} in the header", name)))?
+                    }
+                };
+                if index >= num_columns {
+                    return Err(error(line_no, format!("label column {} is out of range for {} columns", index, num_columns)));
+                }
+                label_index = Some(index);
+            }
+            if header_pending {
+                header_pending = false;
+                continue;
+            }
+            let label_index = label_index.unwrap();
+
+            for (column, field) in fields.iter().enumerate() {
+                let field = field.trim();
+                let value: f32 = field.parse()
+                    .map_err(|_| error(line_no, format!("column {}: {:?} is not a number", column + 1, field)))?;
+                if column != label_index {
+                    features.push(value);
+                } else if options.dense_labels {
+                    dense_labels.push(value);
+                } else {
+                    let class: u32 = field.parse()
+                        .map_err(|_| error(line_no, format!("column {}: label {:?} is not a class index (non-negative integer)", column + 1, field)))?;
+                    classes.push(class);
+                }
+            }
+        }
+
+        let Some(num_columns) = num_columns else {
+            return Err(invalid_data(format!("{}: no data", path.display())));
+        };
+        let num_features = num_columns - 1;
+        let num_samples = features.len() / num_features;
+        let targets = if options.dense_labels {
+            Targets::Dense(Tensor::new(dense_labels, vec![num_samples, 1]))
+        } else {
+            Targets::Classes(classes)
+        };
+        Ok(DataSet::new(Tensor::new(features, vec![num_samples, num_features]), targets))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::tests::temp_path;
+
+    #[test]
+    fn from_csv_with_header_and_named_label() {
+        let path = temp_path("named.csv");
+        std::fs::write(&path, "x1,label,\"x 2\"\n1.5,2,-3\n\n0,0,\"4e-1\"\r\n").unwrap();
+        let options = CsvOptions::new().label_column(CsvColumn::Name("label".to_string()));
+        let dataset = DataSet::from_csv(&path, &options);
+        std::fs::remove_file(&path).unwrap();
+
+        let dataset = dataset.unwrap();
+        assert_eq!(dataset.images, Tensor::new(vec![1.5, -3.0, 0.0, 0.4], vec![2, 2]));
+        assert_eq!(dataset.targets, Targets::Classes(vec![2, 0]));
+    }
+
+    #[test]
+    fn from_csv_without_header_dense_labels() {
+        let path = temp_path("dense.tsv");
+        std::fs::write(&path, "0.5\t1\t2\n-0.25\t3\t4\n").unwrap();
+        let options = CsvOptions::new().has_header(false).label_column(CsvColumn::Index(0)).delimiter('\t').dense_labels(true);
+        let dataset = DataSet::from_csv(&path, &options);
+        std::fs::remove_file(&path).unwrap();
+
+        let dataset = dataset.unwrap();
+        assert_eq!(dataset.images, Tensor::new(vec![1.0, 2.0, 3.0, 4.0], vec![2, 2]));
+        assert_eq!(dataset.targets, Targets::Dense(Tensor::new(vec![0.5, -0.25], vec![2, 1])));
+    }
+
+    #[test]
+    fn from_csv_reports_location_of_errors() {
+        let cases = [
+            ("a,b\n1,2\n3\n", CsvOptions::new(), "3: expected 2 columns, found 1"),
+            ("a,b\n1,x\n", CsvOptions::new().label_column(CsvColumn::Index(0)), "2: column 2: \"x\" is not a number"),
+            ("a,b\n1,2.5\n", CsvOptions::new(), "label \"2.5\" is not a class index"),
+            ("a,b\n1,2\n", CsvOptions::new().label_column(CsvColumn::Name("y".to_string())), "no column named \"y\""),
+            ("a,b\n1,\"2\n", CsvOptions::new(), "2: unterminated quoted field"),
+            ("a,b\n", CsvOptions::new().label_column(CsvColumn::Index(5)), "label column 5 is out of range"),
+        ];
+        for (i, (contents, options, expected)) in cases.into_iter().enumerate() {
+            let path = temp_path(&format!("bad{}.csv", i));
+            std::fs::write(&path, contents).unwrap();
+            let result = DataSet::from_csv(&path, &options);
+            std::fs::remove_file(&path).unwrap();
+            let error = result.err().unwrap_or_else(|| panic!("{:?} should fail", contents));
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+            assert!(error.to_string().contains(expected), "{:?}: {}", contents, error);
+        }
+    }
+}
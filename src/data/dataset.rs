@@ -0,0 +1,142 @@
+// src/data/dataset.rs
+use crate::data::{DataSet, Targets};
+use crate::tensor::Tensor;
+
+/// 1 サンプル分の正解
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Class(u32),
+    Dense(Vec<f32>),
+    MultiHot(Vec<f32>),
+}
+
+/// 1 サンプル分の特徴量と正解
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub features: Vec<f32>,
+    pub target: Target,
+}
+
+impl Targets {
+    /// index 番目の正解
+    pub fn get(&self, index: usize) -> Target {
+        match self {
+            Targets::Classes(classes) => Target::Class(classes[index]),
+            Targets::Dense(values) => Target::Dense(values.row(index).to_vec()),
+            Targets::MultiHot(values) => Target::MultiHot(values.row(index).to_vec()),
+        }
+    }
+
+    /// サンプルごとの正解をまとめる（種類と長さは揃っていなければならない）
+    pub fn from_samples(targets: Vec<Target>) -> Targets {
+        let num_samples = targets.len();
+        let mut classes = Vec::new();
+        let mut values = Vec::new();
+        let mut dim = None;
+        let mut multi_hot = None;
+        for target in targets {
+            let (row, is_multi_hot) = match target {
+                Target::Class(class) => {
+                    classes.push(class);
+                    continue;
+                }
+                Target::Dense(row) => (row, false),
+                Target::MultiHot(row) => (row, true),
+            };
+            assert_eq!(*dim.get_or_insert(row.len()), row.len(), "targets must all have the same length");
+            assert_eq!(*multi_hot.get_or_insert(is_multi_hot), is_multi_hot, "cannot mix dense and multi-hot targets");
+            values.extend(row);
+        }
+        match (multi_hot, dim) {
+            (None, _) => Targets::Classes(classes),
+            (Some(is_multi_hot), Some(dim)) => {
+                assert!(classes.is_empty(), "cannot mix class and vector targets");
+                let values = Tensor::new(values, vec![num_samples, dim]);
+                if is_multi_hot { Targets::MultiHot(values) } else { Targets::Dense(values) }
+            }
+            (Some(_), None) => unreachable!(),
+        }
+    }
+}
+
+/// index で 1 サンプルずつ取り出せるデータセット
+///
+/// Trainer はこの trait を通してデータを読む。DataSet（メモリ上）、MmapDataset（ファイルを mmap）、
+/// StreamingDataset（イテレータから順に読む）が実装している。
+pub trait Dataset {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 1 サンプルあたりの特徴量の形（[C, H, W] や [num_features]）
+    fn feature_shape(&self) -> Vec<usize>;
+
+    /// index 番目のサンプル（index >= len() なら panic）
+    fn get(&self, index: usize) -> Sample;
+
+    /// index 番目の正解だけ（層化抽出などで使う。特徴量を読まずに済むなら上書きする）
+    fn target(&self, index: usize) -> Target {
+        self.get(index).target
+    }
+
+    /// indices のサンプルをまとめて ([batch, feature_shape...], 正解) にする
+    fn batch(&self, indices: &[usize]) -> (Tensor, Targets) {
+        let mut shape = vec![indices.len()];
+        shape.extend(self.feature_shape());
+        let mut features = Vec::with_capacity(shape.iter().product());
+        let mut targets = Vec::with_capacity(indices.len());
+        for &index in indices {
+            let sample = self.get(index);
+            features.extend(sample.features);
+            targets.push(sample.target);
+        }
+        (Tensor::new(features, shape), Targets::from_samples(targets))
+    }
+}
+
+impl Dataset for DataSet {
+    fn len(&self) -> usize {
+        self.num_samples()
+    }
+
+    fn feature_shape(&self) -> Vec<usize> {
+        self.images.shape()[1..].to_vec()
+    }
+
+    fn get(&self, index: usize) -> Sample {
+        assert!(index < self.num_samples(), "index {} is out of range for {} samples", index, self.num_samples());
+        Sample { features: self.images.row(index).to_vec(), target: self.targets.get(index) }
+    }
+
+    fn target(&self, index: usize) -> Target {
+        self.targets.get(index)
+    }
+
+    fn batch(&self, indices: &[usize]) -> (Tensor, Targets) {
+        (self.images.select_rows(indices), self.targets.select(indices))
+    }
+}
+
+impl<D: Dataset + ?Sized> Dataset for Box<D> {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn feature_shape(&self) -> Vec<usize> {
+        (**self).feature_shape()
+    }
+
+    fn get(&self, index: usize) -> Sample {
+        (**self).get(index)
+    }
+
+    fn target(&self, index: usize) -> Target {
+        (**self).target(index)
+    }
+
+    fn batch(&self, indices: &[usize]) -> (Tensor, Targets) {
+        (**self).batch(indices)
+    }
+}
@@ -0,0 +1,164 @@
+// src/data/idx.rs
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+
+use crate::data::{invalid_data, DataSet, Targets};
+use crate::tensor::Tensor;
+
+/// gzip の先頭 2 バイト
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// IDX のデータ型コード（unsigned byte）
+const IDX_TYPE_U8: u8 = 0x08;
+
+/// ファイルを開く（gzip なら展開しながら読む。拡張子ではなく先頭のバイトで判定する）
+pub(crate) fn open_maybe_gzip(path: &Path) -> io::Result<Box<dyn Read>> {
+    let mut file = BufReader::new(File::open(path)?);
+    let mut magic = [0u8; 2];
+    let is_gzip = match file.read_exact(&mut magic) {
+        Ok(()) => magic == GZIP_MAGIC,
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => false,
+        Err(e) => return Err(e),
+    };
+    // 読んだ 2 バイトを戻すために開き直す
+    let file = BufReader::new(File::open(path)?);
+    Ok(if is_gzip { Box::new(GzDecoder::new(file)) } else { Box::new(file) })
+}
+
+/// IDX ファイルを読み込んで (次元, u8 のデータ) を返す
+///
+/// ヘッダー: [0x00, 0x00, 型, 次元数] の後に各次元の大きさ（u32, big-endian）、その後にデータが続く。
+fn read_idx(path: &Path) -> io::Result<(Vec<usize>, Vec<u8>)> {
+    let mut reader = open_maybe_gzip(path)?;
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic[0] != 0 || magic[1] != 0 {
+        return Err(invalid_data(format!("{}: not an IDX file (magic {:02x?})", path.display(), magic)));
+    }
+    if magic[2] != IDX_TYPE_U8 {
+        return Err(invalid_data(format!("{}: unsupported IDX data type 0x{:02x} (only u8 is supported)", path.display(), magic[2])));
+    }
+
+    let mut dims = Vec::with_capacity(magic[3] as usize);
+    for _ in 0..magic[3] {
+        let mut dim = [0u8; 4];
+        reader.read_exact(&mut dim)?;
+        dims.push(u32::from_be_bytes(dim) as usize);
+    }
+    let len = dims.iter().try_fold(1usize, |acc, &d| acc.checked_mul(d))
+        .ok_or_else(|| invalid_data(format!("{}: IDX dimensions {:?} overflow", path.display(), dims)))?;
+
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    if data.len() != len {
+        return Err(invalid_data(format!(
+            "{}: expected {} bytes of data for dimensions {:?}, found {}", path.display(), len, dims, data.len()
+        )));
+    }
+    Ok((dims, data))
+}
+
+impl DataSet {
+    /// IDX 形式（MNIST / Fashion-MNIST / EMNIST / KMNIST）の画像とラベルを読み込む
+    ///
+    /// gzip 圧縮されたファイル（`*.gz`）もそのまま読める。
+    /// 画像は [num_samples, rows * cols] の f32 にして 255 で割る（load_mnist.py と同じ正規化）。
+    pub fn load_idx<P: AsRef<Path>, Q: AsRef<Path>>(images_path: P, labels_path: Q) -> io::Result<Self> {
+        let (images_path, labels_path) = (images_path.as_ref(), labels_path.as_ref());
+        let (image_dims, pixels) = read_idx(images_path)?;
+        let (label_dims, labels) = read_idx(labels_path)?;
+
+        let Some((&num_samples, sample_dims)) = image_dims.split_first() else {
+            return Err(invalid_data(format!("{}: images must have at least one dimension", images_path.display())));
+        };
+        if label_dims.len() != 1 {
+            return Err(invalid_data(format!("{}: labels must be one-dimensional, got {:?}", labels_path.display(), label_dims)));
+        }
+        if label_dims[0] != num_samples {
+            return Err(invalid_data(format!(
+                "{} has {} images but {} has {} labels", images_path.display(), num_samples, labels_path.display(), label_dims[0]
+            )));
+        }
+
+        let num_features = sample_dims.iter().product();
+        let images: Vec<f32> = pixels.into_iter().map(|p| p as f32 / 255.0).collect();
+        let targets = Targets::Classes(labels.into_iter().map(u32::from).collect());
+        Ok(DataSet::new(Tensor::new(images, vec![num_samples, num_features]), targets))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    use crate::data::tests::temp_path;
+
+    /// u8 の IDX ファイルの中身
+    fn idx_bytes(dims: &[u32], data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0, 0, 0x08, dims.len() as u8];
+        for dim in dims {
+            bytes.extend_from_slice(&dim.to_be_bytes());
+        }
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn load_idx_plain_and_gzip() {
+        let pixels: Vec<u8> = (0..12).map(|i| i * 20).collect();
+        let images = idx_bytes(&[3, 2, 2], &pixels);
+        let labels = idx_bytes(&[3], &[7, 0, 9]);
+
+        let (images_path, labels_path) = (temp_path("images-idx3-ubyte"), temp_path("labels-idx1-ubyte.gz"));
+        std::fs::write(&images_path, &images).unwrap();
+        std::fs::write(&labels_path, gzip(&labels)).unwrap();
+        let dataset = DataSet::load_idx(&images_path, &labels_path);
+        let (gz_images_path, plain_labels_path) = (temp_path("images-idx3-ubyte.gz"), temp_path("labels-idx1-ubyte"));
+        std::fs::write(&gz_images_path, gzip(&images)).unwrap();
+        std::fs::write(&plain_labels_path, &labels).unwrap();
+        let gz_dataset = DataSet::load_idx(&gz_images_path, &plain_labels_path);
+        for path in [images_path, labels_path, gz_images_path, plain_labels_path] {
+            std::fs::remove_file(path).unwrap();
+        }
+
+        let dataset = dataset.unwrap();
+        assert_eq!(dataset.images.shape(), &[3, 4]);
+        assert_eq!(dataset.get_image(1).unwrap(), &[80.0 / 255.0, 100.0 / 255.0, 120.0 / 255.0, 140.0 / 255.0]);
+        assert_eq!(dataset.targets, Targets::Classes(vec![7, 0, 9]));
+        let gz_dataset = gz_dataset.unwrap();
+        assert_eq!(gz_dataset.images, dataset.images);
+        assert_eq!(gz_dataset.targets, dataset.targets);
+    }
+
+    #[test]
+    fn load_idx_rejects_malformed_files() {
+        let labels_path = temp_path("bad-labels");
+        std::fs::write(&labels_path, idx_bytes(&[2], &[1, 2])).unwrap();
+        let cases: [(&str, Vec<u8>); 3] = [
+            ("truncated", idx_bytes(&[3, 2, 2], &[0; 11])),
+            ("count-mismatch", idx_bytes(&[3, 2, 2], &[0; 12])),
+            ("float-type", vec![0, 0, 0x0d, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0]),
+        ];
+        for (name, bytes) in cases {
+            let images_path = temp_path(name);
+            std::fs::write(&images_path, bytes).unwrap();
+            let result = DataSet::load_idx(&images_path, &labels_path);
+            std::fs::remove_file(&images_path).unwrap();
+            let error = result.err().unwrap_or_else(|| panic!("{} should fail", name));
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "{}: {}", name, error);
+        }
+        std::fs::remove_file(&labels_path).unwrap();
+    }
+}
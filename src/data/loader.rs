@@ -0,0 +1,399 @@
+// src/data/loader.rs
+use std::collections::BTreeMap;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::data::{Dataset, Target, Targets};
+use crate::tensor::Tensor;
+
+/// サンプルを取り出す順番の決め方
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sampling {
+    /// 0, 1, 2, ... の順（StreamingDataset 向け）
+    Sequential,
+    /// エポックごとに並べ替える
+    Shuffle,
+    /// 並べ替えたうえで、どのバッチでもクラスの割合が全体とほぼ同じになるように並べる（正解はクラス番号）
+    Stratified,
+    /// 重みに比例する確率で復元抽出する（1 エポックはサンプル数と同じ回数）
+    Weighted(Vec<f32>),
+}
+
+/// ミニバッチ（inputs と targets のテンソルは [batch, ...] の連続したバッファ）
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch {
+    /// データセット内の index
+    pub indices: Vec<usize>,
+    pub inputs: Tensor,
+    pub targets: Targets,
+}
+
+type CollateFn<D> = Arc<dyn Fn(&D, &[usize]) -> (Tensor, Targets) + Send + Sync>;
+
+/// データセットからミニバッチを作る
+///
+/// 順番は seed とエポック番号だけで決まる（同じ seed なら毎回同じ順番）。
+/// prefetch > 0 なら別スレッドで先にバッチを作っておき、読み込みや前処理を学習と重ねる。
+pub struct DataLoader<D: Dataset + Send + Sync + 'static> {
+    dataset: Arc<D>,
+    batch_size: usize,
+    drop_last: bool,
+    sampling: Sampling,
+    seed: u64,
+    epoch: u64,
+    prefetch: usize,
+    limit: Option<usize>,
+    collate: CollateFn<D>,
+}
+
+impl<D: Dataset + Send + Sync + 'static> DataLoader<D> {
+    /// 既定: Shuffle、seed はランダム、prefetch は 2 バッチ、drop_last なし
+    pub fn new(dataset: D, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "batch_size must be positive");
+        Self {
+            dataset: Arc::new(dataset),
+            batch_size,
+            drop_last: false,
+            sampling: Sampling::Shuffle,
+            seed: rand::thread_rng().gen(),
+            epoch: 0,
+            prefetch: 2,
+            limit: None,
+            collate: Arc::new(|dataset: &D, indices: &[usize]| dataset.batch(indices)),
+        }
+    }
+
+    /// 最後の端数のバッチを捨てる
+    pub fn drop_last(mut self, drop_last: bool) -> Self {
+        self.drop_last = drop_last;
+        self
+    }
+
+    pub fn sampling(mut self, sampling: Sampling) -> Self {
+        self.set_sampling(sampling);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// 先に作っておくバッチの数（0 ならスレッドを使わない）
+    pub fn prefetch(mut self, prefetch: usize) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// 先頭の limit 個のサンプルだけを使う（デバッグ用）
+    pub fn limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// index の列からバッチを作る関数（既定は Dataset::batch）。データ拡張などに使う
+    /// 返したテンソルが転置ビューなどで非連続なら、Batch に入れる前に連続化する。
+    pub fn collate<F>(mut self, collate: F) -> Self
+    where
+        F: Fn(&D, &[usize]) -> (Tensor, Targets) + Send + Sync + 'static,
+    {
+        self.collate = Arc::new(collate);
+        self
+    }
+
+    pub fn dataset(&self) -> &D {
+        &self.dataset
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// 作った後で順番の決め方を変える（Trainer の train_loader など）
+    pub fn set_sampling(&mut self, sampling: Sampling) {
+        if let Sampling::Weighted(weights) = &sampling {
+            assert_eq!(weights.len(), self.dataset.len(), "expected one sampling weight per sample");
+            assert!(WeightedIndex::new(weights).is_ok(), "sampling weights must be non-negative and not all zero");
+        }
+        self.sampling = sampling;
+    }
+
+    /// 次の iter() で使うエポック番号（再開するときに合わせる）
+    pub fn set_epoch(&mut self, epoch: u64) {
+        self.epoch = epoch;
+    }
+
+    /// 1 エポックで使うサンプル数
+    pub fn num_samples(&self) -> usize {
+        self.limit.unwrap_or(usize::MAX).min(self.dataset.len())
+    }
+
+    pub fn num_batches(&self) -> usize {
+        if self.drop_last {
+            self.num_samples() / self.batch_size
+        } else {
+            self.num_samples().div_ceil(self.batch_size)
+        }
+    }
+
+    /// エポックの順番（seed とエポック番号から決まる）
+    pub fn epoch_indices(&self, epoch: u64) -> Vec<usize> {
+        let n = self.num_samples();
+        let mut rng = StdRng::seed_from_u64(self.seed ^ epoch.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        match &self.sampling {
+            Sampling::Sequential => (0..n).collect(),
+            Sampling::Shuffle => {
+                let mut indices: Vec<usize> = (0..n).collect();
+                indices.shuffle(&mut rng);
+                indices
+            }
+            Sampling::Stratified => {
+                let classes: Vec<u32> = (0..n).map(|i| match self.dataset.target(i) {
+                    Target::Class(class) => class,
+                    _ => panic!("stratified sampling needs class targets"),
+                }).collect();
+                stratified_order(&classes, &mut rng)
+            }
+            Sampling::Weighted(weights) => {
+                let distribution = WeightedIndex::new(&weights[..n]).expect("sampling weights must not all be zero");
+                (0..n).map(|_| distribution.sample(&mut rng)).collect()
+            }
+        }
+    }
+
+    /// 次のエポックのバッチを返す（呼ぶたびにエポック番号が進む）
+    pub fn iter(&mut self) -> Batches<D> {
+        let indices = self.epoch_indices(self.epoch);
+        self.epoch += 1;
+        let mut chunks: Vec<Vec<usize>> = indices.chunks(self.batch_size).map(<[usize]>::to_vec).collect();
+        if self.drop_last && chunks.last().is_some_and(|c| c.len() < self.batch_size) {
+            chunks.pop();
+        }
+
+        let (dataset, collate) = (self.dataset.clone(), self.collate.clone());
+        if self.prefetch == 0 {
+            return Batches { source: Source::Inline { dataset, collate, chunks: chunks.into_iter() } };
+        }
+        // 受け取る側が捨てられたら send が失敗するので、スレッドもそこで終わる
+        let (sender, receiver) = sync_channel(self.prefetch);
+        let handle = std::thread::spawn(move || {
+            for indices in chunks {
+                if sender.send(collate_batch(&collate, &dataset, indices)).is_err() {
+                    break;
+                }
+            }
+        });
+        Batches { source: Source::Prefetch { receiver: Some(receiver), handle: Some(handle) } }
+    }
+}
+
+/// collate でバッチを作り、入力と正解のテンソルを連続なバッファにする
+fn collate_batch<D>(collate: &CollateFn<D>, dataset: &D, indices: Vec<usize>) -> Batch {
+    let (inputs, targets) = collate(dataset, &indices);
+    let targets = match targets {
+        Targets::Dense(values) => Targets::Dense(values.contiguous()),
+        Targets::MultiHot(values) => Targets::MultiHot(values.contiguous()),
+        classes @ Targets::Classes(_) => classes,
+    };
+    Batch { indices, inputs: inputs.contiguous(), targets }
+}
+
+/// 各クラスの中で並べ替え、クラス内の順位 / クラスの大きさ の順に並べる
+/// （どの区間を取ってもクラスの割合が全体とほぼ同じになる）
+fn stratified_order(classes: &[u32], rng: &mut StdRng) -> Vec<usize> {
+    let mut groups: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+    for (index, &class) in classes.iter().enumerate() {
+        groups.entry(class).or_default().push(index);
+    }
+    let mut keyed: Vec<(f64, usize)> = Vec::with_capacity(classes.len());
+    for members in groups.values_mut() {
+        members.shuffle(rng);
+        let offset: f64 = rng.gen();
+        let len = members.len() as f64;
+        keyed.extend(members.iter().enumerate().map(|(rank, &index)| ((rank as f64 + offset) / len, index)));
+    }
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+    keyed.into_iter().map(|(_, index)| index).collect()
+}
+
+enum Source<D> {
+    Inline {
+        dataset: Arc<D>,
+        collate: CollateFn<D>,
+        chunks: std::vec::IntoIter<Vec<usize>>,
+    },
+    Prefetch {
+        receiver: Option<Receiver<Batch>>,
+        handle: Option<JoinHandle<()>>,
+    },
+}
+
+/// 1 エポック分のバッチ
+pub struct Batches<D> {
+    source: Source<D>,
+}
+
+impl<D> Iterator for Batches<D> {
+    type Item = Batch;
+
+    fn next(&mut self) -> Option<Batch> {
+        match &mut self.source {
+            Source::Inline { dataset, collate, chunks } => {
+                let indices = chunks.next()?;
+                Some(collate_batch(collate, dataset, indices))
+            }
+            Source::Prefetch { receiver, handle } => {
+                if let Ok(batch) = receiver.as_ref()?.recv() {
+                    return Some(batch);
+                }
+                // 送る側が終わった。スレッドが panic していたらここで伝える
+                *receiver = None;
+                if let Err(panic) = handle.take()?.join() {
+                    std::panic::resume_unwind(panic);
+                }
+                None
+            }
+        }
+    }
+}
+
+impl<D> Drop for Batches<D> {
+    fn drop(&mut self) {
+        if let Source::Prefetch { receiver, handle } = &mut self.source {
+            // 先に受け取り側を閉じてから、スレッドの終了を待つ
+            *receiver = None;
+            if let Some(handle) = handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::DataSet;
+    use crate::data::tests::indexed_dataset;
+
+    fn epoch_order(loader: &mut DataLoader<DataSet>) -> Vec<usize> {
+        loader.iter().flat_map(|batch| batch.indices).collect()
+    }
+
+    #[test]
+    fn data_loader_seeded_shuffle_is_deterministic() {
+        let loader = || DataLoader::new(indexed_dataset(vec![0; 10]), 3).seed(7);
+        let (mut a, mut b) = (loader(), loader().prefetch(0));
+        let first = epoch_order(&mut a);
+        assert_eq!(first, epoch_order(&mut b));
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..10).collect::<Vec<_>>());
+
+        // エポックごとに順番が変わり、set_epoch で同じエポックをやり直せる
+        let second = epoch_order(&mut a);
+        assert_ne!(first, second);
+        a.set_epoch(1);
+        assert_eq!(epoch_order(&mut a), second);
+        assert_ne!(epoch_order(&mut DataLoader::new(indexed_dataset(vec![0; 10]), 3).seed(8)), first);
+    }
+
+    #[test]
+    fn data_loader_batches_and_drop_last() {
+        let mut loader = DataLoader::new(indexed_dataset(vec![0, 1, 2, 0, 1, 2, 0]), 3).sampling(Sampling::Sequential);
+        assert_eq!(loader.num_batches(), 3);
+        let batches: Vec<Batch> = loader.iter().collect();
+        assert_eq!(batches.iter().map(|b| b.indices.len()).collect::<Vec<_>>(), vec![3, 3, 1]);
+        assert_eq!(batches[1].inputs, Tensor::new(vec![3.0, 4.0, 5.0], vec![3, 1]));
+        assert!(batches[1].inputs.is_contiguous());
+        assert_eq!(batches[1].targets, Targets::Classes(vec![0, 1, 2]));
+
+        let mut loader = loader.drop_last(true).limit(Some(6));
+        assert_eq!(loader.num_batches(), 2);
+        assert_eq!(loader.iter().count(), 2);
+    }
+
+    #[test]
+    fn data_loader_stratified_batches_keep_class_ratio() {
+        // クラス 0 が 8 個、クラス 1 が 4 個: どのバッチも 2:1 になる
+        let classes: Vec<u32> = (0..12).map(|i| (i % 3 == 2) as u32).collect();
+        let mut loader = DataLoader::new(indexed_dataset(classes), 3).sampling(Sampling::Stratified).seed(1);
+        for _ in 0..3 {
+            let batches: Vec<Batch> = loader.iter().collect();
+            assert_eq!(batches.len(), 4);
+            for batch in batches {
+                let Targets::Classes(classes) = &batch.targets else { panic!("expected classes") };
+                assert_eq!(classes.iter().filter(|&&c| c == 1).count(), 1, "{:?}", batch.indices);
+            }
+        }
+    }
+
+    #[test]
+    fn data_loader_weighted_sampling_follows_weights() {
+        let weights = vec![0.0, 1.0, 3.0, 0.0];
+        let mut loader = DataLoader::new(indexed_dataset(vec![0; 4]), 2).sampling(Sampling::Weighted(weights)).seed(3);
+        let mut counts = [0usize; 4];
+        for _ in 0..500 {
+            for index in epoch_order(&mut loader) {
+                counts[index] += 1;
+            }
+        }
+        assert_eq!((counts[0], counts[3]), (0, 0));
+        let ratio = counts[2] as f32 / counts[1] as f32;
+        assert!((2.5..3.5).contains(&ratio), "{:?}", counts);
+    }
+
+    #[test]
+    fn data_loader_custom_collate() {
+        // データ拡張の代わりに特徴量を 2 倍にする
+        let mut loader = DataLoader::new(indexed_dataset(vec![0, 1, 2]), 2)
+            .sampling(Sampling::Sequential)
+            .collate(|dataset: &DataSet, indices: &[usize]| {
+                let (inputs, targets) = dataset.batch(indices);
+                (inputs.scale(2.0), targets)
+            });
+        let inputs: Vec<Tensor> = loader.iter().map(|batch| batch.inputs).collect();
+        assert_eq!(inputs, vec![Tensor::new(vec![0.0, 2.0], vec![2, 1]), Tensor::new(vec![4.0], vec![1, 1])]);
+    }
+
+    #[test]
+    fn data_loader_makes_collated_batches_contiguous() {
+        // 転置ビューを返す collate でも、Batch のテンソルは連続になる
+        for prefetch in [0, 2] {
+            let mut loader = DataLoader::new(indexed_dataset(vec![0; 4]), 2)
+                .prefetch(prefetch)
+                .collate(|_: &DataSet, indices: &[usize]| {
+                    let n = indices.len();
+                    let view = Tensor::new((0..2 * n).map(|i| i as f32).collect(), vec![2, n]).transpose();
+                    (view.clone(), Targets::Dense(view))
+                });
+            for batch in loader.iter() {
+                assert!(batch.inputs.is_contiguous());
+                assert_eq!(batch.inputs.to_vec(), vec![0.0, 2.0, 1.0, 3.0]);
+                let Targets::Dense(targets) = &batch.targets else { panic!("expected dense targets") };
+                assert!(targets.is_contiguous());
+            }
+        }
+    }
+
+    #[test]
+    fn data_loader_stops_worker_when_dropped_early() {
+        let mut loader = DataLoader::new(indexed_dataset(vec![0; 100]), 1).prefetch(1);
+        let first: Vec<Batch> = loader.iter().take(2).collect();
+        assert_eq!(first.len(), 2);
+        assert_eq!(loader.iter().count(), 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "collate failed")]
+    fn data_loader_propagates_worker_panics() {
+        let mut loader = DataLoader::new(indexed_dataset(vec![0; 4]), 2)
+            .collate(|_: &DataSet, _: &[usize]| panic!("collate failed"));
+        loader.iter().for_each(drop);
+    }
+}
@@ -0,0 +1,137 @@
+// src/data/mmap.rs
+use std::fs::File;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::data::nnrb::{Header, SectionReader};
+use crate::data::{Dataset, Sample, Target};
+
+/// NNRB ファイルを mmap して、必要なサンプルだけを読むデータセット
+///
+/// ファイル全体をメモリに読み込まないので、RAM より大きなデータでも使える
+/// （実際に読むのは get / batch で触れたページだけ）。
+/// open では header / class names / labels の CRC32 を検査する。features は大きいので、
+/// 検査したいときは verify() を呼ぶ。
+pub struct MmapDataset {
+    mmap: Mmap,
+    header: Header,
+    class_names: Vec<String>,
+    features: Range<usize>,
+    features_crc: u32,
+    labels: Range<usize>,
+}
+
+impl MmapDataset {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        // SAFETY: 読み込み専用で map する。開いている間に他のプロセスがファイルを書き換えると
+        // 読める値が変わりうるが、長さは open 時に確かめた範囲しか使わない。
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let mut reader = SectionReader { reader: &mmap[..], remaining: mmap.len() as u64, source: path.display().to_string() };
+        let metadata = reader.read_metadata()?;
+
+        // features / labels は読み込まずに位置だけ覚える
+        let (_, features_crc) = reader.read_prefix("features", Some(metadata.features_len), metadata.features_len)?;
+        let features_start = mmap.len() - reader.remaining as usize;
+        let features = features_start..features_start + metadata.features_len as usize;
+        reader.reader = &mmap[features.end..];
+        reader.remaining -= metadata.features_len;
+        let (_, labels_crc) = reader.read_prefix("labels", Some(metadata.labels_len), metadata.labels_len)?;
+        let labels_start = mmap.len() - reader.remaining as usize;
+        let labels = labels_start..labels_start + metadata.labels_len as usize;
+
+        reader.check_crc("labels", &mmap[labels.clone()], labels_crc)?;
+        metadata.header.check_class_names(&mmap[labels.clone()], &metadata.class_names).map_err(|e| reader.error(e))?;
+        Ok(Self { header: metadata.header, class_names: metadata.class_names, features, features_crc, labels, mmap })
+    }
+
+    /// features セクションの CRC32 を検査する（ファイル全体を 1 回読む）
+    pub fn verify(&self) -> io::Result<()> {
+        let crc = crc32fast::hash(&self.mmap[self.features.clone()]);
+        if crc != self.features_crc {
+            return Err(crate::data::invalid_data(format!(
+                "features section checksum mismatch (stored {:08x}, computed {:08x})", self.features_crc, crc
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn class_names(&self) -> &[String] {
+        &self.class_names
+    }
+}
+
+impl Dataset for MmapDataset {
+    fn len(&self) -> usize {
+        self.header.num_samples()
+    }
+
+    fn feature_shape(&self) -> Vec<usize> {
+        self.header.feature_shape[1..].to_vec()
+    }
+
+    fn get(&self, index: usize) -> Sample {
+        assert!(index < self.len(), "index {} is out of range for {} samples", index, self.len());
+        let (feature_bytes, label_bytes) = (self.header.feature_row_bytes(), self.header.label_row_bytes());
+        let features = &self.mmap[self.features.clone()][index * feature_bytes..(index + 1) * feature_bytes];
+        let labels = &self.mmap[self.labels.clone()][index * label_bytes..(index + 1) * label_bytes];
+        let mut values = Vec::with_capacity(self.header.feature_row_bytes() / self.header.feature_dtype.size());
+        self.header.feature_dtype.decode_f32(features, &mut values);
+        Sample { features: values, target: self.header.decode_target(labels) }
+    }
+
+    fn target(&self, index: usize) -> Target {
+        assert!(index < self.len(), "index {} is out of range for {} samples", index, self.len());
+        let label_bytes = self.header.label_row_bytes();
+        self.header.decode_target(&self.mmap[self.labels.clone()][index * label_bytes..(index + 1) * label_bytes])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::tests::temp_path;
+    use crate::data::{DataSet, Targets};
+    use crate::tensor::Tensor;
+
+    #[test]
+    fn mmap_dataset_matches_load_binary() {
+        let path = temp_path("mmap.nnrb");
+        let images = Tensor::new((0..30).map(|i| i as f32 / 7.0).collect(), vec![5, 2, 3]);
+        let dense = DataSet::new(images.clone(), Targets::Dense(Tensor::new((0..10).map(|i| i as f32).collect(), vec![5, 2])));
+        let classes = DataSet::new(images, Targets::Classes(vec![1, 0, 1, 1, 0]))
+            .with_class_names(vec!["no".to_string(), "yes".to_string()]);
+        for dataset in [dense, classes] {
+            dataset.save_binary(&path).unwrap();
+            let mmap = MmapDataset::open(&path).unwrap();
+            mmap.verify().unwrap();
+            assert_eq!(mmap.len(), 5);
+            assert_eq!(mmap.feature_shape(), vec![2, 3]);
+            assert_eq!(mmap.class_names(), &dataset.class_names[..]);
+            for index in 0..5 {
+                assert_eq!(Dataset::get(&mmap, index), Dataset::get(&dataset, index));
+            }
+            assert_eq!(mmap.batch(&[4, 0, 2]), dataset.batch(&[4, 0, 2]));
+        }
+
+        // features の破損は verify で、labels の破損は open で見つかる
+        let valid = std::fs::read(&path).unwrap();
+        let mut bad_features = valid.clone();
+        let labels_len = 5;
+        bad_features[valid.len() - labels_len - 12 - 1] ^= 0x40;
+        std::fs::write(&path, &bad_features).unwrap();
+        let error = MmapDataset::open(&path).unwrap().verify().unwrap_err();
+        assert!(error.to_string().contains("features section checksum mismatch"), "{}", error);
+        let mut bad_labels = valid;
+        *bad_labels.last_mut().unwrap() = 7;
+        std::fs::write(&path, &bad_labels).unwrap();
+        let error = MmapDataset::open(&path).err().unwrap();
+        assert!(error.to_string().contains("labels section checksum mismatch"), "{}", error);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,547 @@
+// src/data/nnrb.rs
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::data::{invalid_data, DataSet, Target, Targets};
+use crate::tensor::Tensor;
+
+/// NNRB 形式
+///
+/// [magic "NNRB"] [version (u32)] の後に 4 つのセクション（header / class names / features / labels）が続く。
+/// 各セクションは [長さ (u64)] [CRC32 (u32)] [中身]。数値は全てリトルエンディアン。
+///
+/// header の中身:
+///   features の dtype (u8), features の次元数 (u32), 各次元 (u64)
+///   targets の種類 (u8), labels の dtype (u8), labels の次元数 (u32), 各次元 (u64)
+/// class names の中身: 個数 (u32) の後に [長さ (u32)] [UTF-8]
+/// features / labels の中身: C order の値
+const NNRB_MAGIC: [u8; 4] = *b"NNRB";
+const NNRB_VERSION: u32 = 1;
+/// header / class names セクションの上限（壊れた長さで巨大な確保をしないため）
+const MAX_METADATA_LEN: u64 = 16 << 20;
+/// セクションの [長さ] [CRC32] の大きさ
+const SECTION_PREFIX_LEN: u64 = 12;
+
+const TARGETS_CLASSES: u8 = 0;
+const TARGETS_DENSE: u8 = 1;
+const TARGETS_MULTI_HOT: u8 = 2;
+
+/// セクションに保存する値の型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum DType {
+    F32,
+    F64,
+    U8,
+    U16,
+    U32,
+}
+
+impl DType {
+    fn code(self) -> u8 {
+        match self {
+            DType::F32 => 0,
+            DType::F64 => 1,
+            DType::U8 => 2,
+            DType::U16 => 3,
+            DType::U32 => 4,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        [DType::F32, DType::F64, DType::U8, DType::U16, DType::U32].into_iter().find(|d| d.code() == code)
+    }
+
+    pub(super) fn size(self) -> usize {
+        match self {
+            DType::U8 => 1,
+            DType::U16 => 2,
+            DType::F32 | DType::U32 => 4,
+            DType::F64 => 8,
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        matches!(self, DType::U8 | DType::U16 | DType::U32)
+    }
+
+    pub(super) fn decode(self, bytes: &[u8]) -> Vec<f64> {
+        bytes.chunks_exact(self.size()).map(|b| match self {
+            DType::F32 => f32::from_le_bytes(b.try_into().unwrap()) as f64,
+            DType::F64 => f64::from_le_bytes(b.try_into().unwrap()),
+            DType::U8 => b[0] as f64,
+            DType::U16 => u16::from_le_bytes(b.try_into().unwrap()) as f64,
+            DType::U32 => u32::from_le_bytes(b.try_into().unwrap()) as f64,
+        }).collect()
+    }
+
+    /// f32 にして output に追加する
+    pub(super) fn decode_f32(self, bytes: &[u8], output: &mut Vec<f32>) {
+        match self {
+            DType::F32 => output.extend(bytes.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap()))),
+            _ => output.extend(self.decode(bytes).into_iter().map(|v| v as f32)),
+        }
+    }
+
+    fn encode(self, values: impl Iterator<Item = f64>, output: &mut Vec<u8>) {
+        for v in values {
+            match self {
+                DType::F32 => output.extend_from_slice(&(v as f32).to_le_bytes()),
+                DType::F64 => output.extend_from_slice(&v.to_le_bytes()),
+                DType::U8 => output.push(v as u8),
+                DType::U16 => output.extend_from_slice(&(v as u16).to_le_bytes()),
+                DType::U32 => output.extend_from_slice(&(v as u32).to_le_bytes()),
+            }
+        }
+    }
+}
+
+/// header セクションの中身
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Header {
+    pub(super) feature_dtype: DType,
+    pub(super) feature_shape: Vec<usize>,
+    targets_kind: u8,
+    pub(super) label_dtype: DType,
+    label_shape: Vec<usize>,
+}
+
+impl Header {
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for (dtype, shape, prefix) in [
+            (self.feature_dtype, &self.feature_shape, None),
+            (self.label_dtype, &self.label_shape, Some(self.targets_kind)),
+        ] {
+            bytes.extend(prefix);
+            bytes.push(dtype.code());
+            bytes.extend_from_slice(&(shape.len() as u32).to_le_bytes());
+            for &dim in shape {
+                bytes.extend_from_slice(&(dim as u64).to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut cursor = Cursor { bytes, pos: 0 };
+        let read_shape = |cursor: &mut Cursor| -> Result<(DType, Vec<usize>), String> {
+            let code = cursor.u8()?;
+            let dtype = DType::from_code(code).ok_or_else(|| format!("unknown dtype code {}", code))?;
+            let rank = cursor.u32()?;
+            let shape = (0..rank)
+                .map(|_| usize::try_from(cursor.u64()?).map_err(|_| "dimension does not fit in usize".to_string()))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((dtype, shape))
+        };
+        let (feature_dtype, feature_shape) = read_shape(&mut cursor)?;
+        let targets_kind = cursor.u8()?;
+        let (label_dtype, label_shape) = read_shape(&mut cursor)?;
+        cursor.finish()?;
+        Ok(Self { feature_dtype, feature_shape, targets_kind, label_dtype, label_shape })
+    }
+
+    pub(super) fn num_samples(&self) -> usize {
+        self.feature_shape[0]
+    }
+
+    /// 1 サンプル分の features のバイト数
+    pub(super) fn feature_row_bytes(&self) -> usize {
+        self.feature_shape[1..].iter().product::<usize>() * self.feature_dtype.size()
+    }
+
+    /// 1 サンプル分の labels のバイト数
+    pub(super) fn label_row_bytes(&self) -> usize {
+        self.label_shape[1..].iter().product::<usize>() * self.label_dtype.size()
+    }
+
+    /// 1 サンプル分の labels のバイト列を正解にする
+    pub(super) fn decode_target(&self, bytes: &[u8]) -> Target {
+        let values = self.label_dtype.decode(bytes);
+        match self.targets_kind {
+            TARGETS_CLASSES => Target::Class(values[0] as u32),
+            TARGETS_DENSE => Target::Dense(values.into_iter().map(|v| v as f32).collect()),
+            _ => Target::MultiHot(values.into_iter().map(|v| v as f32).collect()),
+        }
+    }
+
+    /// クラス名があるとき、全てのクラス番号に名前があるか確かめる
+    pub(super) fn check_class_names(&self, labels: &[u8], class_names: &[String]) -> Result<(), String> {
+        if self.targets_kind != TARGETS_CLASSES || class_names.is_empty() {
+            return Ok(());
+        }
+        match self.label_dtype.decode(labels).into_iter().find(|&c| c as usize >= class_names.len()) {
+            Some(class) => Err(format!("class {} has no name ({} class names)", class, class_names.len())),
+            None => Ok(()),
+        }
+    }
+
+    /// 形の整合性を確かめて、(features, labels) セクションのバイト数を返す
+    fn validate(&self) -> Result<(u64, u64), String> {
+        let byte_len = |dtype: DType, shape: &[usize], what: &str| {
+            shape.iter().try_fold(dtype.size() as u64, |acc, &d| acc.checked_mul(d as u64))
+                .ok_or_else(|| format!("{} shape {:?} overflows", what, shape))
+        };
+        let Some(&num_samples) = self.feature_shape.first() else {
+            return Err("features must have at least one dimension".to_string());
+        };
+        if self.label_shape.first() != Some(&num_samples) {
+            return Err(format!("features have shape {:?} but labels have shape {:?}", self.feature_shape, self.label_shape));
+        }
+        match self.targets_kind {
+            TARGETS_CLASSES if self.label_shape.len() != 1 || !self.label_dtype.is_integer() => {
+                return Err(format!("class labels must be a 1-D integer array, got {:?} {:?}", self.label_dtype, self.label_shape));
+            }
+            TARGETS_CLASSES | TARGETS_DENSE | TARGETS_MULTI_HOT => {}
+            kind => return Err(format!("unknown targets kind {}", kind)),
+        }
+        Ok((byte_len(self.feature_dtype, &self.feature_shape, "feature")?, byte_len(self.label_dtype, &self.label_shape, "label")?))
+    }
+}
+
+/// バイト列を先頭から読む（足りなければエラー）
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], String> {
+        let bytes = self.pos.checked_add(n).and_then(|end| self.bytes.get(self.pos..end))
+            .ok_or_else(|| format!("truncated: needed {} more bytes at offset {}", n, self.pos))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn finish(&self) -> Result<(), String> {
+        if self.pos == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(format!("{} unexpected trailing bytes", self.bytes.len() - self.pos))
+        }
+    }
+}
+
+fn encode_class_names(class_names: &[String]) -> Vec<u8> {
+    let mut bytes = (class_names.len() as u32).to_le_bytes().to_vec();
+    for name in class_names {
+        bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+    }
+    bytes
+}
+
+fn decode_class_names(bytes: &[u8]) -> Result<Vec<String>, String> {
+    let mut cursor = Cursor { bytes, pos: 0 };
+    let count = cursor.u32()?;
+    let mut class_names = Vec::new();
+    for i in 0..count {
+        let len = cursor.u32()? as usize;
+        let name = std::str::from_utf8(cursor.take(len)?).map_err(|_| format!("class name #{} is not valid UTF-8", i))?;
+        class_names.push(name.to_string());
+    }
+    cursor.finish()?;
+    Ok(class_names)
+}
+
+/// セクションを順に読む（長さをファイルの残りと照らし合わせてから確保する）
+pub(super) struct SectionReader<R: Read> {
+    pub(super) reader: R,
+    /// ファイルの残りのバイト数
+    pub(super) remaining: u64,
+    pub(super) source: String,
+}
+
+/// header と class names を読み、features / labels の長さを確かめたもの
+pub(super) struct Metadata {
+    pub(super) header: Header,
+    pub(super) class_names: Vec<String>,
+    pub(super) features_len: u64,
+    pub(super) labels_len: u64,
+}
+
+impl<R: Read> SectionReader<R> {
+    pub(super) fn error(&self, message: String) -> io::Error {
+        invalid_data(format!("{}: {}", self.source, message))
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.reader.read_exact(buf)?;
+        self.remaining -= buf.len() as u64;
+        Ok(())
+    }
+
+    /// セクションの [長さ] [CRC32] を読む
+    /// expected_len が Some ならその長さでなければならない。None なら max_len 以下
+    pub(super) fn read_prefix(&mut self, name: &str, expected_len: Option<u64>, max_len: u64) -> io::Result<(u64, u32)> {
+        if self.remaining < SECTION_PREFIX_LEN {
+            return Err(self.error(format!("file ends before the {} section", name)));
+        }
+        let mut prefix = [0u8; SECTION_PREFIX_LEN as usize];
+        self.read_exact(&mut prefix)?;
+        let len = u64::from_le_bytes(prefix[..8].try_into().unwrap());
+        let crc = u32::from_le_bytes(prefix[8..].try_into().unwrap());
+        match expected_len {
+            Some(expected) if len != expected => {
+                return Err(self.error(format!("{} section is {} bytes, but the header implies {}", name, len, expected)));
+            }
+            None if len > max_len => {
+                return Err(self.error(format!("{} section length {} exceeds the limit of {} bytes", name, len, max_len)));
+            }
+            _ => {}
+        }
+        if len > self.remaining {
+            return Err(self.error(format!("{} section needs {} bytes, but only {} remain", name, len, self.remaining)));
+        }
+        Ok((len, crc))
+    }
+
+    pub(super) fn check_crc(&self, name: &str, payload: &[u8], expected_crc: u32) -> io::Result<()> {
+        let crc = crc32fast::hash(payload);
+        if crc != expected_crc {
+            return Err(self.error(format!("{} section checksum mismatch (stored {:08x}, computed {:08x})", name, expected_crc, crc)));
+        }
+        Ok(())
+    }
+
+    fn read_section(&mut self, name: &str, expected_len: Option<u64>, max_len: u64) -> io::Result<Vec<u8>> {
+        let (len, crc) = self.read_prefix(name, expected_len, max_len)?;
+        let mut payload = vec![0u8; len as usize];
+        self.read_exact(&mut payload)?;
+        self.check_crc(name, &payload, crc)?;
+        Ok(payload)
+    }
+
+    /// セクションを少しずつ読み、CRC32 を計算しながら f32 に変換する
+    /// （バイト列全体を確保しないので、ピークのメモリは変換後の Vec<f32> だけ）
+    fn read_section_f32(&mut self, name: &str, len: u64, dtype: DType) -> io::Result<Vec<f32>> {
+        let (len, expected_crc) = self.read_prefix(name, Some(len), len)?;
+        let mut values = Vec::with_capacity(len as usize / dtype.size());
+        let mut hasher = crc32fast::Hasher::new();
+        let mut chunk = vec![0u8; (64 << 10) * dtype.size()];
+        let mut left = len as usize;
+        while left > 0 {
+            let n = left.min(chunk.len());
+            self.read_exact(&mut chunk[..n])?;
+            hasher.update(&chunk[..n]);
+            dtype.decode_f32(&chunk[..n], &mut values);
+            left -= n;
+        }
+        let crc = hasher.finalize();
+        if crc != expected_crc {
+            return Err(self.error(format!("{} section checksum mismatch (stored {:08x}, computed {:08x})", name, expected_crc, crc)));
+        }
+        Ok(values)
+    }
+
+    /// magic / version / header / class names を読み、残りの長さが header と合っているか確かめる
+    pub(super) fn read_metadata(&mut self) -> io::Result<Metadata> {
+        if self.remaining < 8 {
+            return Err(self.error("file is too short to be an NNRB file".to_string()));
+        }
+        let mut preamble = [0u8; 8];
+        self.read_exact(&mut preamble)?;
+        if preamble[..4] != NNRB_MAGIC {
+            return Err(self.error(format!("not an NNRB file (magic bytes {:?})", &preamble[..4])));
+        }
+        let version = u32::from_le_bytes(preamble[4..].try_into().unwrap());
+        if version != NNRB_VERSION {
+            return Err(self.error(format!("unsupported NNRB version {} (expected {})", version, NNRB_VERSION)));
+        }
+
+        let header = self.read_section("header", None, MAX_METADATA_LEN)?;
+        let header = Header::decode(&header).map_err(|e| self.error(format!("malformed header: {}", e)))?;
+        let (features_len, labels_len) = header.validate().map_err(|e| self.error(format!("inconsistent header: {}", e)))?;
+        let class_names = self.read_section("class names", None, MAX_METADATA_LEN)?;
+        let class_names = decode_class_names(&class_names).map_err(|e| self.error(format!("malformed class names: {}", e)))?;
+        // 中身を読む前に、全体の長さが header と合っているか確かめる
+        let expected_remaining = features_len.checked_add(labels_len).and_then(|n| n.checked_add(2 * SECTION_PREFIX_LEN));
+        if expected_remaining != Some(self.remaining) {
+            return Err(self.error(format!(
+                "header implies {} bytes of features and {} bytes of labels, but {} bytes remain",
+                features_len, labels_len, self.remaining
+            )));
+        }
+        Ok(Metadata { header, class_names, features_len, labels_len })
+    }
+}
+
+fn write_section<W: Write>(writer: &mut W, payload: &[u8]) -> io::Result<()> {
+    writer.write_all(&(payload.len() as u64).to_le_bytes())?;
+    writer.write_all(&crc32fast::hash(payload).to_le_bytes())?;
+    writer.write_all(payload)
+}
+
+impl DataSet {
+    /// NNRB 形式で保存する（特徴量は f32、クラス番号は最大値が収まる最小の整数型）
+    pub fn save_binary<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let (targets_kind, label_dtype, label_shape, label_values): (u8, DType, Vec<usize>, Vec<f64>) = match &self.targets {
+            Targets::Classes(classes) => {
+                let max = classes.iter().copied().max().unwrap_or(0);
+                let dtype = if max <= u8::MAX as u32 { DType::U8 } else if max <= u16::MAX as u32 { DType::U16 } else { DType::U32 };
+                (TARGETS_CLASSES, dtype, vec![classes.len()], classes.iter().map(|&c| c as f64).collect())
+            }
+            // 転置ビューなどの非連続なテンソルも行優先で書く
+            Targets::Dense(values) => (TARGETS_DENSE, DType::F32, values.shape().to_vec(), values.to_vec().into_iter().map(f64::from).collect()),
+            Targets::MultiHot(values) => {
+                let values_f32 = values.to_vec();
+                // 0 / 1 だけなら u8 で保存する
+                let dtype = if values_f32.iter().all(|&v| v == 0.0 || v == 1.0) { DType::U8 } else { DType::F32 };
+                (TARGETS_MULTI_HOT, dtype, values.shape().to_vec(), values_f32.into_iter().map(f64::from).collect())
+            }
+        };
+        let header = Header {
+            feature_dtype: DType::F32,
+            feature_shape: self.images.shape().to_vec(),
+            targets_kind,
+            label_dtype,
+            label_shape,
+        };
+
+        let mut features = Vec::with_capacity(self.images.len() * 4);
+        DType::F32.encode(self.images.contiguous().as_slice().iter().map(|&v| v as f64), &mut features);
+        let mut labels = Vec::new();
+        label_dtype.encode(label_values.into_iter(), &mut labels);
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(&NNRB_MAGIC)?;
+        writer.write_all(&NNRB_VERSION.to_le_bytes())?;
+        write_section(&mut writer, &header.encode())?;
+        write_section(&mut writer, &encode_class_names(&self.class_names))?;
+        write_section(&mut writer, &features)?;
+        write_section(&mut writer, &labels)?;
+        writer.flush()
+    }
+
+    /// NNRB 形式のファイルを読み込む
+    ///
+    /// magic / version / 形の整合性 / セクションの長さを、データを確保する前に確かめる。
+    /// 各セクションは CRC32 で検査する。どれかが合わなければ `ErrorKind::InvalidData`。
+    pub fn load_binary<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut reader = SectionReader { reader: BufReader::new(file), remaining: file_len, source: path.display().to_string() };
+        let Metadata { header, class_names, features_len, labels_len } = reader.read_metadata()?;
+
+        let images = reader.read_section_f32("features", features_len, header.feature_dtype)?;
+        let labels = reader.read_section("labels", Some(labels_len), labels_len)?;
+        header.check_class_names(&labels, &class_names).map_err(|e| reader.error(e))?;
+        let label_values = header.label_dtype.decode(&labels);
+
+        let targets = match header.targets_kind {
+            TARGETS_CLASSES => Targets::Classes(label_values.into_iter().map(|v| v as u32).collect()),
+            kind => {
+                let values = Tensor::new(label_values.into_iter().map(|v| v as f32).collect(), header.label_shape);
+                if kind == TARGETS_DENSE { Targets::Dense(values) } else { Targets::MultiHot(values) }
+            }
+        };
+        Ok(DataSet::new(Tensor::new(images, header.feature_shape), targets).with_class_names(class_names))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::tests::temp_path;
+
+    #[test]
+    fn save_and_load_binary_round_trip() {
+        let path = temp_path("round_trip.nnrb");
+        let images = Tensor::new((0..24).map(|i| i as f32 * 0.5 - 3.0).collect(), vec![3, 2, 2, 2]);
+        let datasets = [
+            DataSet::new(images.clone(), Targets::Classes(vec![2, 0, 1]))
+                .with_class_names(vec!["cat".to_string(), "dog".to_string(), "鳥".to_string()]),
+            DataSet::new(images.clone(), Targets::Classes(vec![70000, 0, 300])),
+            DataSet::new(images.clone(), Targets::Dense(Tensor::new(vec![0.5, -1.0, 2.25, 0.0, 1e-3, 7.0], vec![3, 2]))),
+            DataSet::new(images.clone(), Targets::MultiHot(Tensor::new(vec![1.0, 0.0, 0.0, 1.0, 1.0, 1.0], vec![3, 2]))),
+        ];
+        for dataset in datasets {
+            dataset.save_binary(&path).unwrap();
+            let loaded = DataSet::load_binary(&path).unwrap();
+            assert_eq!(loaded.images, dataset.images);
+            assert_eq!(loaded.targets, dataset.targets);
+            assert_eq!(loaded.class_names, dataset.class_names);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn save_binary_accepts_non_contiguous_labels() {
+        let path = temp_path("views.nnrb");
+        let images = Tensor::new((0..6).map(|i| i as f32).collect(), vec![2, 3]).transpose();
+        let dense = Tensor::new(vec![0.5, -1.0, 2.0, 1.5, 0.0, 3.0], vec![2, 3]).transpose();
+        let multi_hot = Tensor::new(vec![1.0, 0.0, 0.0, 1.0, 1.0, 1.0], vec![2, 3]).transpose();
+        for targets in [Targets::Dense(dense), Targets::MultiHot(multi_hot)] {
+            let dataset = DataSet::new(images.clone(), targets);
+            dataset.save_binary(&path).unwrap();
+            let loaded = DataSet::load_binary(&path).unwrap();
+            assert_eq!(loaded.images, images.contiguous());
+            assert_eq!(loaded.targets.to_tensor(2), dataset.targets.to_tensor(2).contiguous());
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_binary_rejects_corrupt_files() {
+        let path = temp_path("corrupt.nnrb");
+        let dataset = DataSet::new(Tensor::new(vec![1.0, 2.0, 3.0, 4.0], vec![2, 2]), Targets::Classes(vec![0, 1]));
+        dataset.save_binary(&path).unwrap();
+        let valid = std::fs::read(&path).unwrap();
+        // header セクションは 8 バイト目から: [長さ][CRC32][features の dtype][次元数][N][F]...
+        let header_start = 8 + 12;
+        let n_offset = header_start + 1 + 4;
+
+        let mut flipped = valid.clone();
+        *flipped.last_mut().unwrap() ^= 1;
+        let mut truncated = valid.clone();
+        truncated.truncate(valid.len() - 3);
+        let mut huge = valid.clone();
+        huge[n_offset..n_offset + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        // CRC も合わせて直し、header 自体は正しいが長さが合わないファイルにする
+        let mut inconsistent = valid.clone();
+        inconsistent[n_offset + 8..n_offset + 16].copy_from_slice(&(1u64 << 40).to_le_bytes());
+        let header_len = u64::from_le_bytes(valid[8..16].try_into().unwrap()) as usize;
+        let crc = crc32fast::hash(&inconsistent[header_start..header_start + header_len]);
+        inconsistent[16..20].copy_from_slice(&crc.to_le_bytes());
+        let mut version = valid.clone();
+        version[4] = 9;
+
+        let cases = [
+            (flipped, "labels section checksum mismatch"),
+            (truncated, "but 39 bytes remain"),
+            (huge, "header section checksum mismatch"),
+            (inconsistent, "header implies 8796093022208 bytes of features"),
+            (version, "unsupported NNRB version 9"),
+            (b"NNRA\x01\x00\x00\x00".to_vec(), "not an NNRB file"),
+            (b"NNRB".to_vec(), "too short"),
+        ];
+        for (bytes, expected) in cases {
+            std::fs::write(&path, bytes).unwrap();
+            let error = DataSet::load_binary(&path).err().unwrap_or_else(|| panic!("{} should fail", expected));
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+            assert!(error.to_string().contains(expected), "expected {:?} in {}", expected, error);
+        }
+
+        // 旧形式もヘッダーとファイルの長さが合わなければ確保せずにエラー
+        let mut legacy = Vec::new();
+        legacy.extend_from_slice(&u64::MAX.to_le_bytes());
+        legacy.extend_from_slice(&4u64.to_le_bytes());
+        std::fs::write(&path, legacy).unwrap();
+        let error = DataSet::load_from_binary(path.to_str().unwrap()).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,355 @@
+// src/data/npy.rs
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::data::{invalid_data, DataSet, Targets};
+use crate::tensor::Tensor;
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+/// .npy の要素の型（descr の '<f4' などを分解したもの）
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DType {
+    /// 'f'（浮動小数点）/ 'i'（符号付き整数）/ 'u'（符号なし整数）/ 'b'（bool）
+    kind: char,
+    size: usize,
+    big_endian: bool,
+}
+
+impl DType {
+    fn parse(descr: &str) -> Result<Self, String> {
+        let mut chars = descr.chars();
+        let (order, kind) = (chars.next(), chars.next());
+        let size: Option<usize> = chars.as_str().parse().ok();
+        let big_endian = match order {
+            Some('>') => true,
+            Some('<') | Some('|') | Some('=') => cfg!(target_endian = "big") && order == Some('='),
+            _ => return Err(format!("unsupported dtype {:?}", descr)),
+        };
+        match (kind, size) {
+            (Some('f'), Some(4 | 8)) | (Some('i' | 'u'), Some(1 | 2 | 4 | 8)) | (Some('b'), Some(1)) => {
+                Ok(Self { kind: kind.unwrap(), size: size.unwrap(), big_endian })
+            }
+            _ => Err(format!("unsupported dtype {:?} (expected f4, f8, i1-i8, u1-u8 or b1)", descr)),
+        }
+    }
+
+    fn is_integer(&self) -> bool {
+        self.kind != 'f'
+    }
+
+    /// 1 要素分のバイト列を f64 にする
+    fn decode(&self, bytes: &[u8]) -> f64 {
+        macro_rules! read {
+            ($t:ty) => {{
+                let bytes = bytes.try_into().unwrap();
+                (if self.big_endian { <$t>::from_be_bytes(bytes) } else { <$t>::from_le_bytes(bytes) }) as f64
+            }};
+        }
+        match (self.kind, self.size) {
+            ('f', 4) => read!(f32),
+            ('f', 8) => read!(f64),
+            ('i', 1) => read!(i8),
+            ('i', 2) => read!(i16),
+            ('i', 4) => read!(i32),
+            ('i', 8) => read!(i64),
+            ('u', 1) | ('b', 1) => read!(u8),
+            ('u', 2) => read!(u16),
+            ('u', 4) => read!(u32),
+            ('u', 8) => read!(u64),
+            _ => unreachable!("checked in DType::parse"),
+        }
+    }
+}
+
+/// .npy から読んだ配列（値は C order に並べ直してある）
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct NpyArray {
+    pub shape: Vec<usize>,
+    pub values: Vec<f64>,
+    pub is_integer: bool,
+}
+
+/// ヘッダーの辞書 {'descr': '<f4', 'fortran_order': False, 'shape': (3, 4), } を読む
+///
+/// 値は文字列・タプル・識別子（True / False）だけなので、それ以外が出てきたらエラーにする。
+fn parse_header_dict(header: &str) -> Result<Vec<(String, String)>, String> {
+    let mut rest = header.trim().strip_prefix('{').ok_or("header is not a dict")?.trim_start();
+    let mut entries = Vec::new();
+    loop {
+        if let Some(after) = rest.strip_prefix('}') {
+            if !after.trim().is_empty() {
+                return Err("unexpected data after the header dict".to_string());
+            }
+            return Ok(entries);
+        }
+        let quote = rest.chars().next().filter(|c| *c == '\'' || *c == '"').ok_or("expected a quoted key")?;
+        let end = rest[1..].find(quote).ok_or("unterminated key")? + 1;
+        let key = rest[1..end].to_string();
+        rest = rest[end + 1..].trim_start().strip_prefix(':').ok_or("expected ':' after a key")?.trim_start();
+
+        let value_len = match rest.chars().next() {
+            Some(q @ ('\'' | '"')) => rest[1..].find(q).ok_or("unterminated string value")? + 2,
+            Some('(') => rest.find(')').ok_or("unterminated tuple")? + 1,
+            Some(_) => rest.find([',', '}']).ok_or("unterminated value")?,
+            None => return Err("unexpected end of header".to_string()),
+        };
+        entries.push((key, rest[..value_len].trim().to_string()));
+        rest = rest[value_len..].trim_start();
+        rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+    }
+}
+
+/// "(3, 4)" / "(3,)" / "()" を読む
+fn parse_shape(value: &str) -> Result<Vec<usize>, String> {
+    let inner = value.strip_prefix('(').and_then(|v| v.strip_suffix(')'))
+        .ok_or_else(|| format!("shape {} is not a tuple", value))?;
+    inner.split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(|d| d.parse().map_err(|_| format!("invalid dimension {:?} in shape {}", d, value)))
+        .collect()
+}
+
+/// Fortran order（最初の軸が最も速く変わる）の値を C order に並べ直す
+fn fortran_to_c_order(values: &[f64], shape: &[usize]) -> Vec<f64> {
+    let mut f_strides = vec![1; shape.len()];
+    for axis in 1..shape.len() {
+        f_strides[axis] = f_strides[axis - 1] * shape[axis - 1];
+    }
+    let mut index = vec![0; shape.len()];
+    let mut output = Vec::with_capacity(values.len());
+    for _ in 0..values.len() {
+        output.push(values[index.iter().zip(&f_strides).map(|(i, s)| i * s).sum::<usize>()]);
+        // C order で次の位置へ（最後の軸から繰り上げる）
+        for axis in (0..shape.len()).rev() {
+            index[axis] += 1;
+            if index[axis] < shape[axis] {
+                break;
+            }
+            index[axis] = 0;
+        }
+    }
+    output
+}
+
+/// .npy のバイト列を読む（source はエラーメッセージ用の名前）
+pub(crate) fn parse_npy(bytes: &[u8], source: &str) -> io::Result<NpyArray> {
+    let error = |message: String| invalid_data(format!("{}: {}", source, message));
+    if bytes.len() < 10 || &bytes[..6] != NPY_MAGIC {
+        return Err(error("not a .npy file (missing \\x93NUMPY magic)".to_string()));
+    }
+    let (header_start, header_len) = match bytes[6] {
+        1 => (10, u16::from_le_bytes([bytes[8], bytes[9]]) as usize),
+        2 | 3 if bytes.len() >= 12 => (12, u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize),
+        major => return Err(error(format!("unsupported .npy version {}.{}", major, bytes[7]))),
+    };
+    let data_start = header_start + header_len;
+    let header = bytes.get(header_start..data_start)
+        .ok_or_else(|| error(format!("header of {} bytes is truncated", header_len)))?;
+    let header = std::str::from_utf8(header).map_err(|_| error("header is not valid text".to_string()))?;
+    let entries = parse_header_dict(header).map_err(|e| error(format!("malformed header {:?}: {}", header.trim(), e)))?;
+    let field = |key: &str| {
+        entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+            .ok_or_else(|| error(format!("header has no {:?}", key)))
+    };
+
+    let descr = field("descr")?;
+    let descr = descr.strip_prefix(['\'', '"']).and_then(|d| d.strip_suffix(['\'', '"']))
+        .ok_or_else(|| error(format!("unsupported descr {} (structured dtypes are not supported)", descr)))?;
+    let dtype = DType::parse(descr).map_err(error)?;
+    let fortran_order = match field("fortran_order")? {
+        "True" => true,
+        "False" => false,
+        other => return Err(error(format!("fortran_order must be True or False, got {}", other))),
+    };
+    let shape = parse_shape(field("shape")?).map_err(error)?;
+
+    let num_elements = shape.iter().try_fold(1usize, |acc, &d| acc.checked_mul(d))
+        .ok_or_else(|| error(format!("shape {:?} overflows", shape)))?;
+    let data = &bytes[data_start..];
+    if Some(data.len()) != num_elements.checked_mul(dtype.size) {
+        return Err(error(format!(
+            "expected {} elements of {} bytes for shape {:?}, found {} bytes", num_elements, dtype.size, shape, data.len()
+        )));
+    }
+    let values: Vec<f64> = data.chunks_exact(dtype.size).map(|chunk| dtype.decode(chunk)).collect();
+    let values = if fortran_order { fortran_to_c_order(&values, &shape) } else { values };
+    Ok(NpyArray { shape, values, is_integer: dtype.is_integer() })
+}
+
+/// 特徴量 [N, ...] とラベルの配列から DataSet を作る
+///
+/// ラベルが整数の 1 次元配列ならクラス番号、それ以外は実数の正解 [N, ...] とする。
+fn dataset_from_arrays(features: NpyArray, labels: NpyArray, source: &str) -> io::Result<DataSet> {
+    let error = |message: String| invalid_data(format!("{}: {}", source, message));
+    let Some((&num_samples, sample_dims)) = features.shape.split_first() else {
+        return Err(error("features must have at least one dimension".to_string()));
+    };
+    if labels.shape.first() != Some(&num_samples) {
+        return Err(error(format!("features have shape {:?} but labels have shape {:?}", features.shape, labels.shape)));
+    }
+    let num_features = sample_dims.iter().product();
+    let images = Tensor::new(features.values.into_iter().map(|v| v as f32).collect(), vec![num_samples, num_features]);
+
+    let targets = if labels.is_integer && labels.shape.len() == 1 {
+        let classes = labels.values.iter()
+            .map(|&v| if (0.0..=u32::MAX as f64).contains(&v) { Ok(v as u32) } else { Err(v) })
+            .collect::<Result<Vec<u32>, f64>>()
+            .map_err(|v| error(format!("label {} is not a valid class index", v)))?;
+        Targets::Classes(classes)
+    } else {
+        let dim = labels.shape[1..].iter().product();
+        Targets::Dense(Tensor::new(labels.values.into_iter().map(|v| v as f32).collect(), vec![num_samples, dim]))
+    };
+    Ok(DataSet::new(images, targets))
+}
+
+fn read_npy_file(path: &Path) -> io::Result<NpyArray> {
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+    parse_npy(&bytes, &path.display().to_string())
+}
+
+impl DataSet {
+    /// NumPy の .npy（特徴量とラベルの 2 ファイル）を読み込む
+    ///
+    /// 特徴量は [N, ...] を [N, 残りの軸の積] にする。dtype は f4 / f8 / 整数 / bool に対応し、f32 に変換する。
+    /// ラベルが整数の 1 次元配列なら `Targets::Classes`、それ以外は `Targets::Dense`。
+    pub fn from_npy<P: AsRef<Path>, Q: AsRef<Path>>(features_path: P, labels_path: Q) -> io::Result<Self> {
+        let features = read_npy_file(features_path.as_ref())?;
+        let labels = read_npy_file(labels_path.as_ref())?;
+        dataset_from_arrays(features, labels, &features_path.as_ref().display().to_string())
+    }
+
+    /// np.savez / np.savez_compressed で保存した .npz から、features_key と labels_key の配列を読み込む
+    pub fn from_npz<P: AsRef<Path>>(path: P, features_key: &str, labels_key: &str) -> io::Result<Self> {
+        let path = path.as_ref();
+        let source = path.display().to_string();
+        let mut archive = zip::ZipArchive::new(File::open(path)?)
+            .map_err(|e| invalid_data(format!("{}: not a valid .npz archive: {}", source, e)))?;
+        let mut available: Vec<String> = archive.file_names()
+            .filter_map(|n| n.strip_suffix(".npy"))
+            .map(String::from)
+            .collect();
+        available.sort();
+
+        let mut read_array = |key: &str| -> io::Result<NpyArray> {
+            let name = format!("{}.npy", key);
+            let mut entry = match archive.by_name(&name) {
+                Ok(entry) => entry,
+                Err(zip::result::ZipError::FileNotFound) => {
+                    return Err(invalid_data(format!("{}: no array {:?} (available: {:?})", source, key, available)));
+                }
+                Err(e) => return Err(invalid_data(format!("{}: cannot read {}: {}", source, name, e))),
+            };
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+            parse_npy(&bytes, &format!("{}/{}", source, name))
+        };
+        let features = read_array(features_key)?;
+        let labels = read_array(labels_key)?;
+        dataset_from_arrays(features, labels, &source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    use crate::data::tests::temp_path;
+    use crate::data::{DataSet, Targets};
+
+    /// np.save と同じ形式（version 1.0、ヘッダーは 64 バイト境界まで空白で埋めて改行で終える）
+    fn npy_bytes(descr: &str, fortran_order: bool, shape: &str, data: &[u8]) -> Vec<u8> {
+        let fortran_order = if fortran_order { "True" } else { "False" };
+        let mut header = format!("{{'descr': '{}', 'fortran_order': {}, 'shape': {}, }}", descr, fortran_order, shape);
+        while (10 + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn le_bytes<T: Copy, const N: usize>(values: &[T], to_bytes: fn(T) -> [u8; N]) -> Vec<u8> {
+        values.iter().flat_map(|&v| to_bytes(v)).collect()
+    }
+
+    #[test]
+    fn from_npy_dtypes_and_fortran_order() {
+        // [[1, 2, 3], [4, 5, 6]] を Fortran order の f8 で保存したもの
+        let features = npy_bytes("<f8", true, "(2, 3)", &le_bytes(&[1.0f64, 4.0, 2.0, 5.0, 3.0, 6.0], f64::to_le_bytes));
+        let labels = npy_bytes("<i8", false, "(2,)", &le_bytes(&[7i64, 300], i64::to_le_bytes));
+        let (features_path, labels_path) = (temp_path("features.npy"), temp_path("labels.npy"));
+        std::fs::write(&features_path, features).unwrap();
+        std::fs::write(&labels_path, labels).unwrap();
+        let dataset = DataSet::from_npy(&features_path, &labels_path);
+
+        // big-endian の f4、3 次元の特徴量と実数のラベル
+        let features = npy_bytes(">f4", false, "(2, 1, 2)", &[1.0f32, -2.0, 0.5, 8.0].iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<_>>());
+        let labels = npy_bytes("<f4", false, "(2,)", &le_bytes(&[0.25f32, -1.0], f32::to_le_bytes));
+        std::fs::write(&features_path, features).unwrap();
+        std::fs::write(&labels_path, labels).unwrap();
+        let dense = DataSet::from_npy(&features_path, &labels_path);
+        std::fs::remove_file(&features_path).unwrap();
+        std::fs::remove_file(&labels_path).unwrap();
+
+        let dataset = dataset.unwrap();
+        assert_eq!(dataset.images, Tensor::new(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], vec![2, 3]));
+        assert_eq!(dataset.targets, Targets::Classes(vec![7, 300]));
+        let dense = dense.unwrap();
+        assert_eq!(dense.images, Tensor::new(vec![1.0, -2.0, 0.5, 8.0], vec![2, 2]));
+        assert_eq!(dense.targets, Targets::Dense(Tensor::new(vec![0.25, -1.0], vec![2, 1])));
+    }
+
+    #[test]
+    fn parse_npy_rejects_malformed_input() {
+        let data = le_bytes(&[1.0f32, 2.0], f32::to_le_bytes);
+        let cases = [
+            (b"not numpy".to_vec(), "missing \\x93NUMPY magic"),
+            (npy_bytes("<f4", false, "(3,)", &data), "expected 3 elements of 4 bytes"),
+            (npy_bytes("<c8", false, "(2,)", &data), "unsupported dtype \"<c8\""),
+            (npy_bytes("<f4", false, "(2, x)", &data), "invalid dimension \"x\""),
+            (npy_bytes("<f4", false, "2", &data), "shape 2 is not a tuple"),
+            (b"\x93NUMPY\x01\x00\xff\x00{'descr'".to_vec(), "header of 255 bytes is truncated"),
+        ];
+        for (bytes, expected) in cases {
+            let error = parse_npy(&bytes, "test.npy").unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+            assert!(error.to_string().contains(expected), "expected {:?} in {}", expected, error);
+        }
+        let mut no_shape = npy_bytes("<f4", false, "(2,)", &data);
+        let start = no_shape.windows(7).position(|w| w == b"'shape'").unwrap();
+        no_shape[start + 1..start + 6].copy_from_slice(b"SHAPE");
+        assert!(parse_npy(&no_shape, "test.npy").unwrap_err().to_string().contains("header has no \"shape\""));
+    }
+
+    #[test]
+    fn from_npz_reads_named_arrays() {
+        use zip::write::FileOptions;
+        let path = temp_path("data.npz");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        let compressed = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        writer.start_file("x.npy", compressed).unwrap();
+        writer.write_all(&npy_bytes("|u1", false, "(2, 2)", &[0, 255, 128, 1])).unwrap();
+        writer.start_file("y.npy", FileOptions::default().compression_method(zip::CompressionMethod::Stored)).unwrap();
+        writer.write_all(&npy_bytes("|u1", false, "(2,)", &[3, 1])).unwrap();
+        writer.finish().unwrap();
+
+        let dataset = DataSet::from_npz(&path, "x", "y");
+        let missing = DataSet::from_npz(&path, "x", "labels");
+        std::fs::remove_file(&path).unwrap();
+
+        let dataset = dataset.unwrap();
+        assert_eq!(dataset.images, Tensor::new(vec![0.0, 255.0, 128.0, 1.0], vec![2, 2]));
+        assert_eq!(dataset.targets, Targets::Classes(vec![3, 1]));
+        let error = missing.err().unwrap().to_string();
+        assert!(error.contains("no array \"labels\"") && error.contains("\"x\", \"y\""), "{}", error);
+    }
+}
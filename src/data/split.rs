@@ -0,0 +1,240 @@
+// src/data/split.rs
+use std::collections::BTreeMap;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::data::{DataSet, Targets};
+
+/// DataSet::split_with / train_val_test_split / k_fold の設定
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SplitOptions {
+    /// 並べ替えに使う乱数の seed（既定値は 0）
+    pub seed: u64,
+    /// クラスごとに分けて、どの部分もクラスの割合が全体と同じになるようにする（正解はクラス番号）
+    pub stratified: bool,
+}
+
+impl SplitOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn stratified(mut self, stratified: bool) -> Self {
+        self.stratified = stratified;
+        self
+    }
+}
+
+impl DataSet {
+    /// indices の順に取り出した DataSet（class_names はそのまま）
+    pub fn subset(&self, indices: &[usize]) -> DataSet {
+        DataSet::new(self.images.select_rows(indices), self.targets.select(indices))
+            .with_class_names(self.class_names.clone())
+    }
+
+    /// 並べ替えてから約 ratio と残りに分ける
+    ///
+    /// stratified ならクラスごとに round(クラスの数 * ratio) 個ずつ取るので、合計は round(num_samples * ratio) と少しずれることがある。
+    pub fn split_with(&self, ratio: f32, options: &SplitOptions) -> (DataSet, DataSet) {
+        let [first, rest] = split_indices(&self.targets, [ratio], options);
+        (self.subset(&first), self.subset(&rest))
+    }
+
+    /// (train, validation, test) に分ける（test は 1 - train_ratio - val_ratio）
+    pub fn train_val_test_split(&self, train_ratio: f32, val_ratio: f32, options: &SplitOptions) -> (DataSet, DataSet, DataSet) {
+        let [train, val, test] = split_indices(&self.targets, [train_ratio, val_ratio], options);
+        (self.subset(&train), self.subset(&val), self.subset(&test))
+    }
+
+    /// k 分割交差検証: 各 fold を 1 回ずつ検証に使い、(train, validation) を k 回返す
+    pub fn k_fold(&self, k: usize, options: &SplitOptions) -> KFold<'_> {
+        assert!(k >= 2 && k <= self.num_samples(), "k must be in [2, {}], got {}", self.num_samples(), k);
+        KFold { dataset: self, folds: fold_indices(&self.targets, k, options), next: 0 }
+    }
+}
+
+/// DataSet::k_fold が返すイテレータ
+pub struct KFold<'a> {
+    dataset: &'a DataSet,
+    folds: Vec<Vec<usize>>,
+    next: usize,
+}
+
+impl KFold<'_> {
+    /// 各 fold の index（検証に使う順）
+    pub fn folds(&self) -> &[Vec<usize>] {
+        &self.folds
+    }
+}
+
+impl Iterator for KFold<'_> {
+    type Item = (DataSet, DataSet);
+
+    fn next(&mut self) -> Option<(DataSet, DataSet)> {
+        let validation = self.folds.get(self.next)?;
+        let train: Vec<usize> = self.folds.iter().enumerate()
+            .filter(|&(fold, _)| fold != self.next)
+            .flat_map(|(_, indices)| indices.iter().copied())
+            .collect();
+        self.next += 1;
+        Some((self.dataset.subset(&train), self.dataset.subset(validation)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.folds.len() - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for KFold<'_> {}
+
+/// 並べ替えた index のグループ（stratified ならクラスごと、そうでなければ全体で 1 つ）
+fn shuffled_groups(targets: &Targets, options: &SplitOptions, rng: &mut StdRng) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = if options.stratified {
+        let Targets::Classes(classes) = targets else {
+            panic!("stratified split needs class targets");
+        };
+        let mut by_class: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+        for (index, &class) in classes.iter().enumerate() {
+            by_class.entry(class).or_default().push(index);
+        }
+        by_class.into_values().collect()
+    } else {
+        vec![(0..targets.len()).collect()]
+    };
+    for group in &mut groups {
+        group.shuffle(rng);
+    }
+    groups
+}
+
+/// ratios の割合で N 個に分け、残りを最後に入れる
+fn split_indices<const N: usize, const M: usize>(targets: &Targets, ratios: [f32; N], options: &SplitOptions) -> [Vec<usize>; M] {
+    assert_eq!(M, N + 1);
+    assert!(ratios.iter().all(|r| (0.0..=1.0).contains(r)), "split ratios must be in [0, 1], got {:?}", ratios);
+    assert!(ratios.iter().sum::<f32>() <= 1.0 + 1e-6, "split ratios must sum to at most 1, got {:?}", ratios);
+
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut parts: [Vec<usize>; M] = std::array::from_fn(|_| Vec::new());
+    for group in shuffled_groups(targets, options, &mut rng) {
+        // 累積の割合で切るので、丸めの誤差がたまらない
+        let mut start = 0;
+        let mut cumulative = 0.0;
+        for (part, &ratio) in parts.iter_mut().zip(&ratios) {
+            cumulative += ratio as f64;
+            let end = ((group.len() as f64 * cumulative).round() as usize).clamp(start, group.len());
+            part.extend_from_slice(&group[start..end]);
+            start = end;
+        }
+        parts[N].extend_from_slice(&group[start..]);
+    }
+    if options.stratified {
+        // クラスの順に並んでいるので混ぜる
+        for part in &mut parts {
+            part.shuffle(&mut rng);
+        }
+    }
+    parts
+}
+
+/// k 個の fold に配る（大きさの差は 1 以内。stratified ならクラスごとでも差は 1 以内）
+fn fold_indices(targets: &Targets, k: usize, options: &SplitOptions) -> Vec<Vec<usize>> {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut folds = vec![Vec::new(); k];
+    let order: Vec<usize> = shuffled_groups(targets, options, &mut rng).into_iter().flatten().collect();
+    // 順番に配るので、グループ（クラス）の境目をまたいでも大きさがそろう
+    for (position, index) in order.into_iter().enumerate() {
+        folds[position % k].push(index);
+    }
+    folds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::tests::indexed_dataset;
+    use crate::tensor::Tensor;
+
+    fn sorted_features(dataset: &DataSet) -> Vec<usize> {
+        let mut values: Vec<usize> = dataset.images.as_slice().iter().map(|&x| x as usize).collect();
+        values.sort();
+        values
+    }
+
+    fn class_counts(dataset: &DataSet) -> [usize; 2] {
+        let Targets::Classes(classes) = &dataset.targets else { panic!("expected classes") };
+        [classes.iter().filter(|&&c| c == 0).count(), classes.iter().filter(|&&c| c == 1).count()]
+    }
+
+    #[test]
+    fn split_with_shuffles_by_seed() {
+        let dataset = indexed_dataset(vec![0; 20]).with_class_names(vec!["a".to_string()]);
+        let options = SplitOptions::new().seed(5);
+        let (train, test) = dataset.split_with(0.75, &options);
+        assert_eq!((train.num_samples(), test.num_samples()), (15, 5));
+        assert_eq!(train.class_names, dataset.class_names);
+        assert_ne!(train.images, dataset.split_dataset(0.75).0.images);
+        let mut all = sorted_features(&train);
+        all.extend(sorted_features(&test));
+        all.sort();
+        assert_eq!(all, (0..20).collect::<Vec<_>>());
+
+        assert_eq!(dataset.split_with(0.75, &options).0.images, train.images);
+        assert_ne!(dataset.split_with(0.75, &options.clone().seed(6)).0.images, train.images);
+    }
+
+    #[test]
+    fn stratified_split_keeps_class_proportions() {
+        // クラス 1 は 5 個に 1 個
+        let dataset = indexed_dataset((0..50).map(|i| (i % 5 == 0) as u32).collect());
+        let options = SplitOptions::new().seed(1).stratified(true);
+        let (train, test) = dataset.split_with(0.8, &options);
+        assert_eq!(class_counts(&train), [32, 8]);
+        assert_eq!(class_counts(&test), [8, 2]);
+
+        let (train, val, test) = dataset.train_val_test_split(0.6, 0.2, &options);
+        assert_eq!(class_counts(&train), [24, 6]);
+        assert_eq!(class_counts(&val), [8, 2]);
+        assert_eq!(class_counts(&test), [8, 2]);
+        let mut all = [sorted_features(&train), sorted_features(&val), sorted_features(&test)].concat();
+        all.sort();
+        assert_eq!(all, (0..50).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic(expected = "stratified split needs class targets")]
+    fn stratified_split_needs_classes() {
+        let dataset = DataSet::new(Tensor::zeros(&[2, 1]), Targets::Dense(Tensor::zeros(&[2, 1])));
+        dataset.split_with(0.5, &SplitOptions::new().stratified(true));
+    }
+
+    #[test]
+    fn k_fold_uses_each_sample_for_validation_once() {
+        let dataset = indexed_dataset((0..10).map(|i| (i < 4) as u32).collect());
+        for stratified in [false, true] {
+            let folds = dataset.k_fold(3, &SplitOptions::new().seed(2).stratified(stratified));
+            assert_eq!(folds.len(), 3);
+            let mut validated = Vec::new();
+            for (train, validation) in folds {
+                assert!((3..=4).contains(&validation.num_samples()));
+                assert_eq!(train.num_samples() + validation.num_samples(), 10);
+                let train_features = sorted_features(&train);
+                assert!(sorted_features(&validation).iter().all(|i| !train_features.contains(i)));
+                if stratified {
+                    // クラス 1 の 4 個は 1 / 1 / 2 に分かれる
+                    assert!((1..=2).contains(&class_counts(&validation)[1]));
+                }
+                validated.extend(sorted_features(&validation));
+            }
+            validated.sort();
+            assert_eq!(validated, (0..10).collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,118 @@
+// src/data/streaming.rs
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::data::{Dataset, Sample};
+
+type SampleStream = Box<dyn Iterator<Item = Sample> + Send>;
+
+struct StreamState {
+    stream: SampleStream,
+    /// buffer の先頭のサンプルの index
+    start: usize,
+    buffer: VecDeque<Sample>,
+}
+
+/// イテレータから順番にサンプルを読むデータセット
+///
+/// 直近の buffer_size 個だけを保持する。前に戻る index を要求されたら、open でストリームを開き直して
+/// 先頭から読み直すので、順番に読むときに使う。
+/// Trainer で使うときは `trainer.train_loader.set_sampling(Sampling::Sequential)` にする
+/// （既定の `Sampling::Shuffle` だとランダムな index を読むたびに先頭から読み直す）。
+/// 件数は事前に分かっていなければならない（ストリームが早く終わったら panic する）。
+pub struct StreamingDataset {
+    open: Box<dyn Fn() -> SampleStream + Send + Sync>,
+    len: usize,
+    feature_shape: Vec<usize>,
+    buffer_size: usize,
+    state: Mutex<StreamState>,
+}
+
+impl StreamingDataset {
+    /// open はストリームを先頭から開く関数（ファイルを開き直すなど）
+    pub fn new<F, I>(len: usize, feature_shape: Vec<usize>, open: F) -> Self
+    where
+        F: Fn() -> I + Send + Sync + 'static,
+        I: Iterator<Item = Sample> + Send + 'static,
+    {
+        let open: Box<dyn Fn() -> SampleStream + Send + Sync> = Box::new(move || Box::new(open()));
+        let state = StreamState { stream: open(), start: 0, buffer: VecDeque::new() };
+        Self { open, len, feature_shape, buffer_size: 1024, state: Mutex::new(state) }
+    }
+
+    /// 保持するサンプルの数（既定値 1024、バッチサイズ以上にする）
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        assert!(buffer_size > 0, "buffer_size must be positive");
+        self.buffer_size = buffer_size;
+        self
+    }
+}
+
+impl Dataset for StreamingDataset {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn feature_shape(&self) -> Vec<usize> {
+        self.feature_shape.clone()
+    }
+
+    fn get(&self, index: usize) -> Sample {
+        assert!(index < self.len, "index {} is out of range for {} samples", index, self.len);
+        let mut state = self.state.lock().unwrap();
+        if index < state.start {
+            *state = StreamState { stream: (self.open)(), start: 0, buffer: VecDeque::new() };
+        }
+        while state.start + state.buffer.len() <= index {
+            let position = state.start + state.buffer.len();
+            let sample = state.stream.next()
+                .unwrap_or_else(|| panic!("stream ended after {} samples, but the dataset has {}", position, self.len));
+            state.buffer.push_back(sample);
+            if state.buffer.len() > self.buffer_size {
+                state.buffer.pop_front();
+                state.start += 1;
+            }
+        }
+        let offset = index - state.start;
+        state.buffer[offset].clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Target, Targets};
+    use crate::tensor::Tensor;
+
+    #[test]
+    fn streaming_dataset_reopens_only_when_going_back() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let opened = Arc::new(AtomicUsize::new(0));
+        let counter = opened.clone();
+        let dataset = StreamingDataset::new(10, vec![2], move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            (0..10u32).map(|i| Sample { features: vec![i as f32, -(i as f32)], target: Target::Class(i % 3) })
+        }).buffer_size(4);
+
+        let (inputs, targets) = dataset.batch(&[0, 1, 2, 3]);
+        assert_eq!(inputs, Tensor::new(vec![0.0, -0.0, 1.0, -1.0, 2.0, -2.0, 3.0, -3.0], vec![4, 2]));
+        assert_eq!(targets, Targets::Classes(vec![0, 1, 2, 0]));
+        assert_eq!(dataset.get(9).features, vec![9.0, -9.0]);
+        // 直近 4 個（6..=9）は保持しているので開き直さない
+        assert_eq!(dataset.get(6).target, Target::Class(0));
+        assert_eq!(opened.load(Ordering::SeqCst), 1);
+        assert_eq!(dataset.get(2).features, vec![2.0, -2.0]);
+        assert_eq!(opened.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "stream ended after 3 samples, but the dataset has 5")]
+    fn streaming_dataset_panics_when_stream_is_short() {
+        let dataset = StreamingDataset::new(5, vec![1], || {
+            (0..3).map(|i| Sample { features: vec![i as f32], target: Target::Dense(vec![0.0]) })
+        });
+        dataset.get(4);
+    }
+}
@@ -0,0 +1,331 @@
+//! 中心差分による勾配チェック
+//!
+//! レイヤーや損失関数の backward（や autograd のテープ）が返す解析的な勾配を、
+//! (f(x + ε) - f(x - ε)) / 2ε で求めた数値微分と比較する。
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::layers::base_layer::AbstractLayerTrait;
+use crate::losses::base_loss::AbstractLossFunctionTrait;
+use crate::tensor::Tensor;
+
+#[derive(Debug, Clone, Copy)]
+pub struct GradCheckOptions {
+    /// 中心差分の刻み幅
+    pub epsilon: f32,
+    /// |analytical - numerical| <= atol + rtol * max(|analytical|, |numerical|) なら一致とみなす
+    pub atol: f32,
+    pub rtol: f32,
+    /// レイヤーの出力を重み付けする乱数の seed
+    pub seed: u64,
+}
+
+impl Default for GradCheckOptions {
+    fn default() -> Self {
+        Self { epsilon: 1e-3, atol: 1e-3, rtol: 1e-2, seed: 0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradCheckMismatch {
+    /// "input" / "weights" / "biases" / "y_pred"
+    pub target: &'static str,
+    pub index: usize,
+    pub analytical: f32,
+    pub numerical: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GradCheckReport {
+    pub checked: usize,
+    pub max_abs_error: f32,
+    pub mismatches: Vec<GradCheckMismatch>,
+}
+
+impl GradCheckReport {
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    fn compare(&mut self, target: &'static str, analytical: &[f32], numerical: &[f32], options: &GradCheckOptions) {
+        for (index, (&a, &n)) in analytical.iter().zip(numerical.iter()).enumerate() {
+            let error = (a - n).abs();
+            self.checked += 1;
+            self.max_abs_error = self.max_abs_error.max(error);
+            if error > options.atol + options.rtol * a.abs().max(n.abs()) {
+                self.mismatches.push(GradCheckMismatch { target, index, analytical: a, numerical: n });
+            }
+        }
+    }
+}
+
+/// 1 要素だけ delta を持つテンソル
+fn one_hot_delta(shape: &[usize], index: usize, delta: f32) -> Tensor {
+    let mut t = Tensor::zeros(shape);
+    t.as_mut_slice()[index] = delta;
+    t
+}
+
+/// f の入力 x に対する数値微分を中心差分で求める
+fn numerical_gradient<F: FnMut(&Tensor) -> f64>(x: &Tensor, epsilon: f32, mut f: F) -> Vec<f32> {
+    let mut grad = Vec::with_capacity(x.len());
+    let mut x = x.contiguous();
+    for i in 0..x.len() {
+        let original = x.as_slice()[i];
+        x.as_mut_slice()[i] = original + epsilon;
+        let plus = f(&x);
+        x.as_mut_slice()[i] = original - epsilon;
+        let minus = f(&x);
+        x.as_mut_slice()[i] = original;
+        grad.push(((plus - minus) / (2.0 * epsilon as f64)) as f32);
+    }
+    grad
+}
+
+/// L = Σ r ⊙ layer(x)（r は seed で固定した乱数）として、
+/// 入力・重み・バイアスに対する勾配を数値微分と比較する
+pub fn check_layer(layer: &mut dyn AbstractLayerTrait, x: &Tensor, options: &GradCheckOptions) -> GradCheckReport {
+    let mut report = GradCheckReport::default();
+
+    // 出力の重み r を作る
+    let output_shape = layer.forward(x).shape().to_vec();
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let r_data: Vec<f32> = (0..output_shape.iter().product::<usize>())
+        .map(|_| rng.gen_range(-1.0..1.0))
+        .collect();
+    let r = Tensor::new(r_data, output_shape);
+
+    let objective = |layer: &mut dyn AbstractLayerTrait, x: &Tensor| -> f64 {
+        let y = layer.forward(x);
+        y.as_slice().iter().zip(r.as_slice()).map(|(&y, &r)| y as f64 * r as f64).sum()
+    };
+
+    // 解析的な勾配
+    layer.zero_grad();
+    layer.forward(x);
+    let grad_input = layer.backward(&r);
+    let grad_w = layer.grad_w().map(|g| g.to_vec()).unwrap_or_default();
+    let grad_b = layer.grad_b().map(|g| g.to_vec()).unwrap_or_default();
+
+    // 入力に対する数値微分
+    let numerical = numerical_gradient(x, options.epsilon, |x| objective(layer, x));
+    report.compare("input", grad_input.as_slice(), &numerical, options);
+
+    // 重みに対する数値微分（1 要素ずつずらして元に戻す）
+    // パラメータを持たないレイヤーでは grad_w / grad_b が空なので何もしない
+    let w_shape = layer.w().map(|w| w.shape().to_vec()).unwrap_or_default();
+    let mut numerical = Vec::with_capacity(grad_w.len());
+    for i in 0..grad_w.len() {
+        layer.update_weights(&one_hot_delta(&w_shape, i, options.epsilon));
+        let plus = objective(layer, x);
+        layer.update_weights(&one_hot_delta(&w_shape, i, -2.0 * options.epsilon));
+        let minus = objective(layer, x);
+        layer.update_weights(&one_hot_delta(&w_shape, i, options.epsilon));
+        numerical.push(((plus - minus) / (2.0 * options.epsilon as f64)) as f32);
+    }
+    report.compare("weights", &grad_w, &numerical, options);
+
+    // バイアスに対する数値微分
+    let b_shape = layer.b().map(|b| b.shape().to_vec()).unwrap_or_default();
+    let mut numerical = Vec::with_capacity(grad_b.len());
+    for i in 0..grad_b.len() {
+        layer.update_biases(&one_hot_delta(&b_shape, i, options.epsilon));
+        let plus = objective(layer, x);
+        layer.update_biases(&one_hot_delta(&b_shape, i, -2.0 * options.epsilon));
+        let minus = objective(layer, x);
+        layer.update_biases(&one_hot_delta(&b_shape, i, options.epsilon));
+        numerical.push(((plus - minus) / (2.0 * options.epsilon as f64)) as f32);
+    }
+    report.compare("biases", &grad_b, &numerical, options);
+
+    report
+}
+
+/// 損失関数の y_pred に対する勾配を数値微分と比較する
+/// （Reduction::None ではサンプルごとの損失の和と比べる。各サンプルの損失は自分の行にしか依存しない）
+pub fn check_loss(loss: &dyn AbstractLossFunctionTrait, y_true: &Tensor, y_pred: &Tensor, options: &GradCheckOptions) -> GradCheckReport {
+    let mut report = GradCheckReport::default();
+    let analytical = loss.backward(y_true, y_pred);
+    let numerical = numerical_gradient(y_pred, options.epsilon, |y_pred| loss.forward(y_true, y_pred).sum() as f64);
+    report.compare("y_pred", analytical.as_slice(), &numerical, options);
+    report
+}
+
+/// スカラー関数 f の x に対する勾配 analytical（autograd などで求めたもの）を数値微分と比較する
+pub fn check_function<F: FnMut(&Tensor) -> f32>(x: &Tensor, analytical: &Tensor, options: &GradCheckOptions, mut f: F) -> GradCheckReport {
+    let mut report = GradCheckReport::default();
+    assert_eq!(analytical.shape(), x.shape(), "gradient shape does not match the input");
+    let numerical = numerical_gradient(x, options.epsilon, |x| f(x) as f64);
+    report.compare("input", analytical.contiguous().as_slice(), &numerical, options);
+    report
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::autograd::functional;
+    use crate::autograd::tape::Tape;
+    use crate::layers::conv2d_layer::{Conv2dLayer, Conv2dParams};
+    use crate::layers::fc_layer::FcLayer;
+    use crate::layers::softmax_layer::SoftmaxLayer;
+    use crate::losses::base_loss::Reduction;
+    use crate::losses::cross_entropy_loss::CrossEntropyLoss;
+    use crate::losses::softmax_cross_entropy_loss::SoftmaxCrossEntropyLoss;
+    use crate::model::Model;
+
+    pub(crate) fn random_tensor(shape: &[usize], seed: u64) -> Tensor {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let numel = shape.iter().product();
+        Tensor::new((0..numel).map(|_| rng.gen_range(-1.0..1.0)).collect(), shape.to_vec())
+    }
+
+    pub(crate) fn assert_passed(report: &GradCheckReport) {
+        assert!(report.checked > 0, "nothing was checked");
+        assert!(report.passed(), "gradient mismatch: {:?}", report.mismatches);
+    }
+
+    pub(crate) fn fc_layer(activation_type: &str) -> FcLayer {
+        // 既定の初期化 N(0, 0.01) だと出力がほぼ 0 になるので、大きめの重みで確認する
+        let w = random_tensor(&[4, 5], 1);
+        let b = random_tensor(&[4], 2);
+        FcLayer::from_parameters("fc".to_string(), 5, 4, activation_type.to_string(), w.into_vec(), b.into_vec())
+    }
+
+    /// 全ての reduction で勾配を確認し、Mean / Sum / None と forward_per_sample の関係も確かめる
+    /// （ロジットを受け取る損失は f32 の丸め誤差が大きいので、epsilon を大きめにする）
+    pub(crate) fn check_loss_reductions<L: AbstractLossFunctionTrait>(make: impl Fn(Reduction) -> L, y_true: &Tensor, y_pred: &Tensor, epsilon: f32) {
+        let options = GradCheckOptions { epsilon, ..GradCheckOptions::default() };
+        for reduction in [Reduction::Mean, Reduction::Sum, Reduction::None] {
+            assert_passed(&check_loss(&make(reduction), y_true, y_pred, &options));
+        }
+        let batch_size = y_pred.dim(0);
+        let per_sample = make(Reduction::Mean).forward_per_sample(y_true, y_pred);
+        assert_eq!(per_sample.shape(), &[batch_size]);
+        assert!((make(Reduction::Mean).forward(y_true, y_pred).item() - per_sample.mean()).abs() < 1e-5);
+        assert!((make(Reduction::Sum).forward(y_true, y_pred).item() - per_sample.sum()).abs() < 1e-5);
+
+        // None は和を取らずにサンプルごとの損失 [batch_size] を返す
+        let unreduced = make(Reduction::None);
+        assert_eq!(unreduced.forward(y_true, y_pred), per_sample);
+        // backward の i 行目はサンプル i の損失だけに対する勾配（他の行の数値微分は 0）
+        let grad = unreduced.backward(y_true, y_pred).flatten_batch();
+        let row_len = grad.dim(1);
+        for i in 0..batch_size {
+            let mut row_grad = Tensor::zeros(grad.shape());
+            row_grad.as_mut_slice()[i * row_len..(i + 1) * row_len].copy_from_slice(grad.row(i));
+            let report = check_function(y_pred, &row_grad.into_reshape(y_pred.shape()), &options, |y_pred| {
+                unreduced.forward(y_true, y_pred).as_slice()[i]
+            });
+            assert_passed(&report);
+        }
+    }
+
+    #[test]
+    fn conv2d_layer_after_fc_layer() {
+        // FcLayer の出力は [batch, C * H * W] なので、勾配もその shape で返す
+        let fc = FcLayer::from_parameters(
+            "fc".to_string(), 4, 32, "identity".to_string(),
+            random_tensor(&[32, 4], 16).into_vec(), random_tensor(&[32], 17).into_vec(),
+        );
+        let conv = Conv2dLayer::from_parameters(
+            "conv".to_string(), Conv2dParams::new(2, 3, 4, 4),
+            random_tensor(&[3 * 2 * 3 * 3], 8).into_vec(), random_tensor(&[3], 9).into_vec(),
+        );
+        let mut model = Model::new(vec![Box::new(fc), Box::new(conv)]);
+        let x = random_tensor(&[3, 4], 19);
+        let y = model.forward(&x);
+        assert_eq!(y.shape(), &[3, 3, 2, 2]);
+
+        // L = Σ r ⊙ model(x) の x に対する勾配を、レイヤーの backward をつないで求める
+        let r = random_tensor(y.shape(), 20);
+        model.backward(&r);
+        let grad_conv = model.layers[1].backward(&r);
+        let grad_x = model.layers[0].backward(&grad_conv);
+        assert_eq!(grad_x.shape(), &[3, 4]);
+        let report = check_function(&x, &grad_x, &GradCheckOptions::default(), |x| (&model.forward(x) * &r).sum());
+        assert_passed(&report);
+    }
+
+    #[test]
+    fn detects_wrong_gradient() {
+        // 勾配の符号を反転させた損失関数は検出されなければならない
+        struct WrongSign(CrossEntropyLoss);
+        impl AbstractLossFunctionTrait for WrongSign {
+            fn forward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+                self.0.forward(y_true, y_pred)
+            }
+            fn backward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+                self.0.backward(y_true, y_pred).scale(-1.0)
+            }
+            fn forward_per_sample(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+                self.0.forward_per_sample(y_true, y_pred)
+            }
+            fn name(&self) -> &str {
+                "wrong_sign"
+            }
+            fn build(&mut self) {}
+        }
+
+        let loss = WrongSign(CrossEntropyLoss::new("cross_entropy_loss".to_string()));
+        let y_true = Tensor::new(vec![0.0, 1.0, 0.0], vec![1, 3]);
+        let y_pred = Tensor::new(vec![0.2, 0.5, 0.3], vec![1, 3]);
+        assert!(!check_loss(&loss, &y_true, &y_pred, &GradCheckOptions::default()).passed());
+    }
+
+    fn assert_close(a: &Tensor, b: &Tensor, tolerance: f32) {
+        assert_eq!(a.shape(), b.shape());
+        for (i, (&a, &b)) in a.contiguous().as_slice().iter().zip(b.contiguous().as_slice()).enumerate() {
+            assert!((a - b).abs() <= tolerance, "index {}: {} vs {}", i, a, b);
+        }
+    }
+
+    #[test]
+    fn autograd_matches_hand_written_layers() {
+        let x = random_tensor(&[3, 5], 30);
+        let y_true = SoftmaxCrossEntropyLoss::one_hot(&[1, 3, 0], 4);
+
+        // 手書きの backward
+        let mut fc = fc_layer("relu");
+        let mut softmax = SoftmaxLayer::new("softmax".to_string(), 4, 4);
+        let loss_function = CrossEntropyLoss::new("ce".to_string());
+        let probabilities = softmax.forward(&fc.forward(&x));
+        let loss = loss_function.forward(&y_true, &probabilities).item();
+        let grad_x = fc.backward(&softmax.backward(&loss_function.backward(&y_true, &probabilities)));
+
+        // 同じ重みをテープに載せる
+        let tape = Tape::new();
+        let x_var = tape.var(x.clone());
+        let (h, w, b) = functional::fc_layer(&fc, x_var);
+        let tape_loss = functional::cross_entropy(tape.constant(y_true), functional::softmax(h));
+        let grads = tape.backward(tape_loss);
+
+        assert!((tape_loss.value().as_slice()[0] - loss).abs() < 1e-5);
+        assert_close(grads.wrt(x_var).unwrap(), &grad_x, 1e-5);
+        assert_close(grads.wrt(w).unwrap(), fc.grad_w().unwrap(), 1e-5);
+        assert_close(grads.wrt(b).unwrap(), fc.grad_b().unwrap(), 1e-5);
+    }
+
+    /// a は matmul と sum の 2 か所、y は足し算と掛け算の 2 か所で使う（勾配が足し合わされる）
+    fn composed_ops(a: &Tensor, c: &Tensor) -> (f32, Tensor, Tensor) {
+        let tape = Tape::new();
+        let (a, c) = (tape.var(a.clone()), tape.var(c.clone()));
+        let b = tape.constant(random_tensor(&[4, 2], 31));
+        let y = a.matmul(b);
+        let z = ((y + c) * y).sum_axis(1, true).sigmoid();
+        let normalizer = a.exp().sum_axis(1, true);
+        let output = (z / normalizer).mean() + a.sum().scale(0.1) - c.sum_axis(0, false).sum() * (-c).exp().mean();
+        let grads = tape.backward(output);
+        (output.value().as_slice()[0], grads.wrt(a).unwrap().clone(), grads.wrt(c).unwrap().clone())
+    }
+
+    #[test]
+    fn autograd_composed_ops() {
+        let a = random_tensor(&[3, 4], 32);
+        let c = random_tensor(&[2], 33);
+        let (_, grad_a, grad_c) = composed_ops(&a, &c);
+        let options = GradCheckOptions::default();
+        assert_passed(&check_function(&a, &grad_a, &options, |a| composed_ops(a, &c).0));
+        assert_passed(&check_function(&c, &grad_c, &options, |c| composed_ops(&a, c).0));
+    }
+}
@@ -1,4 +1,13 @@
+pub mod avg_pool2d_layer;
 pub mod base_layer;
+pub mod batch_norm_layer;
+pub mod conv2d_layer;
+pub mod dropout_layer;
 pub mod fc_layer;
+pub mod global_avg_pool_layer;
+pub mod layer_norm_layer;
+pub mod max_pool2d_layer;
+pub mod pooling;
+pub mod rms_norm_layer;
 pub mod softmax_layer;
-pub mod utils;
\ No newline at end of file
+pub mod utils;
@@ -0,0 +1,131 @@
+use crate::checkpoint::LayerState;
+use crate::layers::base_layer::{AbstractLayer, AbstractLayerTrait};
+use crate::layers::pooling::Pool2dParams;
+use crate::tensor::Tensor;
+
+/// 2 次元の平均値プーリング
+///
+/// 入力は [batch, channels, H, W]、出力は [batch, channels, H_out, W_out]。
+/// パディング部分は 0 として扱い、常に kh * kw で割る。
+#[derive(Debug)]
+pub struct AvgPool2dLayer {
+    base: AbstractLayer,
+    params: Pool2dParams,
+    // forward に渡された入力の shape（backward で勾配を同じ shape に戻す）
+    input_shape: Vec<usize>,
+}
+
+impl AvgPool2dLayer {
+    pub fn new(name: String, params: Pool2dParams) -> Self {
+        if let Err(reason) = params.validate() {
+            panic!("invalid AvgPool2dLayer {}: {}", name, reason);
+        }
+        Self {
+            input_shape: vec![1, params.channels, params.input_height, params.input_width],
+            base: AbstractLayer::without_parameters(name, params.i_size(), params.o_size(), "avg_pool".to_string()),
+            params,
+        }
+    }
+
+    pub fn params(&self) -> &Pool2dParams {
+        &self.params
+    }
+
+    fn kernel_area(&self) -> f32 {
+        (self.params.kernel_size.0 * self.params.kernel_size.1) as f32
+    }
+}
+
+impl AbstractLayerTrait for AvgPool2dLayer {
+    fn forward(&mut self, x: &Tensor) -> Tensor {
+        let p = &self.params;
+        let batch_size = x.dim(0);
+        self.input_shape = x.shape().to_vec();
+        assert_eq!(x.len(), batch_size * self.base.i_size, "AvgPool2dLayer {} got input of shape {:?}", self.base.name, x.shape());
+        let x = x.reshape(&[batch_size, p.channels, p.input_height, p.input_width]);
+        let (out_h, out_w) = p.output_size();
+        let area = self.kernel_area();
+
+        let mut output = vec![0.0; batch_size * self.base.o_size];
+        for (sample, out) in x.rows().zip(output.chunks_exact_mut(self.base.o_size)) {
+            p.for_each_window(|o, window| {
+                out[o] = window.iter().map(|&i| sample[i]).sum::<f32>() / area;
+            });
+        }
+
+        self.base.last_input = x;
+        Tensor::new(output, vec![batch_size, p.channels, out_h, out_w])
+    }
+
+    fn backward(&mut self, grad_output: &Tensor) -> Tensor {
+        let area = self.kernel_area();
+        let grad_output = grad_output.contiguous();
+        let mut grad_input = Tensor::zeros(self.base.last_input.shape());
+        for (grad, g) in grad_input.as_mut_slice()
+            .chunks_exact_mut(self.base.i_size)
+            .zip(grad_output.as_slice().chunks_exact(self.base.o_size))
+        {
+            // 窓の各要素に g / (kh * kw) ずつ配る
+            self.params.for_each_window(|o, window| {
+                for &i in window {
+                    grad[i] += g[o] / area;
+                }
+            });
+        }
+        grad_input.into_reshape(&self.input_shape)
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn i_size(&self) -> usize {
+        self.base.i_size
+    }
+
+    fn o_size(&self) -> usize {
+        self.base.o_size
+    }
+
+    fn activation_type(&self) -> &str {
+        &self.base.activation_type
+    }
+
+    fn state(&self) -> LayerState {
+        let p = &self.params;
+        LayerState::AvgPool2d {
+            name: self.base.name.clone(),
+            channels: p.channels,
+            input_height: p.input_height,
+            input_width: p.input_width,
+            kernel_size: p.kernel_size,
+            stride: p.stride,
+            padding: p.padding,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::{check_layer, GradCheckOptions};
+    use crate::gradcheck::tests::{assert_passed, random_tensor};
+
+    #[test]
+    fn avg_pool2d_layer() {
+        let params = Pool2dParams::new(2, 5, 5, (3, 3)).stride((2, 2)).padding((1, 1));
+        let mut layer = AvgPool2dLayer::new("avg_pool".to_string(), params);
+        let x = random_tensor(&[2, 2, 5, 5], 13);
+        assert_eq!(layer.forward(&x).shape(), &[2, 2, 3, 3]);
+        assert_passed(&check_layer(&mut layer, &x, &GradCheckOptions::default()));
+    }
+
+    #[test]
+    fn avg_pool2d_layer_accepts_flat_input() {
+        // [batch, C * H * W] の入力には同じ shape の勾配を返す
+        let mut layer = AvgPool2dLayer::new("avg_pool".to_string(), Pool2dParams::new(2, 4, 4, (2, 2)).stride((2, 2)));
+        let x = random_tensor(&[3, 2 * 4 * 4], 20);
+        assert_passed(&check_layer(&mut layer, &x, &GradCheckOptions::default()));
+        assert_eq!(layer.backward(&Tensor::ones(&[3, 2, 2, 2])).shape(), &[3, 32]);
+    }
+}
@@ -1,48 +1,84 @@
+use crate::checkpoint::LayerState;
+use crate::tensor::Tensor;
+
+/// レイヤーの共通インターフェース
+///
+/// パラメータを持たないレイヤー（softmax やプーリング）はパラメータ関連のメソッドを
+/// 実装しなくてよい。既定では w / b / 勾配は None、更新は何もしない。
 pub trait AbstractLayerTrait {
-    fn forward(&mut self, x: &[f32]) -> Vec<f32>;
-    fn backward(&mut self, grad_output: &[f32]) -> Vec<f32>;
+    /// x は先頭の軸をバッチとするテンソル（[batch_size, ...]）
+    fn forward(&mut self, x: &Tensor) -> Tensor;
+    /// grad_output は forward の出力と同じ shape、戻り値は入力に対する勾配
+    /// パラメータの勾配は grad_w / grad_b に加算される（zero_grad() でクリアする）
+    fn backward(&mut self, grad_output: &Tensor) -> Tensor;
     fn name(&self) -> &str;
     fn i_size(&self) -> usize;
     fn o_size(&self) -> usize;
-    fn w(&self) -> &Vec<f32>;
-    fn b(&self) -> &Vec<f32>;
-    fn grad_w(&self) -> &Vec<f32>;
-    fn grad_b(&self) -> &Vec<f32>;
-    fn grad_w_mut(&mut self) -> &mut Vec<f32>;
-    fn grad_b_mut(&mut self) -> &mut Vec<f32>;
-    fn update_weights(&mut self, delta_w: &[f32]);
-    fn update_biases(&mut self, delta_b: &[f32]);
     fn activation_type(&self) -> &str;
-    fn build(&mut self);
+    fn state(&self) -> LayerState;
+
+    fn build(&mut self) {}
+    fn zero_grad(&mut self) {}
+    /// 学習モード（true）と推論モード（false）の切り替え。ドロップアウトなど挙動が変わるレイヤーだけ実装する
+    fn set_training(&mut self, _training: bool) {}
+    fn w(&self) -> Option<&Tensor> { None }
+    fn b(&self) -> Option<&Tensor> { None }
+    fn grad_w(&self) -> Option<&Tensor> { None }
+    fn grad_b(&self) -> Option<&Tensor> { None }
+    fn grad_w_mut(&mut self) -> Option<&mut Tensor> { None }
+    fn grad_b_mut(&mut self) -> Option<&mut Tensor> { None }
+    /// w += delta_w（勾配降下なら呼び出し側が -lr * grad_w を渡す）
+    fn update_weights(&mut self, _delta_w: &Tensor) {}
+    /// b += delta_b
+    fn update_biases(&mut self, _delta_b: &Tensor) {}
+
+    fn has_parameters(&self) -> bool {
+        self.w().is_some() || self.b().is_some()
+    }
 }
 
 #[derive(Debug)]
 pub struct AbstractLayer {
     pub name: String,
-    pub w: Vec<f32>,
-    pub b: Vec<f32>,
+    pub w: Tensor,
+    pub b: Tensor,
     pub i_size: usize,
     pub o_size: usize,
     pub activation_type: String,
-    pub last_input: Vec<f32>,
-    pub last_output: Vec<f32>,
-    pub grad_w: Vec<f32>,
-    pub grad_b: Vec<f32>,
+    pub last_input: Tensor,
+    pub last_output: Tensor,
+    pub grad_w: Tensor,
+    pub grad_b: Tensor,
 }
 
 impl AbstractLayer {
     pub fn new(name: String, i_size: usize, o_size: usize, activation_type: String) -> Self {
+        Self::with_parameter_shapes(name, i_size, o_size, activation_type, &[o_size, i_size], &[o_size])
+    }
+
+    /// 重みとバイアスの shape が全結合の [o_size, i_size] / [o_size] と異なるレイヤー用
+    pub fn with_parameter_shapes(name: String, i_size: usize, o_size: usize, activation_type: String, w_shape: &[usize], b_shape: &[usize]) -> Self {
         Self {
             name,
-            w: vec![0.0; i_size * o_size],
-            b: vec![0.0; o_size],
+            w: Tensor::zeros(w_shape),
+            b: Tensor::zeros(b_shape),
             i_size,
             o_size,
             activation_type,
-            last_input: vec![0.0; i_size],
-            last_output: vec![0.0; o_size],
-            grad_w: vec![0.0; i_size * o_size],
-            grad_b: vec![0.0; o_size],
+            last_input: Tensor::zeros(&[1, i_size]),
+            last_output: Tensor::zeros(&[1, o_size]),
+            grad_w: Tensor::zeros(w_shape),
+            grad_b: Tensor::zeros(b_shape),
         }
     }
+
+    /// パラメータを持たないレイヤー用（w / b は空のまま使わない）
+    pub fn without_parameters(name: String, i_size: usize, o_size: usize, activation_type: String) -> Self {
+        Self::with_parameter_shapes(name, i_size, o_size, activation_type, &[0], &[0])
+    }
+
+    pub fn zero_grad(&mut self) {
+        self.grad_w.fill(0.0);
+        self.grad_b.fill(0.0);
+    }
 }
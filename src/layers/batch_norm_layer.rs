@@ -0,0 +1,313 @@
+use crate::checkpoint::LayerState;
+use crate::layers::base_layer::{AbstractLayer, AbstractLayerTrait};
+use crate::tensor::Tensor;
+
+/// BatchNormLayer の設定
+///
+/// `spatial_size` が (1, 1) なら 1 次元（全結合層の出力 [batch, num_features] 用）、
+/// それ以外なら 2 次元（畳み込みの特徴マップ [batch, num_features, H, W] 用）。
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchNormParams {
+    pub num_features: usize,
+    pub spatial_size: (usize, usize),
+    pub momentum: f32,
+    pub epsilon: f32,
+}
+
+impl BatchNormParams {
+    pub fn new(num_features: usize) -> Self {
+        Self {
+            num_features,
+            spatial_size: (1, 1),
+            momentum: 0.1,
+            epsilon: 1e-5,
+        }
+    }
+
+    /// 2 次元（特徴マップの高さと幅）にする
+    pub fn spatial_size(mut self, spatial_size: (usize, usize)) -> Self {
+        self.spatial_size = spatial_size;
+        self
+    }
+
+    /// running_mean = (1 - momentum) * running_mean + momentum * batch_mean
+    pub fn momentum(mut self, momentum: f32) -> Self {
+        self.momentum = momentum;
+        self
+    }
+
+    pub fn epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    fn spatial_len(&self) -> usize {
+        self.spatial_size.0 * self.spatial_size.1
+    }
+}
+
+/// バッチ正規化
+///
+/// チャンネル（特徴量）ごとにバッチ内の平均と分散で正規化し、gamma 倍して beta を足す。
+/// gamma / beta は w / b として持つので、オプティマイザからは通常の重みとして更新される。
+/// 学習モードではバッチの統計量を使って running_mean / running_var を更新し、
+/// 推論モードでは running_mean / running_var を使う。
+#[derive(Debug)]
+pub struct BatchNormLayer {
+    base: AbstractLayer,
+    params: BatchNormParams,
+    running_mean: Tensor,
+    running_var: Tensor,
+    training: bool,
+    // backward 用: 正規化後の値 [batch, C, H * W]、1 / sqrt(var + eps) [C]、バッチの統計量を使ったか
+    x_hat: Tensor,
+    inv_std: Tensor,
+    used_batch_stats: bool,
+    input_shape: Vec<usize>,
+}
+
+/// [batch, C, S] をチャンネルごとに足し合わせて [C] にする
+fn channel_sum(x: &Tensor) -> Tensor {
+    x.sum_axis(2).sum_axis(0)
+}
+
+impl BatchNormLayer {
+    pub fn new(name: String, params: BatchNormParams) -> Self {
+        assert!(params.num_features > 0, "BatchNormLayer {} needs at least one feature", name);
+        assert!(params.spatial_len() > 0, "BatchNormLayer {} needs a non-empty spatial size", name);
+        assert!((0.0..=1.0).contains(&params.momentum), "momentum must be in [0, 1]");
+        assert!(params.epsilon > 0.0, "epsilon must be positive");
+        let size = params.num_features * params.spatial_len();
+        let c = params.num_features;
+        let mut layer = Self {
+            base: AbstractLayer::with_parameter_shapes(name, size, size, "batch_norm".to_string(), &[c], &[c]),
+            params,
+            running_mean: Tensor::zeros(&[c]),
+            running_var: Tensor::ones(&[c]),
+            training: true,
+            x_hat: Tensor::zeros(&[0]),
+            inv_std: Tensor::zeros(&[c]),
+            used_batch_stats: true,
+            input_shape: vec![1, size],
+        };
+        layer.build();
+        layer
+    }
+
+    /// 学習済みのパラメータと統計量からレイヤーを作る
+    pub fn from_parameters(name: String, params: BatchNormParams, gamma: Vec<f32>, beta: Vec<f32>, running_mean: Vec<f32>, running_var: Vec<f32>) -> Self {
+        let mut layer = Self::new(name, params);
+        let c = layer.params.num_features;
+        layer.base.w = Tensor::new(gamma, vec![c]);
+        layer.base.b = Tensor::new(beta, vec![c]);
+        layer.running_mean = Tensor::new(running_mean, vec![c]);
+        layer.running_var = Tensor::new(running_var, vec![c]);
+        layer
+    }
+
+    pub fn params(&self) -> &BatchNormParams {
+        &self.params
+    }
+
+    pub fn running_mean(&self) -> &Tensor {
+        &self.running_mean
+    }
+
+    pub fn running_var(&self) -> &Tensor {
+        &self.running_var
+    }
+
+    /// [C] を [1, C, 1] にしてブロードキャストできるようにする
+    fn per_channel(&self, t: &Tensor) -> Tensor {
+        t.reshape(&[1, self.params.num_features, 1])
+    }
+}
+
+impl AbstractLayerTrait for BatchNormLayer {
+    /// gamma = 1, beta = 0 で初期化し、統計量もリセットする
+    fn build(&mut self) {
+        self.base.w.fill(1.0);
+        self.base.b.fill(0.0);
+        self.running_mean.fill(0.0);
+        self.running_var.fill(1.0);
+    }
+
+    fn forward(&mut self, x: &Tensor) -> Tensor {
+        let batch_size = x.dim(0);
+        assert_eq!(x.len(), batch_size * self.base.i_size, "BatchNormLayer {} got input of shape {:?}", self.base.name, x.shape());
+        self.input_shape = x.shape().to_vec();
+        let x = x.reshape(&[batch_size, self.params.num_features, self.params.spatial_len()]);
+        let count = batch_size * self.params.spatial_len();
+
+        let (mean, var) = if self.training {
+            let mean = channel_sum(&x).scale(1.0 / count as f32);
+            let centered = &x - &self.per_channel(&mean);
+            let var = channel_sum(&(&centered * &centered)).scale(1.0 / count as f32);
+
+            // running_var は不偏分散で更新する
+            let momentum = self.params.momentum;
+            let unbiased = if count > 1 { var.scale(count as f32 / (count - 1) as f32) } else { var.clone() };
+            self.running_mean = &self.running_mean.scale(1.0 - momentum) + &mean.scale(momentum);
+            self.running_var = &self.running_var.scale(1.0 - momentum) + &unbiased.scale(momentum);
+            (mean, var)
+        } else {
+            (self.running_mean.clone(), self.running_var.clone())
+        };
+
+        let epsilon = self.params.epsilon;
+        self.inv_std = var.map(|v| 1.0 / (v + epsilon).sqrt());
+        self.used_batch_stats = self.training;
+        self.x_hat = &(&x - &self.per_channel(&mean)) * &self.per_channel(&self.inv_std);
+
+        // y = gamma * x_hat + beta
+        let output = &(&self.x_hat * &self.per_channel(&self.base.w)) + &self.per_channel(&self.base.b);
+        output.into_reshape(&self.input_shape)
+    }
+
+    fn backward(&mut self, grad_output: &Tensor) -> Tensor {
+        let g = grad_output.reshape(self.x_hat.shape());
+
+        // dgamma += Σ G x_hat, dbeta += Σ G
+        self.base.grad_w += &channel_sum(&(&g * &self.x_hat));
+        self.base.grad_b += &channel_sum(&g);
+
+        let grad_x_hat = &g * &self.per_channel(&self.base.w);
+        let grad_input = if self.used_batch_stats {
+            // 平均と分散も入力に依存するので、その分を差し引く:
+            // dX = inv_std / m * (m dX_hat - Σ dX_hat - X_hat Σ (dX_hat X_hat))
+            let count = (self.x_hat.dim(0) * self.x_hat.dim(2)) as f32;
+            let sum_grad = self.per_channel(&channel_sum(&grad_x_hat));
+            let sum_grad_x_hat = self.per_channel(&channel_sum(&(&grad_x_hat * &self.x_hat)));
+            let centered = &(&grad_x_hat.scale(count) - &sum_grad) - &(&self.x_hat * &sum_grad_x_hat);
+            &centered * &self.per_channel(&self.inv_std.scale(1.0 / count))
+        } else {
+            &grad_x_hat * &self.per_channel(&self.inv_std)
+        };
+        grad_input.into_reshape(&self.input_shape)
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.training = training;
+    }
+
+    fn zero_grad(&mut self) {
+        self.base.zero_grad();
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn i_size(&self) -> usize {
+        self.base.i_size
+    }
+
+    fn o_size(&self) -> usize {
+        self.base.o_size
+    }
+
+    fn w(&self) -> Option<&Tensor> {
+        Some(&self.base.w)
+    }
+
+    fn b(&self) -> Option<&Tensor> {
+        Some(&self.base.b)
+    }
+
+    fn grad_w(&self) -> Option<&Tensor> {
+        Some(&self.base.grad_w)
+    }
+
+    fn grad_b(&self) -> Option<&Tensor> {
+        Some(&self.base.grad_b)
+    }
+
+    fn grad_w_mut(&mut self) -> Option<&mut Tensor> {
+        Some(&mut self.base.grad_w)
+    }
+
+    fn grad_b_mut(&mut self) -> Option<&mut Tensor> {
+        Some(&mut self.base.grad_b)
+    }
+
+    fn update_weights(&mut self, delta_w: &Tensor) {
+        self.base.w += delta_w;
+    }
+
+    fn update_biases(&mut self, delta_b: &Tensor) {
+        self.base.b += delta_b;
+    }
+
+    fn activation_type(&self) -> &str {
+        &self.base.activation_type
+    }
+
+    fn state(&self) -> LayerState {
+        let p = &self.params;
+        LayerState::BatchNorm {
+            name: self.base.name.clone(),
+            num_features: p.num_features,
+            spatial_size: p.spatial_size,
+            momentum: p.momentum,
+            epsilon: p.epsilon,
+            gamma: self.base.w.to_vec(),
+            beta: self.base.b.to_vec(),
+            running_mean: self.running_mean.to_vec(),
+            running_var: self.running_var.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::{check_layer, GradCheckOptions};
+    use crate::gradcheck::tests::{assert_passed, random_tensor};
+
+    fn batch_norm_layer(params: BatchNormParams) -> BatchNormLayer {
+        let c = params.num_features;
+        let gamma = random_tensor(&[c], 20).into_vec();
+        let beta = random_tensor(&[c], 21).into_vec();
+        let running_mean = random_tensor(&[c], 22).into_vec();
+        let running_var = random_tensor(&[c], 23).map(|v| v.abs() + 0.5).into_vec();
+        BatchNormLayer::from_parameters("batch_norm".to_string(), params, gamma, beta, running_mean, running_var)
+    }
+
+    #[test]
+    fn batch_norm_layer_1d() {
+        let mut layer = batch_norm_layer(BatchNormParams::new(3));
+        assert_passed(&check_layer(&mut layer, &random_tensor(&[4, 3], 24), &GradCheckOptions::default()));
+    }
+
+    #[test]
+    fn batch_norm_layer_2d() {
+        let mut layer = batch_norm_layer(BatchNormParams::new(2).spatial_size((3, 2)));
+        let x = random_tensor(&[2, 2, 3, 2], 25);
+        assert_eq!(layer.forward(&x).shape(), &[2, 2, 3, 2]);
+        assert_passed(&check_layer(&mut layer, &x, &GradCheckOptions::default()));
+    }
+
+    #[test]
+    fn batch_norm_layer_eval_mode() {
+        let mut layer = batch_norm_layer(BatchNormParams::new(2).spatial_size((2, 2)));
+        layer.set_training(false);
+        assert_passed(&check_layer(&mut layer, &random_tensor(&[3, 2, 2, 2], 26), &GradCheckOptions::default()));
+    }
+
+    #[test]
+    fn batch_norm_layer_running_statistics() {
+        let mut layer = BatchNormLayer::new("batch_norm".to_string(), BatchNormParams::new(2).momentum(0.5));
+        // 特徴量 0: [1, 3]（平均 2、不偏分散 2）、特徴量 1: [4, 4]（平均 4、不偏分散 0）
+        let x = Tensor::new(vec![1.0, 4.0, 3.0, 4.0], vec![2, 2]);
+        let y = layer.forward(&x);
+        assert_eq!(layer.running_mean().to_vec(), vec![1.0, 2.0]);
+        assert_eq!(layer.running_var().to_vec(), vec![1.5, 0.5]);
+        assert!((y.get(&[0, 0]) + 1.0).abs() < 1e-4 && (y.get(&[1, 0]) - 1.0).abs() < 1e-4);
+
+        // 推論モードでは running 統計量を使い、更新もしない
+        layer.set_training(false);
+        let y = layer.forward(&x);
+        assert_eq!(layer.running_mean().to_vec(), vec![1.0, 2.0]);
+        assert!((y.get(&[0, 0]) - 0.0).abs() < 1e-4);
+    }
+}
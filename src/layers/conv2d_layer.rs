@@ -0,0 +1,362 @@
+use crate::activation::{activation_backward, activation_from_type, identity};
+use crate::checkpoint::LayerState;
+use crate::layers::base_layer::{AbstractLayer, AbstractLayerTrait};
+use crate::tensor::Tensor;
+use rand_distr::{Distribution, Normal};
+
+/// Conv2dLayer の設定（kernel_size / stride / padding / dilation は (縦, 横)）
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conv2dParams {
+    pub in_channels: usize,
+    pub out_channels: usize,
+    pub input_height: usize,
+    pub input_width: usize,
+    pub kernel_size: (usize, usize),
+    pub stride: (usize, usize),
+    pub padding: (usize, usize),
+    pub dilation: (usize, usize),
+    pub activation_type: String,
+}
+
+impl Conv2dParams {
+    pub fn new(in_channels: usize, out_channels: usize, input_height: usize, input_width: usize) -> Self {
+        Self {
+            in_channels,
+            out_channels,
+            input_height,
+            input_width,
+            kernel_size: (3, 3),
+            stride: (1, 1),
+            padding: (0, 0),
+            dilation: (1, 1),
+            activation_type: "identity".to_string(),
+        }
+    }
+
+    pub fn kernel_size(mut self, kernel_size: (usize, usize)) -> Self {
+        self.kernel_size = kernel_size;
+        self
+    }
+
+    pub fn stride(mut self, stride: (usize, usize)) -> Self {
+        self.stride = stride;
+        self
+    }
+
+    pub fn padding(mut self, padding: (usize, usize)) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn dilation(mut self, dilation: (usize, usize)) -> Self {
+        self.dilation = dilation;
+        self
+    }
+
+    pub fn activation_type(mut self, activation_type: String) -> Self {
+        self.activation_type = activation_type;
+        self
+    }
+
+    /// 出力の (高さ, 幅): (H + 2p - d(k - 1) - 1) / s + 1
+    pub fn output_size(&self) -> (usize, usize) {
+        let out = |input: usize, k: usize, s: usize, p: usize, d: usize| {
+            let effective_kernel = d * (k - 1) + 1;
+            assert!(
+                input + 2 * p >= effective_kernel,
+                "kernel (effective size {}) is larger than the padded input ({})", effective_kernel, input + 2 * p
+            );
+            (input + 2 * p - effective_kernel) / s + 1
+        };
+        (
+            out(self.input_height, self.kernel_size.0, self.stride.0, self.padding.0, self.dilation.0),
+            out(self.input_width, self.kernel_size.1, self.stride.1, self.padding.1, self.dilation.1),
+        )
+    }
+}
+
+/// 2 次元畳み込み層
+///
+/// 入力は [batch, in_channels, H, W]（[batch, in_channels * H * W] でもよい）、
+/// 出力は [batch, out_channels, H_out, W_out]。
+/// 重みは [out_channels, in_channels, kh, kw]、バイアスは [out_channels]。
+/// im2col で畳み込みを行列積に変換して計算する。
+#[derive(Debug)]
+pub struct Conv2dLayer {
+    base: AbstractLayer,
+    params: Conv2dParams,
+    pub activation_fn: fn(f32) -> f32,
+    // forward に渡された入力の shape（backward で勾配を同じ shape に戻す）
+    input_shape: Vec<usize>,
+}
+
+impl Conv2dLayer {
+    pub fn new(name: String, params: Conv2dParams) -> Self {
+        assert!(params.kernel_size.0 > 0 && params.kernel_size.1 > 0, "kernel_size must be positive");
+        assert!(params.stride.0 > 0 && params.stride.1 > 0, "stride must be positive");
+        assert!(params.dilation.0 > 0 && params.dilation.1 > 0, "dilation must be positive");
+        let (out_h, out_w) = params.output_size();
+        let i_size = params.in_channels * params.input_height * params.input_width;
+        let o_size = params.out_channels * out_h * out_w;
+        let w_shape = [params.out_channels, params.in_channels, params.kernel_size.0, params.kernel_size.1];
+        Self {
+            base: AbstractLayer::with_parameter_shapes(name, i_size, o_size, params.activation_type.clone(), &w_shape, &[params.out_channels]),
+            activation_fn: identity,
+            input_shape: vec![1, params.in_channels, params.input_height, params.input_width],
+            params,
+        }
+    }
+
+    /// 学習済みの重みとバイアスからレイヤーを作る（build() は呼ばなくてよい）
+    pub fn from_parameters(name: String, params: Conv2dParams, w: Vec<f32>, b: Vec<f32>) -> Self {
+        let mut layer = Self::new(name, params);
+        let w_shape = layer.base.w.shape().to_vec();
+        layer.base.w = Tensor::new(w, w_shape);
+        layer.base.b = Tensor::new(b, vec![layer.params.out_channels]);
+        layer.activation_fn = activation_from_type(&layer.base.activation_type);
+        layer
+    }
+
+    pub fn params(&self) -> &Conv2dParams {
+        &self.params
+    }
+
+    /// 1 行あたりの im2col の長さ（in_channels * kh * kw）
+    fn patch_size(&self) -> usize {
+        self.params.in_channels * self.params.kernel_size.0 * self.params.kernel_size.1
+    }
+
+    /// 入力画素の位置 (ih, iw) を列挙する。パディング領域は None
+    fn for_each_patch<F: FnMut(usize, usize, Option<usize>)>(&self, mut f: F) {
+        let p = &self.params;
+        let (out_h, out_w) = p.output_size();
+        let (kh, kw) = p.kernel_size;
+        for c in 0..p.in_channels {
+            for ki in 0..kh {
+                for kj in 0..kw {
+                    let row = (c * kh + ki) * kw + kj;
+                    for oh in 0..out_h {
+                        let ih = (oh * p.stride.0 + ki * p.dilation.0) as isize - p.padding.0 as isize;
+                        for ow in 0..out_w {
+                            let iw = (ow * p.stride.1 + kj * p.dilation.1) as isize - p.padding.1 as isize;
+                            let col = oh * out_w + ow;
+                            let inside = ih >= 0 && iw >= 0
+                                && (ih as usize) < p.input_height && (iw as usize) < p.input_width;
+                            let src = inside.then(|| (c * p.input_height + ih as usize) * p.input_width + iw as usize);
+                            f(row, col, src);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// 1 サンプル分の入力 [C, H, W] を [C * kh * kw, H_out * W_out] に展開する
+    fn im2col(&self, x: &[f32]) -> Tensor {
+        let (out_h, out_w) = self.params.output_size();
+        let num_cols = out_h * out_w;
+        let mut cols = vec![0.0; self.patch_size() * num_cols];
+        self.for_each_patch(|row, col, src| {
+            if let Some(src) = src {
+                cols[row * num_cols + col] = x[src];
+            }
+        });
+        Tensor::new(cols, vec![self.patch_size(), num_cols])
+    }
+
+    /// im2col の逆（重なった位置の勾配は足し合わせる）
+    fn col2im(&self, cols: &Tensor, grad_x: &mut [f32]) {
+        let num_cols = cols.dim(1);
+        let cols = cols.as_slice();
+        self.for_each_patch(|row, col, src| {
+            if let Some(src) = src {
+                grad_x[src] += cols[row * num_cols + col];
+            }
+        });
+    }
+
+    fn weight_matrix(&self) -> Tensor {
+        self.base.w.reshape(&[self.params.out_channels, self.patch_size()])
+    }
+}
+
+impl AbstractLayerTrait for Conv2dLayer {
+    fn build(&mut self) {
+
+        // He の初期化: N(0, 2 / fan_in)
+        let fan_in = self.patch_size() as f64;
+        let normal = Normal::new(0.0, (2.0 / fan_in).sqrt()).unwrap();
+        let mut rng = rand::thread_rng();
+        for w in self.base.w.as_mut_slice() {
+            *w = normal.sample(&mut rng) as f32;
+        }
+        self.base.b.fill(0.0);
+
+        // set activation function
+        self.activation_fn = activation_from_type(&self.base.activation_type);
+    }
+
+    fn forward(&mut self, x: &Tensor) -> Tensor {
+        let p = &self.params;
+        let (out_h, out_w) = p.output_size();
+        let batch_size = x.dim(0);
+        self.input_shape = x.shape().to_vec();
+        assert_eq!(
+            x.len(), batch_size * self.base.i_size,
+            "Conv2dLayer {} expects inputs of shape [batch, {}, {}, {}], got {:?}",
+            self.base.name, p.in_channels, p.input_height, p.input_width, x.shape()
+        );
+        let x = x.reshape(&[batch_size, p.in_channels, p.input_height, p.input_width]);
+
+        let weight = self.weight_matrix();
+        let bias = self.base.b.reshape(&[p.out_channels, 1]);
+        let mut output = Vec::with_capacity(batch_size * self.base.o_size);
+        for sample in x.rows() {
+            // [C_out, K] x [K, H_out * W_out] + b
+            let y = &weight.matmul(&self.im2col(sample)) + &bias;
+            output.extend(y.as_slice().iter().map(|&z| (self.activation_fn)(z)));
+        }
+        let output = Tensor::new(output, vec![batch_size, p.out_channels, out_h, out_w]);
+
+        self.base.last_input = x;
+        self.base.last_output = output.clone();
+        output
+    }
+
+    fn backward(&mut self, grad_output: &Tensor) -> Tensor {
+        let batch_size = self.base.last_input.dim(0);
+        let (out_h, out_w) = self.params.output_size();
+        let num_cols = out_h * out_w;
+
+        // 活性化関数の勾配を計算（last_output は活性化後の値）
+        let activation_type = self.base.activation_type.as_str();
+        let grad_activation = self.base.last_output
+            .zip_map(&grad_output.reshape(self.base.last_output.shape()), |y, grad| activation_backward(activation_type, y, grad));
+
+        let weight = self.weight_matrix();
+        let mut grad_w = Tensor::zeros(&[self.params.out_channels, self.patch_size()]);
+        let mut grad_input = vec![0.0; batch_size * self.base.i_size];
+        for ((sample, g), grad_x) in self.base.last_input.rows()
+            .zip(grad_activation.rows())
+            .zip(grad_input.chunks_exact_mut(self.base.i_size))
+        {
+            let g = Tensor::new(g.to_vec(), vec![self.params.out_channels, num_cols]);
+            let cols = self.im2col(sample);
+
+            // dW += G cols^T, db += Σ G, dcols = W^T G
+            grad_w += &g.matmul(&cols.transpose());
+            self.base.grad_b += &g.sum_axis(1);
+            self.col2im(&weight.transpose().matmul(&g), grad_x);
+        }
+        self.base.grad_w += &grad_w.into_reshape(self.base.w.shape());
+
+        Tensor::new(grad_input, vec![batch_size, self.base.i_size]).into_reshape(&self.input_shape)
+    }
+
+    fn zero_grad(&mut self) {
+        self.base.zero_grad();
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn i_size(&self) -> usize {
+        self.base.i_size
+    }
+
+    fn o_size(&self) -> usize {
+        self.base.o_size
+    }
+
+    fn w(&self) -> Option<&Tensor> {
+        Some(&self.base.w)
+    }
+
+    fn b(&self) -> Option<&Tensor> {
+        Some(&self.base.b)
+    }
+
+    fn grad_w(&self) -> Option<&Tensor> {
+        Some(&self.base.grad_w)
+    }
+
+    fn grad_b(&self) -> Option<&Tensor> {
+        Some(&self.base.grad_b)
+    }
+
+    fn grad_w_mut(&mut self) -> Option<&mut Tensor> {
+        Some(&mut self.base.grad_w)
+    }
+
+    fn grad_b_mut(&mut self) -> Option<&mut Tensor> {
+        Some(&mut self.base.grad_b)
+    }
+
+    fn update_weights(&mut self, delta_w: &Tensor) {
+        self.base.w += delta_w;
+    }
+
+    fn update_biases(&mut self, delta_b: &Tensor) {
+        self.base.b += delta_b;
+    }
+
+    fn activation_type(&self) -> &str {
+        &self.base.activation_type
+    }
+
+    fn state(&self) -> LayerState {
+        let p = &self.params;
+        LayerState::Conv2d {
+            name: self.base.name.clone(),
+            in_channels: p.in_channels,
+            out_channels: p.out_channels,
+            input_height: p.input_height,
+            input_width: p.input_width,
+            kernel_size: p.kernel_size,
+            stride: p.stride,
+            padding: p.padding,
+            dilation: p.dilation,
+            activation_type: self.base.activation_type.clone(),
+            w: self.base.w.to_vec(),
+            b: self.base.b.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::{check_layer, GradCheckOptions};
+    use crate::gradcheck::tests::{assert_passed, random_tensor};
+
+    fn conv2d_layer(params: Conv2dParams) -> Conv2dLayer {
+        let w_len = params.out_channels * params.in_channels * params.kernel_size.0 * params.kernel_size.1;
+        let w = random_tensor(&[w_len], 8);
+        let b = random_tensor(&[params.out_channels], 9);
+        Conv2dLayer::from_parameters("conv".to_string(), params, w.into_vec(), b.into_vec())
+    }
+
+    #[test]
+    fn conv2d_layer_basic() {
+        let mut layer = conv2d_layer(Conv2dParams::new(2, 3, 5, 4).kernel_size((3, 3)));
+        let x = random_tensor(&[2, 2, 5, 4], 10);
+        assert_eq!(layer.forward(&x).shape(), &[2, 3, 3, 2]);
+        assert_passed(&check_layer(&mut layer, &x, &GradCheckOptions::default()));
+    }
+
+    #[test]
+    fn conv2d_layer_stride_padding_dilation() {
+        let params = Conv2dParams::new(2, 2, 6, 5)
+            .kernel_size((3, 2))
+            .stride((2, 1))
+            .padding((1, 2))
+            .dilation((2, 1))
+            .activation_type("sigmoid".to_string());
+        assert_eq!(params.output_size(), (2, 8));
+        let mut layer = conv2d_layer(params);
+        // フラットな [batch, C * H * W] の入力も受け付ける
+        let x = random_tensor(&[2, 2 * 6 * 5], 11);
+        assert_passed(&check_layer(&mut layer, &x, &GradCheckOptions::default()));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::checkpoint::LayerState;
+use crate::layers::base_layer::{AbstractLayer, AbstractLayerTrait};
+use crate::tensor::Tensor;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+/// ドロップアウト（inverted dropout）
+///
+/// 学習モードでは各要素を確率 p で 0 にし、残った要素を 1 / (1 - p) 倍する。
+/// 推論モードでは入力をそのまま返す。マスクは seed で初期化した乱数から作るので再現できる。
+/// 乱数がどこまで進んだかもチェックポイントに保存するので、再開後も続きのマスクになる。
+#[derive(Debug)]
+pub struct DropoutLayer {
+    base: AbstractLayer,
+    p: f32,
+    seed: u64,
+    // StdRng と同じ ChaCha12（位置を保存・復元できるように型を明示する）
+    rng: ChaCha12Rng,
+    training: bool,
+    // forward で使ったマスク（残した要素は 1 / (1 - p)、落とした要素は 0）
+    mask: Tensor,
+}
+
+impl DropoutLayer {
+    pub fn new(name: String, size: usize, p: f32, seed: u64) -> Self {
+        assert!((0.0..1.0).contains(&p), "dropout probability must be in [0, 1), got {}", p);
+        Self {
+            base: AbstractLayer::without_parameters(name, size, size, "dropout".to_string()),
+            p,
+            seed,
+            rng: ChaCha12Rng::seed_from_u64(seed),
+            training: true,
+            mask: Tensor::zeros(&[0]),
+        }
+    }
+
+    /// 乱数を position（ChaCha の word 単位）まで進める（チェックポイントからの再開用）
+    pub fn rng_position(mut self, position: u128) -> Self {
+        self.rng.set_word_pos(position);
+        self
+    }
+
+    pub fn p(&self) -> f32 {
+        self.p
+    }
+
+    pub fn is_training(&self) -> bool {
+        self.training
+    }
+}
+
+impl AbstractLayerTrait for DropoutLayer {
+    fn forward(&mut self, x: &Tensor) -> Tensor {
+        if !self.training || self.p == 0.0 {
+            self.mask = Tensor::ones(x.shape());
+            return x.clone();
+        }
+
+        let keep = 1.0 - self.p;
+        let scale = 1.0 / keep;
+        let mask: Vec<f32> = (0..x.len())
+            .map(|_| if self.rng.gen::<f32>() < keep { scale } else { 0.0 })
+            .collect();
+        self.mask = Tensor::new(mask, x.shape().to_vec());
+        x * &self.mask
+    }
+
+    fn backward(&mut self, grad_output: &Tensor) -> Tensor {
+        // forward と同じマスクを掛ける
+        &grad_output.reshape(self.mask.shape()) * &self.mask
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.training = training;
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn i_size(&self) -> usize {
+        self.base.i_size
+    }
+
+    fn o_size(&self) -> usize {
+        self.base.o_size
+    }
+
+    fn activation_type(&self) -> &str {
+        &self.base.activation_type
+    }
+
+    fn state(&self) -> LayerState {
+        LayerState::Dropout {
+            name: self.base.name.clone(),
+            size: self.base.i_size,
+            p: self.p,
+            seed: self.seed,
+            rng_position: self.rng.get_word_pos(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::{check_layer, GradCheckOptions};
+    use crate::gradcheck::tests::{assert_passed, random_tensor};
+
+    #[test]
+    fn dropout_layer_uses_forward_mask_in_backward() {
+        let mut layer = DropoutLayer::new("dropout".to_string(), 50, 0.5, 15);
+        let y = layer.forward(&Tensor::ones(&[4, 50]));
+        assert!(y.as_slice().iter().all(|&v| v == 0.0 || v == 2.0));
+        assert!(y.as_slice().contains(&0.0) && y.as_slice().contains(&2.0));
+        // 入力が 1 なので、勾配 1 を流すと forward の出力と一致する
+        assert_eq!(layer.backward(&Tensor::ones(&[4, 50])).to_vec(), y.to_vec());
+
+        // 同じ seed なら同じマスクになる
+        let mut same_seed = DropoutLayer::new("dropout".to_string(), 50, 0.5, 15);
+        assert_eq!(same_seed.forward(&Tensor::ones(&[4, 50])).to_vec(), y.to_vec());
+    }
+
+    #[test]
+    fn dropout_layer_resumes_masks_from_state() {
+        let mut layer = DropoutLayer::new("dropout".to_string(), 50, 0.5, 15);
+        let first = layer.forward(&Tensor::ones(&[4, 50]));
+
+        // 保存した状態から作り直すと、最初のマスクではなく続きのマスクになる
+        let mut restored = layer.state().into_layer();
+        let next = layer.forward(&Tensor::ones(&[4, 50]));
+        let resumed = restored.forward(&Tensor::ones(&[4, 50]));
+        assert_eq!(resumed.to_vec(), next.to_vec());
+        assert_ne!(resumed.to_vec(), first.to_vec());
+    }
+
+    #[test]
+    fn dropout_layer_eval_mode() {
+        let mut layer = DropoutLayer::new("dropout".to_string(), 5, 0.5, 16);
+        layer.set_training(false);
+        assert_passed(&check_layer(&mut layer, &random_tensor(&[3, 5], 17), &GradCheckOptions::default()));
+    }
+}
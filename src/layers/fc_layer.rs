@@ -1,5 +1,7 @@
-use crate::activation::{relu, sigmoid, identity};
+use crate::activation::{activation_backward, activation_from_type, identity};
+use crate::checkpoint::LayerState;
 use crate::layers::base_layer::{AbstractLayer, AbstractLayerTrait};
+use crate::tensor::Tensor;
 use rand_distr::{Distribution, Normal};
 
 
@@ -7,6 +9,8 @@ use rand_distr::{Distribution, Normal};
 pub struct FcLayer {
     base: AbstractLayer,
     pub activation_fn: fn(f32) -> f32,
+    // forward に渡された入力の shape（backward で勾配を同じ shape に戻す）
+    input_shape: Vec<usize>,
 }
 
 impl FcLayer {
@@ -14,8 +18,22 @@ impl FcLayer {
         Self { 
             base: AbstractLayer::new(name, i_size, o_size, activation_type),
             activation_fn: identity,
+            input_shape: vec![1, i_size],
         }
     }
+
+    /// 学習済みの重みとバイアスからレイヤーを作る（build() は呼ばなくてよい）
+    pub fn from_parameters(name: String, i_size: usize, o_size: usize, activation_type: String, w: Vec<f32>, b: Vec<f32>) -> Self {
+        let mut layer = Self::new(name, i_size, o_size, activation_type);
+        layer.base.w = Tensor::new(w, vec![o_size, i_size]);
+        layer.base.b = Tensor::new(b, vec![o_size]);
+        layer.set_activation_fn();
+        layer
+    }
+
+    fn set_activation_fn(&mut self) {
+        self.activation_fn = activation_from_type(&self.base.activation_type);
+    }
 }
 
 impl AbstractLayerTrait for FcLayer {
@@ -28,93 +46,50 @@ impl AbstractLayerTrait for FcLayer {
         // initialize weights and biases
         let mut rng = rand::thread_rng();
         let normal = Normal::new(mu, sigma).unwrap();
-        for i in 0..self.base.i_size * self.base.o_size {
-            self.base.w[i] = normal.sample(&mut rng) as f32;
+        for w in self.base.w.as_mut_slice() {
+            *w = normal.sample(&mut rng) as f32;
         }
-        for i in 0..self.base.o_size {
-            self.base.b[i] = normal.sample(&mut rng) as f32;
+        for b in self.base.b.as_mut_slice() {
+            *b = normal.sample(&mut rng) as f32;
         }
 
         // set activation function
-        match self.base.activation_type.as_str() {
-            "relu" => self.activation_fn = relu,
-            "sigmoid" => self.activation_fn = sigmoid,
-            _ => self.activation_fn = identity,
-        }
+        self.set_activation_fn();
     }
 
-    fn forward(&mut self, x: &[f32]) -> Vec<f32> {
-
-        self.base.last_input = x.to_vec();
-        
-        let mut pre_activation = Vec::with_capacity(self.base.o_size);
-        let mut post_activation = Vec::with_capacity(self.base.o_size);
-        
-        // calculate output for each neuron
-        for j in 0..self.base.o_size {
-            let weight_row_start = j * self.base.i_size;
-            let weight_row = &self.base.w[weight_row_start..weight_row_start + self.base.i_size];
-            
-            // calculate dot product of input and weight
-            let dot_product: f32 = x.iter()
-                .zip(weight_row.iter())
-                .map(|(x_i, w_ij)| x_i * w_ij)
-                .sum();
-
-            let z = dot_product + self.base.b[j];
-            pre_activation.push(z);
-
-            let activation: f32 = (self.activation_fn)(z);
-            post_activation.push(activation);
-        }
+    fn forward(&mut self, x: &Tensor) -> Tensor {
+        // [batch, ...] を [batch, i_size] として扱う
+        self.input_shape = x.shape().to_vec();
+        let x = x.flatten_batch();
+        assert_eq!(x.dim(1), self.base.i_size, "FcLayer {} expects {} input features, got {}", self.base.name, self.base.i_size, x.dim(1));
 
-        self.base.last_output = post_activation.clone();
-        post_activation
-    }
-
-    fn backward(&mut self, grad_output: &[f32]) -> Vec<f32> {
-
-        // 活性化関数の勾配を計算
-        let grad_activation: Vec<f32> = self.base.last_output.iter()
-            .zip(grad_output.iter())
-            .map(|(z, grad)| {
-                match self.base.activation_type.as_str() {
-                    "relu" => if *z > 0.0 { *grad } else { 0.0 },
-                    "sigmoid" => {
-                        let s = sigmoid(*z);
-                        *grad * s * (1.0 - s)
-                    },
-                    _ => *grad,  // identity
-                }
-            })
-            .collect();
-        
-        // 重みとバイアスの勾配用の配列を初期化
-        self.base.grad_w = vec![0.0; self.base.i_size * self.base.o_size];
-        self.base.grad_b = vec![0.0; self.base.o_size];
-        
-        for j in 0..self.base.o_size {
-            // バイアスの勾配
-            self.base.grad_b[j] = grad_activation[j];
-            
-            // 重みの勾配
-            let weight_row_start = j * self.base.i_size;
-            for i in 0..self.base.i_size {
-                self.base.grad_w[weight_row_start + i] = 
-                    grad_activation[j] * self.base.last_input[i];
-            }
-        }
-        
-        // 入力に対する勾配を計算（前のレイヤーに伝播）
-        let mut grad_input = vec![0.0; self.base.i_size];
-        for j in 0..self.base.o_size {
-            let weight_row_start = j * self.base.i_size;
-            for i in 0..self.base.i_size {
-                grad_input[i] += grad_activation[j] * self.base.w[weight_row_start + i];
-            }
-        }
-        
-        grad_input
+        // Y = f(X W^T + b)  (X: [batch, i_size], W: [o_size, i_size])
+        let pre_activation = &x.matmul(&self.base.w.transpose()) + &self.base.b;
+        let output = pre_activation.map(self.activation_fn);
+
+        self.base.last_input = x;
+        self.base.last_output = output.clone();
+        output
+    }
+
+    fn backward(&mut self, grad_output: &Tensor) -> Tensor {
+
+        // 活性化関数の勾配を計算（last_output は活性化後の値 y = f(z)）
+        let activation_type = self.base.activation_type.as_str();
+        let grad_activation: Tensor = self.base.last_output.zip_map(grad_output, |y, grad| {
+            activation_backward(activation_type, y, grad)
+        });
+
+        // 重みとバイアスの勾配（バッチ内で加算）: dW += G^T X, db += Σ_batch G
+        self.base.grad_w += &grad_activation.transpose().matmul(&self.base.last_input);
+        self.base.grad_b += &grad_activation.sum_axis(0);
+
+        // 入力に対する勾配を計算（前のレイヤーに伝播）: dX = G W
+        grad_activation.matmul(&self.base.w).into_reshape(&self.input_shape)
+    }
+
+    fn zero_grad(&mut self) {
+        self.base.zero_grad();
     }
 
     fn name(&self) -> &str {
@@ -129,43 +104,84 @@ impl AbstractLayerTrait for FcLayer {
         self.base.o_size
     }
 
-    fn w(&self) -> &Vec<f32> {
-        &self.base.w
+    fn w(&self) -> Option<&Tensor> {
+        Some(&self.base.w)
     }
 
-    fn b(&self) -> &Vec<f32> {
-        &self.base.b
+    fn b(&self) -> Option<&Tensor> {
+        Some(&self.base.b)
     }
     
-    fn grad_w(&self) -> &Vec<f32> {
-        &self.base.grad_w
+    fn grad_w(&self) -> Option<&Tensor> {
+        Some(&self.base.grad_w)
     }
 
-    fn grad_b(&self) -> &Vec<f32> {
-        &self.base.grad_b
+    fn grad_b(&self) -> Option<&Tensor> {
+        Some(&self.base.grad_b)
     }
 
-    fn grad_w_mut(&mut self) -> &mut Vec<f32> {
-        &mut self.base.grad_w
+    fn grad_w_mut(&mut self) -> Option<&mut Tensor> {
+        Some(&mut self.base.grad_w)
     }
 
-    fn grad_b_mut(&mut self) -> &mut Vec<f32> {
-        &mut self.base.grad_b
+    fn grad_b_mut(&mut self) -> Option<&mut Tensor> {
+        Some(&mut self.base.grad_b)
     }
 
-    fn update_weights(&mut self, delta_w: &[f32]) {
-        for i in 0..self.base.w.len() {
-            self.base.w[i] += delta_w[i];
-        }
+    fn update_weights(&mut self, delta_w: &Tensor) {
+        self.base.w += delta_w;
     }
 
-    fn update_biases(&mut self, delta_b: &[f32]) {
-        for i in 0..self.base.b.len() {
-            self.base.b[i] += delta_b[i];
-        }
+    fn update_biases(&mut self, delta_b: &Tensor) {
+        self.base.b += delta_b;
     }
 
     fn activation_type(&self) -> &str {
         &self.base.activation_type
     }
+
+    fn state(&self) -> LayerState {
+        LayerState::Fc {
+            name: self.base.name.clone(),
+            i_size: self.base.i_size,
+            o_size: self.base.o_size,
+            activation_type: self.base.activation_type.clone(),
+            w: self.base.w.to_vec(),
+            b: self.base.b.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::{check_layer, GradCheckOptions};
+    use crate::gradcheck::tests::{assert_passed, fc_layer, random_tensor};
+
+    #[test]
+    fn fc_layer_identity() {
+        let mut layer = fc_layer("identity");
+        assert_passed(&check_layer(&mut layer, &random_tensor(&[3, 5], 3), &GradCheckOptions::default()));
+    }
+
+    #[test]
+    fn fc_layer_relu() {
+        let mut layer = fc_layer("relu");
+        assert_passed(&check_layer(&mut layer, &random_tensor(&[3, 5], 4), &GradCheckOptions::default()));
+    }
+
+    #[test]
+    fn fc_layer_sigmoid() {
+        let mut layer = fc_layer("sigmoid");
+        assert_passed(&check_layer(&mut layer, &random_tensor(&[3, 5], 5), &GradCheckOptions::default()));
+    }
+
+    #[test]
+    fn fc_layer_flattens_trailing_axes() {
+        let mut layer = fc_layer("identity");
+        let x = random_tensor(&[2, 1, 5], 6);
+        let report = check_layer(&mut layer, &x, &GradCheckOptions::default());
+        assert_passed(&report);
+        assert_eq!(layer.backward(&Tensor::ones(&[2, 4])).shape(), &[2, 1, 5]);
+    }
 }
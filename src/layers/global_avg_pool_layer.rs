@@ -0,0 +1,98 @@
+use crate::checkpoint::LayerState;
+use crate::layers::base_layer::{AbstractLayer, AbstractLayerTrait};
+use crate::tensor::Tensor;
+
+/// チャンネルごとに空間方向の平均を取る（[batch, C, H, W] -> [batch, C]）
+#[derive(Debug)]
+pub struct GlobalAvgPoolLayer {
+    base: AbstractLayer,
+    channels: usize,
+    input_height: usize,
+    input_width: usize,
+    // forward に渡された入力の shape（backward で勾配を同じ shape に戻す）
+    input_shape: Vec<usize>,
+}
+
+impl GlobalAvgPoolLayer {
+    pub fn new(name: String, channels: usize, input_height: usize, input_width: usize) -> Self {
+        assert!(input_height > 0 && input_width > 0, "GlobalAvgPoolLayer {} needs a non-empty feature map", name);
+        Self {
+            base: AbstractLayer::without_parameters(name, channels * input_height * input_width, channels, "global_avg_pool".to_string()),
+            channels,
+            input_height,
+            input_width,
+            input_shape: vec![1, channels, input_height, input_width],
+        }
+    }
+}
+
+impl AbstractLayerTrait for GlobalAvgPoolLayer {
+    fn forward(&mut self, x: &Tensor) -> Tensor {
+        let batch_size = x.dim(0);
+        self.input_shape = x.shape().to_vec();
+        assert_eq!(x.len(), batch_size * self.base.i_size, "GlobalAvgPoolLayer {} got input of shape {:?}", self.base.name, x.shape());
+        let x = x.reshape(&[batch_size, self.channels, self.input_height * self.input_width]);
+        let output = x.mean_axis(2);
+        self.base.last_input = x;
+        output
+    }
+
+    fn backward(&mut self, grad_output: &Tensor) -> Tensor {
+        // 平均なので各画素に g / (H * W) を配る
+        let spatial = self.input_height * self.input_width;
+        let grad = grad_output
+            .reshape(&[grad_output.dim(0), self.channels, 1])
+            .scale(1.0 / spatial as f32);
+        grad.broadcast_to(self.base.last_input.shape())
+            .into_reshape(&self.input_shape)
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn i_size(&self) -> usize {
+        self.base.i_size
+    }
+
+    fn o_size(&self) -> usize {
+        self.base.o_size
+    }
+
+    fn activation_type(&self) -> &str {
+        &self.base.activation_type
+    }
+
+    fn state(&self) -> LayerState {
+        LayerState::GlobalAvgPool {
+            name: self.base.name.clone(),
+            channels: self.channels,
+            input_height: self.input_height,
+            input_width: self.input_width,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::{check_layer, GradCheckOptions};
+    use crate::gradcheck::tests::{assert_passed, random_tensor};
+
+    #[test]
+    fn global_avg_pool_layer() {
+        let mut layer = GlobalAvgPoolLayer::new("gap".to_string(), 3, 2, 3);
+        let x = random_tensor(&[2, 3, 2, 3], 14);
+        assert_eq!(layer.forward(&x).shape(), &[2, 3]);
+        assert_passed(&check_layer(&mut layer, &x, &GradCheckOptions::default()));
+    }
+
+    #[test]
+    fn global_avg_pool_layer_accepts_flat_input() {
+        // [batch, C * H * W] の入力には同じ shape の勾配を返す
+        let mut layer = GlobalAvgPoolLayer::new("gap".to_string(), 2, 4, 4);
+        let x = random_tensor(&[3, 2 * 4 * 4], 20);
+        assert_passed(&check_layer(&mut layer, &x, &GradCheckOptions::default()));
+        assert_eq!(layer.backward(&Tensor::ones(&[3, 2])).shape(), &[3, 32]);
+    }
+}
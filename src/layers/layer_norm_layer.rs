@@ -0,0 +1,179 @@
+use crate::checkpoint::LayerState;
+use crate::layers::base_layer::{AbstractLayer, AbstractLayerTrait};
+use crate::tensor::Tensor;
+
+/// レイヤー正規化
+///
+/// サンプルごとに特徴量方向（[batch, ...] の batch 以外の全要素）の平均と分散で正規化し、
+/// gamma 倍して beta を足す。バッチの統計量を使わないので、学習時と推論時で挙動は同じ。
+#[derive(Debug)]
+pub struct LayerNormLayer {
+    base: AbstractLayer,
+    epsilon: f32,
+    // backward 用: 正規化後の値 [batch, size] と 1 / sqrt(var + eps) [batch, 1]
+    x_hat: Tensor,
+    inv_std: Tensor,
+    input_shape: Vec<usize>,
+}
+
+impl LayerNormLayer {
+    pub fn new(name: String, size: usize, epsilon: f32) -> Self {
+        assert!(size > 0, "LayerNormLayer {} needs at least one feature", name);
+        assert!(epsilon > 0.0, "epsilon must be positive");
+        let mut layer = Self {
+            base: AbstractLayer::with_parameter_shapes(name, size, size, "layer_norm".to_string(), &[size], &[size]),
+            epsilon,
+            x_hat: Tensor::zeros(&[1, size]),
+            inv_std: Tensor::zeros(&[1, 1]),
+            input_shape: vec![1, size],
+        };
+        layer.build();
+        layer
+    }
+
+    /// 学習済みの gamma / beta からレイヤーを作る
+    pub fn from_parameters(name: String, size: usize, epsilon: f32, gamma: Vec<f32>, beta: Vec<f32>) -> Self {
+        let mut layer = Self::new(name, size, epsilon);
+        layer.base.w = Tensor::new(gamma, vec![size]);
+        layer.base.b = Tensor::new(beta, vec![size]);
+        layer
+    }
+
+    pub fn epsilon(&self) -> f32 {
+        self.epsilon
+    }
+}
+
+impl AbstractLayerTrait for LayerNormLayer {
+    /// gamma = 1, beta = 0 で初期化する
+    fn build(&mut self) {
+        self.base.w.fill(1.0);
+        self.base.b.fill(0.0);
+    }
+
+    fn forward(&mut self, x: &Tensor) -> Tensor {
+        self.input_shape = x.shape().to_vec();
+        let x = x.flatten_batch();
+        assert_eq!(x.dim(1), self.base.i_size, "LayerNormLayer {} expects {} features, got {}", self.base.name, self.base.i_size, x.dim(1));
+        let batch_size = x.dim(0);
+        let size = self.base.i_size as f32;
+
+        let mean = x.sum_axis(1).scale(1.0 / size).into_reshape(&[batch_size, 1]);
+        let centered = &x - &mean;
+        let var = (&centered * &centered).sum_axis(1).scale(1.0 / size).into_reshape(&[batch_size, 1]);
+        let epsilon = self.epsilon;
+        self.inv_std = var.map(|v| 1.0 / (v + epsilon).sqrt());
+        self.x_hat = &centered * &self.inv_std;
+
+        // y = gamma * x_hat + beta
+        let output = &(&self.x_hat * &self.base.w) + &self.base.b;
+        output.into_reshape(&self.input_shape)
+    }
+
+    fn backward(&mut self, grad_output: &Tensor) -> Tensor {
+        let g = grad_output.reshape(self.x_hat.shape());
+        let batch_size = g.dim(0);
+        let size = self.base.i_size as f32;
+
+        // dgamma += Σ_batch G x_hat, dbeta += Σ_batch G
+        self.base.grad_w += &(&g * &self.x_hat).sum_axis(0);
+        self.base.grad_b += &g.sum_axis(0);
+
+        // dX = inv_std / n * (n dX_hat - Σ dX_hat - X_hat Σ (dX_hat X_hat))（和はサンプル内）
+        let grad_x_hat = &g * &self.base.w;
+        let sum_grad = grad_x_hat.sum_axis(1).into_reshape(&[batch_size, 1]);
+        let sum_grad_x_hat = (&grad_x_hat * &self.x_hat).sum_axis(1).into_reshape(&[batch_size, 1]);
+        let centered = &(&grad_x_hat.scale(size) - &sum_grad) - &(&self.x_hat * &sum_grad_x_hat);
+        (&centered * &self.inv_std.scale(1.0 / size)).into_reshape(&self.input_shape)
+    }
+
+    fn zero_grad(&mut self) {
+        self.base.zero_grad();
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn i_size(&self) -> usize {
+        self.base.i_size
+    }
+
+    fn o_size(&self) -> usize {
+        self.base.o_size
+    }
+
+    fn w(&self) -> Option<&Tensor> {
+        Some(&self.base.w)
+    }
+
+    fn b(&self) -> Option<&Tensor> {
+        Some(&self.base.b)
+    }
+
+    fn grad_w(&self) -> Option<&Tensor> {
+        Some(&self.base.grad_w)
+    }
+
+    fn grad_b(&self) -> Option<&Tensor> {
+        Some(&self.base.grad_b)
+    }
+
+    fn grad_w_mut(&mut self) -> Option<&mut Tensor> {
+        Some(&mut self.base.grad_w)
+    }
+
+    fn grad_b_mut(&mut self) -> Option<&mut Tensor> {
+        Some(&mut self.base.grad_b)
+    }
+
+    fn update_weights(&mut self, delta_w: &Tensor) {
+        self.base.w += delta_w;
+    }
+
+    fn update_biases(&mut self, delta_b: &Tensor) {
+        self.base.b += delta_b;
+    }
+
+    fn activation_type(&self) -> &str {
+        &self.base.activation_type
+    }
+
+    fn state(&self) -> LayerState {
+        LayerState::LayerNorm {
+            name: self.base.name.clone(),
+            size: self.base.i_size,
+            epsilon: self.epsilon,
+            gamma: self.base.w.to_vec(),
+            beta: self.base.b.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::{check_layer, GradCheckOptions};
+    use crate::gradcheck::tests::{assert_passed, random_tensor};
+
+    #[test]
+    fn layer_norm_layer() {
+        let gamma = random_tensor(&[6], 27).into_vec();
+        let beta = random_tensor(&[6], 28).into_vec();
+        let mut layer = LayerNormLayer::from_parameters("layer_norm".to_string(), 6, 1e-5, gamma, beta);
+        // 特徴量方向に正規化するので batch_size 1 でもよい
+        assert_passed(&check_layer(&mut layer, &random_tensor(&[1, 2, 3], 29), &GradCheckOptions::default()));
+        assert_passed(&check_layer(&mut layer, &random_tensor(&[3, 6], 30), &GradCheckOptions::default()));
+    }
+
+    #[test]
+    fn layer_norm_layer_ignores_mode() {
+        let x = random_tensor(&[2, 4], 34);
+        let mut layer = LayerNormLayer::new("layer_norm".to_string(), 4, 1e-5);
+        let y = layer.forward(&x);
+        layer.set_training(false);
+        assert_eq!(layer.forward(&x).to_vec(), y.to_vec());
+        // gamma = 1, beta = 0 なら各サンプルの平均は 0
+        assert!(y.sum_axis(1).as_slice().iter().all(|v| v.abs() < 1e-5));
+    }
+}
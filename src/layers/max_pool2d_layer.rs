@@ -0,0 +1,135 @@
+use crate::checkpoint::LayerState;
+use crate::layers::base_layer::{AbstractLayer, AbstractLayerTrait};
+use crate::layers::pooling::Pool2dParams;
+use crate::tensor::Tensor;
+
+/// 2 次元の最大値プーリング
+///
+/// 入力は [batch, channels, H, W]、出力は [batch, channels, H_out, W_out]。
+/// backward では各窓の最大値（argmax）の位置にだけ勾配を流す。
+#[derive(Debug)]
+pub struct MaxPool2dLayer {
+    base: AbstractLayer,
+    params: Pool2dParams,
+    // forward に渡された入力の shape（backward で勾配を同じ shape に戻す）
+    input_shape: Vec<usize>,
+    // forward で選ばれた入力のインデックス（バッチ全体でのフラットな位置）
+    argmax: Vec<usize>,
+}
+
+impl MaxPool2dLayer {
+    pub fn new(name: String, params: Pool2dParams) -> Self {
+        if let Err(reason) = params.validate() {
+            panic!("invalid MaxPool2dLayer {}: {}", name, reason);
+        }
+        Self {
+            input_shape: vec![1, params.channels, params.input_height, params.input_width],
+            base: AbstractLayer::without_parameters(name, params.i_size(), params.o_size(), "max_pool".to_string()),
+            params,
+            argmax: Vec::new(),
+        }
+    }
+
+    pub fn params(&self) -> &Pool2dParams {
+        &self.params
+    }
+}
+
+impl AbstractLayerTrait for MaxPool2dLayer {
+    fn forward(&mut self, x: &Tensor) -> Tensor {
+        let p = &self.params;
+        let batch_size = x.dim(0);
+        self.input_shape = x.shape().to_vec();
+        assert_eq!(x.len(), batch_size * self.base.i_size, "MaxPool2dLayer {} got input of shape {:?}", self.base.name, x.shape());
+        let x = x.reshape(&[batch_size, p.channels, p.input_height, p.input_width]);
+        let (out_h, out_w) = p.output_size();
+
+        let mut output = vec![0.0; batch_size * self.base.o_size];
+        self.argmax = vec![0; batch_size * self.base.o_size];
+        for (n, sample) in x.rows().enumerate() {
+            let in_offset = n * self.base.i_size;
+            let out_offset = n * self.base.o_size;
+            p.for_each_window(|o, window| {
+                let &best = window.iter()
+                    .max_by(|&&a, &&b| sample[a].total_cmp(&sample[b]))
+                    .expect("pooling window is never empty");
+                output[out_offset + o] = sample[best];
+                self.argmax[out_offset + o] = in_offset + best;
+            });
+        }
+
+        self.base.last_input = x;
+        Tensor::new(output, vec![batch_size, p.channels, out_h, out_w])
+    }
+
+    fn backward(&mut self, grad_output: &Tensor) -> Tensor {
+        let mut grad_input = Tensor::zeros(self.base.last_input.shape());
+        let grad = grad_input.as_mut_slice();
+        for (&src, &g) in self.argmax.iter().zip(grad_output.contiguous().as_slice()) {
+            grad[src] += g;
+        }
+        grad_input.into_reshape(&self.input_shape)
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn i_size(&self) -> usize {
+        self.base.i_size
+    }
+
+    fn o_size(&self) -> usize {
+        self.base.o_size
+    }
+
+    fn activation_type(&self) -> &str {
+        &self.base.activation_type
+    }
+
+    fn state(&self) -> LayerState {
+        let p = &self.params;
+        LayerState::MaxPool2d {
+            name: self.base.name.clone(),
+            channels: p.channels,
+            input_height: p.input_height,
+            input_width: p.input_width,
+            kernel_size: p.kernel_size,
+            stride: p.stride,
+            padding: p.padding,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::{check_layer, GradCheckOptions};
+    use crate::gradcheck::tests::{assert_passed, random_tensor};
+
+    #[test]
+    fn max_pool2d_layer() {
+        let params = Pool2dParams::new(2, 5, 4, (2, 2)).stride((2, 1)).padding((1, 0));
+        assert_eq!(params.output_size(), (3, 3));
+        let mut layer = MaxPool2dLayer::new("max_pool".to_string(), params);
+        assert_passed(&check_layer(&mut layer, &random_tensor(&[2, 2, 5, 4], 12), &GradCheckOptions::default()));
+    }
+
+    #[test]
+    fn max_pool2d_routes_gradient_to_argmax() {
+        let mut layer = MaxPool2dLayer::new("max_pool".to_string(), Pool2dParams::new(1, 2, 4, (2, 2)));
+        let x = Tensor::new(vec![1.0, 5.0, -1.0, -3.0, 2.0, 0.0, -2.0, -4.0], vec![1, 1, 2, 4]);
+        assert_eq!(layer.forward(&x).to_vec(), vec![5.0, -1.0]);
+        let grad = layer.backward(&Tensor::new(vec![10.0, 20.0], vec![1, 1, 1, 2]));
+        assert_eq!(grad.to_vec(), vec![0.0, 10.0, 20.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn max_pool2d_layer_accepts_flat_input() {
+        // [batch, C * H * W] の入力には同じ shape の勾配を返す
+        let mut layer = MaxPool2dLayer::new("max_pool".to_string(), Pool2dParams::new(2, 4, 4, (2, 2)).stride((2, 2)));
+        let x = random_tensor(&[3, 2 * 4 * 4], 20);
+        assert_passed(&check_layer(&mut layer, &x, &GradCheckOptions::default()));
+        assert_eq!(layer.backward(&Tensor::ones(&[3, 2, 2, 2])).shape(), &[3, 32]);
+    }
+}
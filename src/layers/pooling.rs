@@ -0,0 +1,98 @@
+/// MaxPool2dLayer / AvgPool2dLayer の設定（kernel_size / stride / padding は (縦, 横)）
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pool2dParams {
+    pub channels: usize,
+    pub input_height: usize,
+    pub input_width: usize,
+    pub kernel_size: (usize, usize),
+    pub stride: (usize, usize),
+    pub padding: (usize, usize),
+}
+
+impl Pool2dParams {
+    /// stride は既定で kernel_size と同じ（窓が重ならない）
+    pub fn new(channels: usize, input_height: usize, input_width: usize, kernel_size: (usize, usize)) -> Self {
+        Self {
+            channels,
+            input_height,
+            input_width,
+            kernel_size,
+            stride: kernel_size,
+            padding: (0, 0),
+        }
+    }
+
+    pub fn stride(mut self, stride: (usize, usize)) -> Self {
+        self.stride = stride;
+        self
+    }
+
+    pub fn padding(mut self, padding: (usize, usize)) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// 設定の妥当性を検査する（パディングはカーネルの半分まで）
+    pub fn validate(&self) -> Result<(), String> {
+        let (kh, kw) = self.kernel_size;
+        if kh == 0 || kw == 0 {
+            return Err("kernel_size must be positive".to_string());
+        }
+        if self.stride.0 == 0 || self.stride.1 == 0 {
+            return Err("stride must be positive".to_string());
+        }
+        if self.padding.0 * 2 > kh || self.padding.1 * 2 > kw {
+            return Err(format!("padding {:?} must be at most half of kernel_size {:?}", self.padding, self.kernel_size));
+        }
+        if self.input_height + 2 * self.padding.0 < kh || self.input_width + 2 * self.padding.1 < kw {
+            return Err(format!("kernel_size {:?} is larger than the padded input", self.kernel_size));
+        }
+        Ok(())
+    }
+
+    /// 出力の (高さ, 幅): (H + 2p - k) / s + 1
+    pub fn output_size(&self) -> (usize, usize) {
+        (
+            (self.input_height + 2 * self.padding.0 - self.kernel_size.0) / self.stride.0 + 1,
+            (self.input_width + 2 * self.padding.1 - self.kernel_size.1) / self.stride.1 + 1,
+        )
+    }
+
+    pub fn i_size(&self) -> usize {
+        self.channels * self.input_height * self.input_width
+    }
+
+    pub fn o_size(&self) -> usize {
+        let (out_h, out_w) = self.output_size();
+        self.channels * out_h * out_w
+    }
+
+    /// 1 サンプル内の各出力位置について、窓に含まれる入力のインデックス（パディングを除く）を渡す
+    /// f(出力インデックス, 入力インデックスの列)
+    pub(crate) fn for_each_window<F: FnMut(usize, &[usize])>(&self, mut f: F) {
+        let (out_h, out_w) = self.output_size();
+        let (kh, kw) = self.kernel_size;
+        let mut window = Vec::with_capacity(kh * kw);
+        for c in 0..self.channels {
+            for oh in 0..out_h {
+                for ow in 0..out_w {
+                    window.clear();
+                    for ki in 0..kh {
+                        let ih = (oh * self.stride.0 + ki) as isize - self.padding.0 as isize;
+                        if ih < 0 || ih as usize >= self.input_height {
+                            continue;
+                        }
+                        for kj in 0..kw {
+                            let iw = (ow * self.stride.1 + kj) as isize - self.padding.1 as isize;
+                            if iw < 0 || iw as usize >= self.input_width {
+                                continue;
+                            }
+                            window.push((c * self.input_height + ih as usize) * self.input_width + iw as usize);
+                        }
+                    }
+                    f((c * out_h + oh) * out_w + ow, &window);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::checkpoint::LayerState;
+use crate::layers::base_layer::{AbstractLayer, AbstractLayerTrait};
+use crate::tensor::Tensor;
+
+/// RMSNorm
+///
+/// サンプルごとに特徴量方向の二乗平均平方根 sqrt(mean(x^2) + eps) で割り、gamma 倍する。
+/// LayerNorm と違って平均を引かず、beta も持たない（b は None）。
+#[derive(Debug)]
+pub struct RmsNormLayer {
+    base: AbstractLayer,
+    epsilon: f32,
+    // backward 用: 正規化後の値 [batch, size] と 1 / rms [batch, 1]
+    x_hat: Tensor,
+    inv_rms: Tensor,
+    input_shape: Vec<usize>,
+}
+
+impl RmsNormLayer {
+    pub fn new(name: String, size: usize, epsilon: f32) -> Self {
+        assert!(size > 0, "RmsNormLayer {} needs at least one feature", name);
+        assert!(epsilon > 0.0, "epsilon must be positive");
+        let mut layer = Self {
+            base: AbstractLayer::with_parameter_shapes(name, size, size, "rms_norm".to_string(), &[size], &[0]),
+            epsilon,
+            x_hat: Tensor::zeros(&[1, size]),
+            inv_rms: Tensor::zeros(&[1, 1]),
+            input_shape: vec![1, size],
+        };
+        layer.build();
+        layer
+    }
+
+    /// 学習済みの gamma からレイヤーを作る
+    pub fn from_parameters(name: String, size: usize, epsilon: f32, gamma: Vec<f32>) -> Self {
+        let mut layer = Self::new(name, size, epsilon);
+        layer.base.w = Tensor::new(gamma, vec![size]);
+        layer
+    }
+
+    pub fn epsilon(&self) -> f32 {
+        self.epsilon
+    }
+}
+
+impl AbstractLayerTrait for RmsNormLayer {
+    /// gamma = 1 で初期化する
+    fn build(&mut self) {
+        self.base.w.fill(1.0);
+    }
+
+    fn forward(&mut self, x: &Tensor) -> Tensor {
+        self.input_shape = x.shape().to_vec();
+        let x = x.flatten_batch();
+        assert_eq!(x.dim(1), self.base.i_size, "RmsNormLayer {} expects {} features, got {}", self.base.name, self.base.i_size, x.dim(1));
+        let batch_size = x.dim(0);
+        let size = self.base.i_size as f32;
+
+        let mean_square = (&x * &x).sum_axis(1).scale(1.0 / size).into_reshape(&[batch_size, 1]);
+        let epsilon = self.epsilon;
+        self.inv_rms = mean_square.map(|v| 1.0 / (v + epsilon).sqrt());
+        self.x_hat = &x * &self.inv_rms;
+
+        (&self.x_hat * &self.base.w).into_reshape(&self.input_shape)
+    }
+
+    fn backward(&mut self, grad_output: &Tensor) -> Tensor {
+        let g = grad_output.reshape(self.x_hat.shape());
+        let batch_size = g.dim(0);
+        let size = self.base.i_size as f32;
+
+        // dgamma += Σ_batch G x_hat
+        self.base.grad_w += &(&g * &self.x_hat).sum_axis(0);
+
+        // dX = inv_rms * (dX_hat - X_hat mean(dX_hat X_hat))（平均はサンプル内）
+        let grad_x_hat = &g * &self.base.w;
+        let mean_grad_x_hat = (&grad_x_hat * &self.x_hat).sum_axis(1).scale(1.0 / size).into_reshape(&[batch_size, 1]);
+        let grad_input = &(&grad_x_hat - &(&self.x_hat * &mean_grad_x_hat)) * &self.inv_rms;
+        grad_input.into_reshape(&self.input_shape)
+    }
+
+    fn zero_grad(&mut self) {
+        self.base.zero_grad();
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn i_size(&self) -> usize {
+        self.base.i_size
+    }
+
+    fn o_size(&self) -> usize {
+        self.base.o_size
+    }
+
+    fn w(&self) -> Option<&Tensor> {
+        Some(&self.base.w)
+    }
+
+    fn grad_w(&self) -> Option<&Tensor> {
+        Some(&self.base.grad_w)
+    }
+
+    fn grad_w_mut(&mut self) -> Option<&mut Tensor> {
+        Some(&mut self.base.grad_w)
+    }
+
+    fn update_weights(&mut self, delta_w: &Tensor) {
+        self.base.w += delta_w;
+    }
+
+    fn activation_type(&self) -> &str {
+        &self.base.activation_type
+    }
+
+    fn state(&self) -> LayerState {
+        LayerState::RmsNorm {
+            name: self.base.name.clone(),
+            size: self.base.i_size,
+            epsilon: self.epsilon,
+            gamma: self.base.w.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::{check_layer, GradCheckOptions};
+    use crate::gradcheck::tests::{assert_passed, random_tensor};
+
+    #[test]
+    fn rms_norm_layer() {
+        let gamma = random_tensor(&[5], 31).into_vec();
+        let mut layer = RmsNormLayer::from_parameters("rms_norm".to_string(), 5, 1e-5, gamma);
+        assert!(layer.b().is_none());
+        assert_passed(&check_layer(&mut layer, &random_tensor(&[1, 5], 32), &GradCheckOptions::default()));
+        assert_passed(&check_layer(&mut layer, &random_tensor(&[3, 5], 33), &GradCheckOptions::default()));
+    }
+
+    #[test]
+    fn rms_norm_layer_ignores_mode() {
+        let x = random_tensor(&[2, 4], 34);
+        let mut layer = RmsNormLayer::new("rms_norm".to_string(), 4, 1e-5);
+        let y = layer.forward(&x);
+        layer.set_training(false);
+        assert_eq!(layer.forward(&x).to_vec(), y.to_vec());
+    }
+}
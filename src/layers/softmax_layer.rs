@@ -1,57 +1,54 @@
 use crate::layers::base_layer::{AbstractLayer, AbstractLayerTrait};
 use crate::activation::softmax;
+use crate::checkpoint::LayerState;
+use crate::tensor::Tensor;
 
 #[derive(Debug)]
 pub struct SoftmaxLayer {
     base: AbstractLayer,
+    /// forward に渡された入力の shape（backward の勾配をこの shape で返す）
+    input_shape: Vec<usize>,
 }
 
 impl SoftmaxLayer {
     pub fn new(name: String, i_size: usize, o_size: usize) -> Self {
         Self {
-            base: AbstractLayer::new(name, i_size, o_size, "softmax".to_string()),
+            base: AbstractLayer::without_parameters(name, i_size, o_size, "softmax".to_string()),
+            input_shape: vec![1, i_size],
         }
     }
 }
 
 impl AbstractLayerTrait for SoftmaxLayer {
-    fn build(&mut self) {}
-
-    fn forward(&mut self, x: &[f32]) -> Vec<f32> {
-        // 入力を保存
-        self.base.last_input = x.to_vec();
-        
-        // softmax を計算
-        let output = softmax(x);
-        
-        // 出力を保存（backward で使用）
+    fn forward(&mut self, x: &Tensor) -> Tensor {
+        // [batch, C, H, W] などの入力はサンプルごとに 1 次元にまとめて softmax を取る
+        self.input_shape = x.shape().to_vec();
+        let x = x.flatten_batch();
+
+        // サンプルごとに softmax を計算
+        let output_data: Vec<f32> = x.rows()
+            .flat_map(softmax)
+            .collect();
+        let output = Tensor::new(output_data, x.shape().to_vec());
+
+        // 入力と出力を保存（backward で使用）
+        self.base.last_input = x;
         self.base.last_output = output.clone();
-        output
+        output.into_reshape(&self.input_shape)
     }
 
-    fn backward(&mut self, grad_output: &[f32]) -> Vec<f32> {
-        // softmax の出力（forward で保存したもの）
-        let s = &self.base.last_output;
-        
+    fn backward(&mut self, grad_output: &Tensor) -> Tensor {
         // softmax の勾配: ∂s_i / ∂x_j = s_i * (δ_ij - s_j)
         // チェインルール: ∂L / ∂x_j = Σ_i (∂L / ∂s_i) * (∂s_i / ∂x_j)
         //                = s_j * (grad_output[j] - Σ_i grad_output[i] * s_i)
-        
-        // Σ_i grad_output[i] * s_i を計算
-        let sum_grad_s: f32 = grad_output.iter()
-            .zip(s.iter())
-            .map(|(g, s_i)| g * s_i)
-            .sum();
-        
+        let s = &self.base.last_output;
+        let grad_output = grad_output.reshape(s.shape());
+
+        // Σ_i grad_output[i] * s_i をサンプルごとに計算
+        let sum_grad_s = (&grad_output * s).sum_axis(1).reshape(&[s.dim(0), 1]);
+
         // 各入力に対する勾配を計算
-        let grad_input: Vec<f32> = s.iter()
-            .zip(grad_output.iter())
-            .map(|(s_j, grad_j)| {
-                s_j * (grad_j - sum_grad_s)
-            })
-            .collect();
-        
-        grad_input
+        (s * &(&grad_output - &sum_grad_s)).into_reshape(&self.input_shape)
     }
 
     fn name(&self) -> &str {
@@ -66,43 +63,40 @@ impl AbstractLayerTrait for SoftmaxLayer {
         self.base.o_size
     }
 
-    fn w(&self) -> &Vec<f32> {
-        &self.base.w
-    }
-
-    fn b(&self) -> &Vec<f32> {
-        &self.base.b
-    }
-
-    fn grad_w(&self) -> &Vec<f32> {
-        &self.base.grad_w
-    }
-
-    fn grad_b(&self) -> &Vec<f32> {
-        &self.base.grad_b
-    }
-
-    fn grad_w_mut(&mut self) -> &mut Vec<f32> {
-        &mut self.base.grad_w
-    }
-
-    fn grad_b_mut(&mut self) -> &mut Vec<f32> {
-        &mut self.base.grad_b
+    fn activation_type(&self) -> &str {
+        &self.base.activation_type
     }
 
-    fn update_weights(&mut self, delta_w: &[f32]) {
-        for i in 0..self.base.w.len() {
-            self.base.w[i] += delta_w[i];
+    fn state(&self) -> LayerState {
+        LayerState::Softmax {
+            name: self.base.name.clone(),
+            i_size: self.base.i_size,
+            o_size: self.base.o_size,
         }
     }
+}
 
-    fn update_biases(&mut self, delta_b: &[f32]) {
-        for i in 0..self.base.b.len() {
-            self.base.b[i] += delta_b[i];
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::{check_layer, GradCheckOptions};
+    use crate::gradcheck::tests::{assert_passed, random_tensor};
+
+    #[test]
+    fn softmax_layer() {
+        let mut layer = SoftmaxLayer::new("softmax".to_string(), 4, 4);
+        assert_passed(&check_layer(&mut layer, &random_tensor(&[3, 4], 7), &GradCheckOptions::default()));
     }
 
-    fn activation_type(&self) -> &str {
-        &self.base.activation_type
+    #[test]
+    fn softmax_layer_keeps_4d_input_shape() {
+        // [batch, C, H, W] の入力はサンプルごとに C * H * W 個の softmax を取り、同じ shape で返す
+        let mut layer = SoftmaxLayer::new("softmax".to_string(), 12, 12);
+        let x = random_tensor(&[2, 3, 2, 2], 8);
+        let y = layer.forward(&x);
+        assert_eq!(y.shape(), &[2, 3, 2, 2]);
+        assert!(y.flatten_batch().sum_axis(1).as_slice().iter().all(|s| (s - 1.0).abs() < 1e-5));
+        assert_passed(&check_layer(&mut layer, &x, &GradCheckOptions::default()));
+        assert_eq!(layer.backward(&Tensor::ones(&[2, 3, 2, 2])).shape(), &[2, 3, 2, 2]);
     }
 }
@@ -1,4 +1,5 @@
 
+use crate::layers::conv2d_layer::{Conv2dLayer, Conv2dParams};
 use crate::layers::fc_layer::FcLayer;
 use crate::layers::softmax_layer::SoftmaxLayer;
 use crate::layers::base_layer::AbstractLayerTrait;
@@ -16,6 +17,38 @@ pub fn create_layers(layer_sizes: Vec<usize>, activation_type: String, use_softm
     layers
 }
 
+/// 畳み込み層を並べた後に全結合層を繋げる
+///
+/// * `input_shape` - (チャンネル数, 高さ, 幅)
+/// * `conv_channels` - 各畳み込み層の出力チャンネル数（3x3, stride 2, padding 1 で縦横を半分にする）
+/// * `fc_sizes` - 畳み込みの後に続く全結合層の出力サイズ（最後が出力次元）
+pub fn create_cnn_layers(input_shape: (usize, usize, usize), conv_channels: Vec<usize>, fc_sizes: Vec<usize>, activation_type: String, use_softmax: bool) -> Vec<Box<dyn AbstractLayerTrait>> {
+    let mut layers: Vec<Box<dyn AbstractLayerTrait>> = Vec::new();
+    let (mut channels, mut height, mut width) = input_shape;
+    for (i, &out_channels) in conv_channels.iter().enumerate() {
+        let params = Conv2dParams::new(channels, out_channels, height, width)
+            .kernel_size((3, 3))
+            .stride((2, 2))
+            .padding((1, 1))
+            .activation_type(activation_type.clone());
+        (height, width) = params.output_size();
+        channels = out_channels;
+        layers.push(Box::new(Conv2dLayer::new(format!("conv_{}", i), params)));
+    }
+
+    let mut layer_sizes = vec![channels * height * width];
+    layer_sizes.extend(fc_sizes);
+    for (i, sizes) in layer_sizes.windows(2).enumerate() {
+        let layer = Box::new(FcLayer::new(format!("layer_{}", conv_channels.len() + i), sizes[0], sizes[1], activation_type.clone()));
+        layers.push(layer);
+    }
+    if use_softmax {
+        let size = *layer_sizes.last().unwrap();
+        layers.push(Box::new(SoftmaxLayer::new(format!("softmax_{}", conv_channels.len() + layer_sizes.len() - 1), size, size)));
+    }
+    layers
+}
+
 pub fn print_layers(layers: &[Box<dyn AbstractLayerTrait>]) {
     for layer in layers {
         println!("-----------------------------");
@@ -23,9 +56,11 @@ pub fn print_layers(layers: &[Box<dyn AbstractLayerTrait>]) {
         println!("     Input Size      : {}", layer.i_size());
         println!("     Output Size     : {}", layer.o_size());
 
-        if layer.activation_type() != "softmax" {
-            println!("     Weights Length  : {}", layer.w().len());
-            println!("     Biases Length   : {}", layer.b().len());
+        if let Some(w) = layer.w() {
+            println!("     Weights Length  : {}", w.len());
+        }
+        if let Some(b) = layer.b() {
+            println!("     Biases Length   : {}", b.len());
         }
 
         println!("     Activation Type : {}", layer.activation_type());
@@ -0,0 +1,12 @@
+pub mod activation;
+pub mod autograd;
+pub mod checkpoint;
+pub mod data;
+pub mod gradcheck;
+pub mod layers;
+pub mod losses;
+pub mod model;
+pub mod optimizers;
+pub mod schedulers;
+pub mod tensor;
+pub mod trainer;
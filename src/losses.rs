@@ -1,2 +1,10 @@
 pub mod base_loss;
-pub mod cross_entropy_loss;
\ No newline at end of file
+pub mod binary_cross_entropy_loss;
+pub mod cross_entropy_loss;
+pub mod focal_loss;
+pub mod hinge_loss;
+pub mod huber_loss;
+pub mod kl_div_loss;
+pub mod mae_loss;
+pub mod mse_loss;
+pub mod softmax_cross_entropy_loss;
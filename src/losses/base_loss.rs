@@ -1,8 +1,65 @@
+use crate::tensor::Tensor;
+
+/// バッチ内の損失のまとめ方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Reduction {
+    /// サンプルごとの損失の平均
+    #[default]
+    Mean,
+    /// サンプルごとの損失の和
+    Sum,
+    /// まとめない（forward はサンプルごとの損失 [batch_size] を返す）
+    None,
+}
+
+impl Reduction {
+    /// サンプルごとの損失 [batch_size] をまとめる（Mean / Sum はスカラー、None はそのまま）
+    pub fn reduce(self, per_sample: Tensor) -> Tensor {
+        match self {
+            Reduction::Mean => Tensor::scalar(per_sample.mean()),
+            Reduction::Sum => Tensor::scalar(per_sample.sum()),
+            Reduction::None => per_sample,
+        }
+    }
+
+    /// サンプルごとの損失の勾配に掛ける係数（平均なら 1 / batch_size）
+    pub fn scale(self, batch_size: usize) -> f32 {
+        match self {
+            Reduction::Mean => 1.0 / batch_size.max(1) as f32,
+            Reduction::Sum | Reduction::None => 1.0,
+        }
+    }
+}
+
 pub trait AbstractLossFunctionTrait {
-    fn forward(&self, y_true: &[f32], y_pred: &[f32]) -> f32;
-    fn backward(&self, y_true: &[f32], y_pred: &[f32]) -> Vec<f32>;
+    /// y_true / y_pred は [batch_size, num_classes]、戻り値は reduction でまとめた損失
+    /// （Mean / Sum はスカラー、None はサンプルごとの損失 [batch_size]）
+    fn forward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor;
+    /// forward の損失に対する y_pred の勾配（y_pred と同じ shape）
+    /// None では i 行目がサンプル i の損失に対する勾配になる
+    fn backward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor;
+    /// サンプルごとの損失 [batch_size]
+    fn forward_per_sample(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor;
     fn name(&self) -> &str;
     fn build(&mut self);
+
+    fn reduction(&self) -> Reduction {
+        Reduction::Mean
+    }
+
+    /// サンプルごとの重み weights [batch_size] を掛けた損失（まとめ方は reduction に従う）
+    fn forward_weighted(&self, y_true: &Tensor, y_pred: &Tensor, weights: &Tensor) -> Tensor {
+        self.reduction().reduce(&self.forward_per_sample(y_true, y_pred) * weights)
+    }
+
+    /// forward_weighted に対する勾配
+    /// backward の i 行目はサンプル i の損失だけに依存するので、行ごとに重みを掛ければよい
+    fn backward_weighted(&self, y_true: &Tensor, y_pred: &Tensor, weights: &Tensor) -> Tensor {
+        let batch_size = y_pred.dim(0);
+        assert_eq!(weights.len(), batch_size, "expected one weight per sample");
+        let grad = self.backward(y_true, y_pred).flatten_batch();
+        (&grad * &weights.reshape(&[batch_size, 1])).into_reshape(y_pred.shape())
+    }
 }
 
 #[derive(Debug)]
@@ -10,10 +67,104 @@ pub struct AbstractLossFunction {
     pub name: String,
     pub loss: f32,
     pub gradient: Vec<f32>,
+    pub reduction: Reduction,
 }
 
 impl AbstractLossFunction {
     pub fn new(name: String) -> Self {
-        Self { name, loss: 0.0, gradient: Vec::new() }
+        Self { name, loss: 0.0, gradient: Vec::new(), reduction: Reduction::Mean }
+    }
+
+    /// サンプルごとの損失 [batch_size] を reduction でまとめる
+    pub fn reduce(&self, per_sample: Tensor) -> Tensor {
+        self.reduction.reduce(per_sample)
+    }
+
+    /// サンプルごとの損失の勾配に掛ける係数（平均なら 1 / batch_size）
+    pub fn reduction_scale(&self, batch_size: usize) -> f32 {
+        self.reduction.scale(batch_size)
     }
-}
\ No newline at end of file
+}
+
+/// クラスごとの重み [num_classes] を y_true [batch_size, num_classes] に掛ける（重みがなければそのまま）
+pub fn weight_classes(y_true: &Tensor, class_weights: Option<&Tensor>) -> Tensor {
+    match class_weights {
+        Some(weights) => {
+            assert_eq!(weights.len(), y_true.dim(y_true.ndim() - 1), "expected one weight per class");
+            y_true * weights
+        }
+        None => y_true.clone(),
+    }
+}
+
+/// 要素ごとの損失 f(y_true, y_pred) をサンプル内で平均して [batch_size] にする（回帰・多ラベル用）
+pub fn elementwise_mean_per_sample<F: Fn(f32, f32) -> f32>(y_true: &Tensor, y_pred: &Tensor, f: F) -> Tensor {
+    y_true.zip_map(y_pred, f).flatten_batch().mean_axis(1)
+}
+
+/// 要素ごとの勾配 df/dy_pred を、サンプル内の平均と reduction に合わせて縮める
+pub fn elementwise_mean_backward<F: Fn(f32, f32) -> f32>(base: &AbstractLossFunction, y_true: &Tensor, y_pred: &Tensor, df: F) -> Tensor {
+    let batch_size = y_pred.dim(0);
+    let features = (y_pred.len() / batch_size.max(1)).max(1);
+    let scale = base.reduction_scale(batch_size) / features as f32;
+    y_true.zip_map(y_pred, |t, p| df(t, p) * scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::{check_loss, GradCheckOptions};
+    use crate::gradcheck::tests::{assert_passed, random_tensor};
+    use crate::losses::mse_loss::MseLoss;
+    use crate::losses::softmax_cross_entropy_loss::SoftmaxCrossEntropyLoss;
+
+    #[test]
+    fn reduction_none_keeps_per_sample_losses() {
+        // 誤差はサンプル 0 が [1, -1]、サンプル 1 が [0, 2]
+        let y_true = Tensor::new(vec![0.0, 0.0, 1.0, 1.0], vec![2, 2]);
+        let y_pred = Tensor::new(vec![1.0, -1.0, 1.0, 3.0], vec![2, 2]);
+        let loss = MseLoss::new("mse".to_string()).reduction(Reduction::None);
+        assert_eq!(loss.forward(&y_true, &y_pred), Tensor::new(vec![1.0, 2.0], vec![2]));
+        assert_eq!(loss.backward(&y_true, &y_pred).to_vec(), vec![1.0, -1.0, 0.0, 2.0]);
+        let weights = Tensor::new(vec![2.0, 0.5], vec![2]);
+        assert_eq!(loss.forward_weighted(&y_true, &y_pred, &weights), Tensor::new(vec![2.0, 1.0], vec![2]));
+        assert_eq!(loss.backward_weighted(&y_true, &y_pred, &weights).to_vec(), vec![2.0, -2.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn sample_weighted_loss() {
+        // forward_weighted / backward_weighted を forward / backward として勾配を確認する
+        struct Weighted(SoftmaxCrossEntropyLoss, Tensor);
+        impl AbstractLossFunctionTrait for Weighted {
+            fn forward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+                self.0.forward_weighted(y_true, y_pred, &self.1)
+            }
+            fn backward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+                self.0.backward_weighted(y_true, y_pred, &self.1)
+            }
+            fn forward_per_sample(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+                &self.0.forward_per_sample(y_true, y_pred) * &self.1
+            }
+            fn name(&self) -> &str {
+                "weighted"
+            }
+            fn build(&mut self) {}
+        }
+
+        let y_true = SoftmaxCrossEntropyLoss::one_hot(&[2, 0, 1], 3);
+        let logits = random_tensor(&[3, 3], 42).scale(2.0);
+        let weights = Tensor::new(vec![1.0, 0.0, 3.0], vec![3]);
+        for reduction in [Reduction::Mean, Reduction::Sum, Reduction::None] {
+            let loss = Weighted(SoftmaxCrossEntropyLoss::new("softmax_ce".to_string()).reduction(reduction), weights.clone());
+            assert_passed(&check_loss(&loss, &y_true, &logits, &GradCheckOptions::default()));
+        }
+
+        // 重み 0 のサンプルには勾配が流れない
+        let loss = SoftmaxCrossEntropyLoss::new("softmax_ce".to_string());
+        let grad = loss.backward_weighted(&y_true, &logits, &weights);
+        assert!(grad.row(1).iter().all(|&g| g == 0.0));
+        // 重みが全て 1 なら重みなしと同じ
+        let ones = Tensor::ones(&[3]);
+        assert_eq!(loss.forward_weighted(&y_true, &logits, &ones), loss.forward(&y_true, &logits));
+    }
+}
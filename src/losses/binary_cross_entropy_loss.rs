@@ -0,0 +1,118 @@
+use crate::losses::base_loss::{elementwise_mean_backward, elementwise_mean_per_sample, AbstractLossFunction, AbstractLossFunctionTrait, Reduction};
+use crate::tensor::Tensor;
+
+const EPSILON: f32 = 1e-7;
+
+/// 2 値交差エントロピー（y_pred は sigmoid 後の確率）
+///
+/// 要素ごとに -(t ln p + (1 - t) ln(1 - p)) をサンプル内で平均する。多ラベル分類にも使える。
+pub struct BinaryCrossEntropyLoss {
+    base: AbstractLossFunction,
+}
+
+impl BinaryCrossEntropyLoss {
+    pub fn new(name: String) -> Self {
+        Self { base: AbstractLossFunction::new(name) }
+    }
+
+    pub fn reduction(mut self, reduction: Reduction) -> Self {
+        self.base.reduction = reduction;
+        self
+    }
+}
+
+impl AbstractLossFunctionTrait for BinaryCrossEntropyLoss {
+    fn forward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        self.base.reduce(self.forward_per_sample(y_true, y_pred))
+    }
+
+    fn backward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        // (p - t) / (p (1 - p))
+        elementwise_mean_backward(&self.base, y_true, y_pred, |t, p| {
+            let p = p.clamp(EPSILON, 1.0 - EPSILON);
+            (p - t) / (p * (1.0 - p))
+        })
+    }
+
+    fn forward_per_sample(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        elementwise_mean_per_sample(y_true, y_pred, |t, p| {
+            let p = p.clamp(EPSILON, 1.0 - EPSILON);
+            -(t * p.ln() + (1.0 - t) * (1.0 - p).ln())
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn build(&mut self) {}
+
+    fn reduction(&self) -> Reduction {
+        self.base.reduction
+    }
+}
+
+/// sigmoid と 2 値交差エントロピーをまとめた損失（y_pred は sigmoid 前のロジット）
+///
+/// max(x, 0) - x t + ln(1 + exp(-|x|)) で計算するので、大きなロジットでも安定する。
+/// 勾配は sigmoid(x) - t。
+pub struct BinaryCrossEntropyWithLogitsLoss {
+    base: AbstractLossFunction,
+}
+
+impl BinaryCrossEntropyWithLogitsLoss {
+    pub fn new(name: String) -> Self {
+        Self { base: AbstractLossFunction::new(name) }
+    }
+
+    pub fn reduction(mut self, reduction: Reduction) -> Self {
+        self.base.reduction = reduction;
+        self
+    }
+}
+
+impl AbstractLossFunctionTrait for BinaryCrossEntropyWithLogitsLoss {
+    fn forward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        self.base.reduce(self.forward_per_sample(y_true, y_pred))
+    }
+
+    fn backward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        elementwise_mean_backward(&self.base, y_true, y_pred, |t, x| 1.0 / (1.0 + (-x).exp()) - t)
+    }
+
+    fn forward_per_sample(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        elementwise_mean_per_sample(y_true, y_pred, |t, x| x.max(0.0) - x * t + (-x.abs()).exp().ln_1p())
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn build(&mut self) {}
+
+    fn reduction(&self) -> Reduction {
+        self.base.reduction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::tests::{check_loss_reductions, random_tensor};
+
+    #[test]
+    fn binary_cross_entropy_losses() {
+        let y_true = Tensor::new(vec![1.0, 0.0, 1.0, 0.0, 0.0, 1.0], vec![2, 3]);
+        let logits = random_tensor(&[2, 3], 38).scale(2.0);
+        let probs = logits.map(|x| 1.0 / (1.0 + (-x).exp()));
+        check_loss_reductions(|r| BinaryCrossEntropyLoss::new("bce".to_string()).reduction(r), &y_true, &probs, 1e-4);
+        check_loss_reductions(|r| BinaryCrossEntropyWithLogitsLoss::new("bce_logits".to_string()).reduction(r), &y_true, &logits, 1e-4);
+
+        let bce = BinaryCrossEntropyLoss::new("bce".to_string()).forward(&y_true, &probs).item();
+        let with_logits = BinaryCrossEntropyWithLogitsLoss::new("bce_logits".to_string());
+        assert!((bce - with_logits.forward(&y_true, &logits).item()).abs() < 1e-5);
+        // 大きなロジットでも有限
+        let extreme = Tensor::new(vec![-500.0, 500.0, -500.0, 500.0, 500.0, -500.0], vec![2, 3]);
+        assert!((with_logits.forward(&y_true, &extreme).item() - 500.0).abs() < 1e-3);
+    }
+}
@@ -1,39 +1,52 @@
-use crate::losses::base_loss::{AbstractLossFunction, AbstractLossFunctionTrait};
+use crate::losses::base_loss::{weight_classes, AbstractLossFunction, AbstractLossFunctionTrait, Reduction};
+use crate::tensor::Tensor;
 
+/// 交差エントロピー（y_pred は softmax 後の確率）
+///
+/// class_weights を指定すると、クラス c の項に w_c を掛ける: -Σ w_c t_c ln p_c
 pub struct CrossEntropyLoss {
     base: AbstractLossFunction,
+    class_weights: Option<Tensor>,
 }
 
 impl CrossEntropyLoss {
     pub fn new(name: String) -> Self {
-        Self { base: AbstractLossFunction::new(name) }
+        Self { base: AbstractLossFunction::new(name), class_weights: None }
+    }
+
+    pub fn class_weights(mut self, class_weights: Vec<f32>) -> Self {
+        let num_classes = class_weights.len();
+        self.class_weights = Some(Tensor::new(class_weights, vec![num_classes]));
+        self
+    }
+
+    pub fn reduction(mut self, reduction: Reduction) -> Self {
+        self.base.reduction = reduction;
+        self
     }
 }
 
 impl AbstractLossFunctionTrait for CrossEntropyLoss {
-    fn forward(&self, y_true: &[f32], y_pred: &[f32]) -> f32 {
+    fn forward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        self.base.reduce(self.forward_per_sample(y_true, y_pred))
+    }
+
+    fn backward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        // Cross Entropy Loss の勾配: dL/dy_pred = -w * y_true / y_pred（平均なら 1 / batch_size 倍）
         const EPSILON: f32 = 1e-7;
-        let loss = -y_true.iter()
-            .zip(y_pred.iter())
-            .map(|(y_true_i, y_pred_i)| {
-                let y_pred_clipped = (y_pred_i + EPSILON).min(1.0 - EPSILON);
-                y_true_i * y_pred_clipped.ln()
-            })
-            .sum::<f32>();
-        loss
-    }
-
-    fn backward(&self, y_true: &[f32], y_pred: &[f32]) -> Vec<f32> {
-        // Cross Entropy Loss の勾配: dL/dy_pred = -y_true / y_pred
+        let scale = self.base.reduction_scale(y_pred.dim(0));
+        weight_classes(y_true, self.class_weights.as_ref()).zip_map(y_pred, |y_true_i, y_pred_i| {
+            let y_pred_clipped = (y_pred_i + EPSILON).min(1.0 - EPSILON);
+            -y_true_i / y_pred_clipped * scale
+        })
+    }
+
+    fn forward_per_sample(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
         const EPSILON: f32 = 1e-7;
-        let gradient: Vec<f32> = y_true.iter()
-            .zip(y_pred.iter())
-            .map(|(y_true_i, y_pred_i)| {
-                let y_pred_clipped = (y_pred_i + EPSILON).min(1.0 - EPSILON);
-                -y_true_i / y_pred_clipped
-            })
-            .collect();
-        gradient
+        weight_classes(y_true, self.class_weights.as_ref()).zip_map(y_pred, |y_true_i, y_pred_i| {
+            let y_pred_clipped = (y_pred_i + EPSILON).min(1.0 - EPSILON);
+            -y_true_i * y_pred_clipped.ln()
+        }).flatten_batch().sum_axis(1)
     }
 
     fn name(&self) -> &str {
@@ -41,4 +54,21 @@ impl AbstractLossFunctionTrait for CrossEntropyLoss {
     }
 
     fn build(&mut self) {}
-}
\ No newline at end of file
+
+    fn reduction(&self) -> Reduction {
+        self.base.reduction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::tests::check_loss_reductions;
+
+    #[test]
+    fn cross_entropy_loss() {
+        let y_true = Tensor::new(vec![0.0, 1.0, 0.0, 0.0, 0.0, 1.0], vec![2, 3]);
+        let y_pred = Tensor::new(vec![0.2, 0.5, 0.3, 0.1, 0.3, 0.6], vec![2, 3]);
+        check_loss_reductions(|r| CrossEntropyLoss::new("cross_entropy_loss".to_string()).reduction(r), &y_true, &y_pred, 1e-4);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::losses::base_loss::{weight_classes, AbstractLossFunction, AbstractLossFunctionTrait, Reduction};
+use crate::losses::softmax_cross_entropy_loss::log_softmax_rows;
+use crate::tensor::Tensor;
+
+/// Focal Loss（入力は softmax 前のロジット）
+///
+/// L = -Σ_c a_c (1 - p_c)^γ ln p_c（p = softmax(logits)、a = class_weights * target）
+/// 正しく分類できているサンプル（p_c が 1 に近い）の損失を小さくして、難しいサンプルに学習を集中させる。
+/// gamma = 0 なら SoftmaxCrossEntropyLoss と同じ。
+pub struct FocalLoss {
+    base: AbstractLossFunction,
+    gamma: f32,
+    class_weights: Option<Tensor>,
+}
+
+impl FocalLoss {
+    pub fn new(name: String) -> Self {
+        Self { base: AbstractLossFunction::new(name), gamma: 2.0, class_weights: None }
+    }
+
+    /// 既定値は 2
+    pub fn gamma(mut self, gamma: f32) -> Self {
+        assert!(gamma >= 0.0, "gamma must be non-negative");
+        self.gamma = gamma;
+        self
+    }
+
+    /// クラスごとの重み（論文の α）
+    pub fn class_weights(mut self, class_weights: Vec<f32>) -> Self {
+        let num_classes = class_weights.len();
+        self.class_weights = Some(Tensor::new(class_weights, vec![num_classes]));
+        self
+    }
+
+    pub fn reduction(mut self, reduction: Reduction) -> Self {
+        self.base.reduction = reduction;
+        self
+    }
+}
+
+impl AbstractLossFunctionTrait for FocalLoss {
+    fn forward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        self.base.reduce(self.forward_per_sample(y_true, y_pred))
+    }
+
+    fn backward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        // h_c = p_c dL/dp_c = a_c (γ (1 - p_c)^(γ-1) p_c ln p_c - (1 - p_c)^γ)
+        // softmax を通すと dL/dx_j = h_j - p_j Σ_c h_c
+        // （p_c で割らない形にしてあるので、p_c が 0 に近くても発散しない）
+        let gamma = self.gamma;
+        let log_probs = log_softmax_rows(y_pred);
+        let weighted_target = weight_classes(y_true, self.class_weights.as_ref());
+        let h = weighted_target.zip_map(&log_probs, |a, log_p| {
+            if a == 0.0 {
+                return 0.0;
+            }
+            let p = log_p.exp();
+            let q = 1.0 - p;
+            // q = 0 のとき p ln p = 0 なので第 1 項は 0（γ < 1 で q^(γ-1) が発散するのを避ける）
+            let focusing = if q > 0.0 && gamma > 0.0 { gamma * q.powf(gamma - 1.0) * p * log_p } else { 0.0 };
+            a * (focusing - q.powf(gamma))
+        });
+        let probs = log_probs.map(f32::exp);
+        let h_sum = h.sum_axis(1).into_reshape(&[y_pred.dim(0), 1]);
+        (&h - &(&probs * &h_sum)).scale(self.base.reduction_scale(y_pred.dim(0)))
+    }
+
+    fn forward_per_sample(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        let gamma = self.gamma;
+        let log_probs = log_softmax_rows(y_pred);
+        weight_classes(y_true, self.class_weights.as_ref())
+            .zip_map(&log_probs, |a, log_p| -a * (1.0 - log_p.exp()).powf(gamma) * log_p)
+            .sum_axis(1)
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn build(&mut self) {}
+
+    fn reduction(&self) -> Reduction {
+        self.base.reduction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::tests::{check_loss_reductions, random_tensor};
+    use crate::losses::softmax_cross_entropy_loss::SoftmaxCrossEntropyLoss;
+
+    #[test]
+    fn focal_loss() {
+        let y_true = SoftmaxCrossEntropyLoss::one_hot(&[0, 2, 1], 3);
+        let logits = random_tensor(&[3, 3], 41).scale(2.0);
+        for gamma in [0.5, 2.0] {
+            check_loss_reductions(|r| FocalLoss::new("focal".to_string()).gamma(gamma).reduction(r), &y_true, &logits, 1e-3);
+        }
+        check_loss_reductions(
+            |r| FocalLoss::new("focal".to_string()).class_weights(vec![0.25, 1.0, 2.0]).reduction(r),
+            &y_true, &logits, 1e-3,
+        );
+
+        // gamma = 0 なら SoftmaxCrossEntropyLoss と同じ
+        let focal = FocalLoss::new("focal".to_string()).gamma(0.0);
+        let cross_entropy = SoftmaxCrossEntropyLoss::new("softmax_ce".to_string());
+        assert!((focal.forward(&y_true, &logits).item() - cross_entropy.forward(&y_true, &logits).item()).abs() < 1e-5);
+        for (a, b) in focal.backward(&y_true, &logits).as_slice().iter().zip(cross_entropy.backward(&y_true, &logits).as_slice()) {
+            assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+        }
+
+        // 正しく分類できているサンプルほど交差エントロピーより小さくなる
+        let easy = Tensor::new(vec![5.0, 0.0, 0.0], vec![1, 3]);
+        let one = SoftmaxCrossEntropyLoss::one_hot(&[0], 3);
+        let ratio = FocalLoss::new("focal".to_string()).forward(&one, &easy).item() / cross_entropy.forward(&one, &easy).item();
+        assert!(ratio < 1e-3, "{}", ratio);
+        // 確率がほぼ 1 でも発散しない
+        let saturated = Tensor::new(vec![100.0, 0.0, 0.0], vec![1, 3]);
+        let gamma_half = FocalLoss::new("focal".to_string()).gamma(0.5);
+        assert!(gamma_half.backward(&one, &saturated).as_slice().iter().all(|g| g.is_finite()));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::losses::base_loss::{AbstractLossFunction, AbstractLossFunctionTrait, Reduction};
+use crate::tensor::Tensor;
+
+/// 多クラスのヒンジ損失（Weston-Watkins 型、y_pred はスコア）
+///
+/// 正解クラス y（y_true の各行で最大の位置）について Σ_{i != y} max(0, margin - s_y + s_i)。
+pub struct HingeLoss {
+    base: AbstractLossFunction,
+    margin: f32,
+}
+
+impl HingeLoss {
+    /// 既定の margin は 1
+    pub fn new(name: String) -> Self {
+        Self { base: AbstractLossFunction::new(name), margin: 1.0 }
+    }
+
+    pub fn margin(mut self, margin: f32) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn reduction(mut self, reduction: Reduction) -> Self {
+        self.base.reduction = reduction;
+        self
+    }
+
+    /// 各サンプルの (正解クラス, スコアの行) について f(クラス番号, i, max(0, margin - s_y + s_i)) を呼ぶ
+    fn for_each_margin<F: FnMut(usize, usize, usize, f32)>(&self, y_true: &Tensor, y_pred: &Tensor, mut f: F) {
+        let scores = y_pred.flatten_batch();
+        for ((row, scores), class) in scores.rows().enumerate().zip(y_true.flatten_batch().argmax_rows()) {
+            for (i, &s) in scores.iter().enumerate() {
+                if i != class {
+                    f(row, class, i, (self.margin - scores[class] + s).max(0.0));
+                }
+            }
+        }
+    }
+}
+
+impl AbstractLossFunctionTrait for HingeLoss {
+    fn forward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        self.base.reduce(self.forward_per_sample(y_true, y_pred))
+    }
+
+    fn backward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        // マージンを破っているクラスごとに s_i へ +1、s_y へ -1
+        let num_classes = y_pred.dim(1);
+        let scale = self.base.reduction_scale(y_pred.dim(0));
+        let mut grad = Tensor::zeros(&[y_pred.dim(0), num_classes]);
+        let data = grad.as_mut_slice();
+        self.for_each_margin(y_true, y_pred, |row, class, i, violation| {
+            if violation > 0.0 {
+                data[row * num_classes + i] += scale;
+                data[row * num_classes + class] -= scale;
+            }
+        });
+        grad.into_reshape(y_pred.shape())
+    }
+
+    fn forward_per_sample(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        let mut losses = vec![0.0; y_pred.dim(0)];
+        self.for_each_margin(y_true, y_pred, |row, _, _, violation| losses[row] += violation);
+        Tensor::new(losses, vec![y_pred.dim(0)])
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn build(&mut self) {}
+
+    fn reduction(&self) -> Reduction {
+        self.base.reduction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::tests::{check_loss_reductions, random_tensor};
+
+    #[test]
+    fn hinge_loss() {
+        let y_true = Tensor::new(vec![0.0, 1.0, 0.0, 1.0, 0.0, 0.0], vec![2, 3]);
+        let scores = random_tensor(&[2, 3], 39).scale(2.0);
+        check_loss_reductions(|r| HingeLoss::new("hinge".to_string()).reduction(r), &y_true, &scores, 1e-4);
+
+        // 正解のスコア 2、他が 0.5 と 1.8: max(0, 1 - 2 + 0.5) + max(0, 1 - 2 + 1.8) = 0.8
+        let loss = HingeLoss::new("hinge".to_string());
+        let one = Tensor::new(vec![0.0, 1.0, 0.0], vec![1, 3]);
+        let s = Tensor::new(vec![0.5, 2.0, 1.8], vec![1, 3]);
+        assert!((loss.forward(&one, &s).item() - 0.8).abs() < 1e-6);
+        assert_eq!(loss.backward(&one, &s).to_vec(), vec![0.0, -1.0, 1.0]);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::losses::base_loss::{elementwise_mean_backward, elementwise_mean_per_sample, AbstractLossFunction, AbstractLossFunctionTrait, Reduction};
+use crate::tensor::Tensor;
+
+/// Huber 損失: 誤差 d が |d| <= delta なら 0.5 d^2、それ以外は delta (|d| - 0.5 delta)
+pub struct HuberLoss {
+    base: AbstractLossFunction,
+    delta: f32,
+}
+
+impl HuberLoss {
+    /// 既定の delta は 1
+    pub fn new(name: String) -> Self {
+        Self { base: AbstractLossFunction::new(name), delta: 1.0 }
+    }
+
+    pub fn delta(mut self, delta: f32) -> Self {
+        assert!(delta > 0.0, "delta must be positive");
+        self.delta = delta;
+        self
+    }
+
+    pub fn reduction(mut self, reduction: Reduction) -> Self {
+        self.base.reduction = reduction;
+        self
+    }
+}
+
+impl AbstractLossFunctionTrait for HuberLoss {
+    fn forward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        self.base.reduce(self.forward_per_sample(y_true, y_pred))
+    }
+
+    fn backward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        let delta = self.delta;
+        elementwise_mean_backward(&self.base, y_true, y_pred, |t, p| (p - t).clamp(-delta, delta))
+    }
+
+    fn forward_per_sample(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        let delta = self.delta;
+        elementwise_mean_per_sample(y_true, y_pred, |t, p| {
+            let d = (p - t).abs();
+            if d <= delta { 0.5 * d * d } else { delta * (d - 0.5 * delta) }
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn build(&mut self) {}
+
+    fn reduction(&self) -> Reduction {
+        self.base.reduction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::tests::{check_loss_reductions, random_tensor};
+
+    #[test]
+    fn huber_loss() {
+        let y_true = random_tensor(&[3, 4], 36);
+        let y_pred = random_tensor(&[3, 4], 37).scale(2.0);
+        check_loss_reductions(|r| HuberLoss::new("huber".to_string()).delta(0.5).reduction(r), &y_true, &y_pred, 1e-4);
+
+        // 誤差 [1, 3]、delta = 1: [0.5, 2.5] の平均
+        let t = Tensor::new(vec![0.0, 0.0], vec![1, 2]);
+        let p = Tensor::new(vec![1.0, -3.0], vec![1, 2]);
+        assert_eq!(HuberLoss::new("huber".to_string()).forward(&t, &p).item(), 1.5);
+    }
+}
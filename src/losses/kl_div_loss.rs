@@ -0,0 +1,64 @@
+use crate::losses::base_loss::{AbstractLossFunction, AbstractLossFunctionTrait, Reduction};
+use crate::tensor::Tensor;
+
+const EPSILON: f32 = 1e-7;
+
+/// KL ダイバージェンス KL(y_true || y_pred) = Σ t (ln t - ln p)（y_true / y_pred はどちらも確率分布）
+///
+/// t = 0 の項は 0 とする。y_pred は epsilon でクリップする。
+pub struct KlDivLoss {
+    base: AbstractLossFunction,
+}
+
+impl KlDivLoss {
+    pub fn new(name: String) -> Self {
+        Self { base: AbstractLossFunction::new(name) }
+    }
+
+    pub fn reduction(mut self, reduction: Reduction) -> Self {
+        self.base.reduction = reduction;
+        self
+    }
+}
+
+impl AbstractLossFunctionTrait for KlDivLoss {
+    fn forward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        self.base.reduce(self.forward_per_sample(y_true, y_pred))
+    }
+
+    fn backward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        // dKL/dp = -t / p
+        let scale = self.base.reduction_scale(y_pred.dim(0));
+        y_true.zip_map(y_pred, |t, p| -t / p.max(EPSILON) * scale)
+    }
+
+    fn forward_per_sample(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        y_true.zip_map(y_pred, |t, p| if t > 0.0 { t * (t.ln() - p.max(EPSILON).ln()) } else { 0.0 })
+            .flatten_batch()
+            .sum_axis(1)
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn build(&mut self) {}
+
+    fn reduction(&self) -> Reduction {
+        self.base.reduction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::tests::check_loss_reductions;
+
+    #[test]
+    fn kl_div_loss() {
+        let y_true = Tensor::new(vec![0.2, 0.5, 0.3, 0.0, 0.1, 0.9], vec![2, 3]);
+        let y_pred = Tensor::new(vec![0.3, 0.3, 0.4, 0.2, 0.2, 0.6], vec![2, 3]);
+        check_loss_reductions(|r| KlDivLoss::new("kl_div".to_string()).reduction(r), &y_true, &y_pred, 1e-4);
+        assert_eq!(KlDivLoss::new("kl_div".to_string()).forward(&y_true, &y_true).item(), 0.0);
+    }
+}
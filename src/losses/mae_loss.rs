@@ -0,0 +1,63 @@
+use crate::losses::base_loss::{elementwise_mean_backward, elementwise_mean_per_sample, AbstractLossFunction, AbstractLossFunctionTrait, Reduction};
+use crate::tensor::Tensor;
+
+/// 平均絶対誤差: サンプルごとに mean(|y_pred - y_true|)
+pub struct MaeLoss {
+    base: AbstractLossFunction,
+}
+
+impl MaeLoss {
+    pub fn new(name: String) -> Self {
+        Self { base: AbstractLossFunction::new(name) }
+    }
+
+    pub fn reduction(mut self, reduction: Reduction) -> Self {
+        self.base.reduction = reduction;
+        self
+    }
+}
+
+impl AbstractLossFunctionTrait for MaeLoss {
+    fn forward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        self.base.reduce(self.forward_per_sample(y_true, y_pred))
+    }
+
+    fn backward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        // sign(y_pred - y_true)（一致しているところは 0）
+        elementwise_mean_backward(&self.base, y_true, y_pred, |t, p| {
+            if p > t { 1.0 } else if p < t { -1.0 } else { 0.0 }
+        })
+    }
+
+    fn forward_per_sample(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        elementwise_mean_per_sample(y_true, y_pred, |t, p| (p - t).abs())
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn build(&mut self) {}
+
+    fn reduction(&self) -> Reduction {
+        self.base.reduction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::tests::{check_loss_reductions, random_tensor};
+
+    #[test]
+    fn mae_loss() {
+        let y_true = random_tensor(&[3, 4], 36);
+        let y_pred = random_tensor(&[3, 4], 37).scale(2.0);
+        check_loss_reductions(|r| MaeLoss::new("mae".to_string()).reduction(r), &y_true, &y_pred, 1e-4);
+
+        // 誤差 [1, 3]: [1, 3] の平均
+        let t = Tensor::new(vec![0.0, 0.0], vec![1, 2]);
+        let p = Tensor::new(vec![1.0, -3.0], vec![1, 2]);
+        assert_eq!(MaeLoss::new("mae".to_string()).forward(&t, &p).item(), 2.0);
+    }
+}
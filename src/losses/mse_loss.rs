@@ -0,0 +1,60 @@
+use crate::losses::base_loss::{elementwise_mean_backward, elementwise_mean_per_sample, AbstractLossFunction, AbstractLossFunctionTrait, Reduction};
+use crate::tensor::Tensor;
+
+/// 平均二乗誤差: サンプルごとに mean((y_pred - y_true)^2)
+pub struct MseLoss {
+    base: AbstractLossFunction,
+}
+
+impl MseLoss {
+    pub fn new(name: String) -> Self {
+        Self { base: AbstractLossFunction::new(name) }
+    }
+
+    pub fn reduction(mut self, reduction: Reduction) -> Self {
+        self.base.reduction = reduction;
+        self
+    }
+}
+
+impl AbstractLossFunctionTrait for MseLoss {
+    fn forward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        self.base.reduce(self.forward_per_sample(y_true, y_pred))
+    }
+
+    fn backward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        elementwise_mean_backward(&self.base, y_true, y_pred, |t, p| 2.0 * (p - t))
+    }
+
+    fn forward_per_sample(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        elementwise_mean_per_sample(y_true, y_pred, |t, p| (p - t) * (p - t))
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn build(&mut self) {}
+
+    fn reduction(&self) -> Reduction {
+        self.base.reduction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::tests::{check_loss_reductions, random_tensor};
+
+    #[test]
+    fn mse_loss() {
+        let y_true = random_tensor(&[3, 4], 36);
+        let y_pred = random_tensor(&[3, 4], 37).scale(2.0);
+        check_loss_reductions(|r| MseLoss::new("mse".to_string()).reduction(r), &y_true, &y_pred, 1e-4);
+
+        // 誤差 [1, 3]: [1, 9] の平均
+        let t = Tensor::new(vec![0.0, 0.0], vec![1, 2]);
+        let p = Tensor::new(vec![1.0, -3.0], vec![1, 2]);
+        assert_eq!(MseLoss::new("mse".to_string()).forward(&t, &p).item(), 5.0);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::losses::base_loss::{weight_classes, AbstractLossFunction, AbstractLossFunctionTrait, Reduction};
+use crate::tensor::Tensor;
+
+/// softmax と交差エントロピーをまとめた損失（入力は softmax 前のロジット）
+///
+/// forward は log-sum-exp で log softmax を求めるので、確率が 0 に近くても安定する。
+/// backward は (softmax(logits) - target) / batch_size で、softmax の Jacobian を通さない。
+/// モデルの最後に SoftmaxLayer を置かずに使う（`create_layers(..., false)`）。
+///
+/// label_smoothing = s のとき、正解の分布を (1 - s) * target + s / num_classes にする。
+/// class_weights を指定すると、クラス c の項に w_c を掛ける: -Σ w_c t_c log_softmax_c
+pub struct SoftmaxCrossEntropyLoss {
+    base: AbstractLossFunction,
+    label_smoothing: f32,
+    class_weights: Option<Tensor>,
+}
+
+/// 各行の log softmax: x - max - ln Σ exp(x - max)
+pub(crate) fn log_softmax_rows(logits: &Tensor) -> Tensor {
+    let logits = logits.flatten_batch();
+    let mut output = Vec::with_capacity(logits.len());
+    for row in logits.rows() {
+        let max = row.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let log_sum_exp = row.iter().map(|&x| (x - max).exp()).sum::<f32>().ln() + max;
+        output.extend(row.iter().map(|&x| x - log_sum_exp));
+    }
+    Tensor::new(output, logits.shape().to_vec())
+}
+
+impl SoftmaxCrossEntropyLoss {
+    pub fn new(name: String) -> Self {
+        Self { base: AbstractLossFunction::new(name), label_smoothing: 0.0, class_weights: None }
+    }
+
+    pub fn reduction(mut self, reduction: Reduction) -> Self {
+        self.base.reduction = reduction;
+        self
+    }
+
+    pub fn label_smoothing(mut self, label_smoothing: f32) -> Self {
+        assert!((0.0..=1.0).contains(&label_smoothing), "label_smoothing must be in [0, 1]");
+        self.label_smoothing = label_smoothing;
+        self
+    }
+
+    pub fn class_weights(mut self, class_weights: Vec<f32>) -> Self {
+        let num_classes = class_weights.len();
+        self.class_weights = Some(Tensor::new(class_weights, vec![num_classes]));
+        self
+    }
+
+    /// ラベルスムージングを適用した正解の分布
+    fn smoothed(&self, y_true: &Tensor) -> Tensor {
+        if self.label_smoothing == 0.0 {
+            return y_true.clone();
+        }
+        let num_classes = y_true.dim(y_true.ndim() - 1) as f32;
+        let s = self.label_smoothing;
+        y_true.map(|t| (1.0 - s) * t + s / num_classes)
+    }
+
+    /// クラス番号 [batch] を one-hot [batch, num_classes] にする
+    pub(crate) fn one_hot(classes: &[usize], num_classes: usize) -> Tensor {
+        let mut y_true = Tensor::zeros(&[classes.len(), num_classes]);
+        let data = y_true.as_mut_slice();
+        for (row, &class) in classes.iter().enumerate() {
+            assert!(class < num_classes, "class {} is out of range for {} classes", class, num_classes);
+            data[row * num_classes + class] = 1.0;
+        }
+        y_true
+    }
+
+    /// 正解をクラス番号で渡す版の forward（logits は [batch, num_classes]）
+    pub fn forward_classes(&self, classes: &[usize], logits: &Tensor) -> Tensor {
+        self.forward(&Self::one_hot(classes, logits.dim(1)), logits)
+    }
+
+    /// 正解をクラス番号で渡す版の backward
+    pub fn backward_classes(&self, classes: &[usize], logits: &Tensor) -> Tensor {
+        self.backward(&Self::one_hot(classes, logits.dim(1)), logits)
+    }
+}
+
+impl AbstractLossFunctionTrait for SoftmaxCrossEntropyLoss {
+    /// -Σ target * log_softmax(logits) を reduction でまとめる（既定はバッチ平均）
+    fn forward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        self.base.reduce(self.forward_per_sample(y_true, y_pred))
+    }
+
+    fn backward(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        // dL/dlogits = softmax(logits) * Σ_c a_c - a（a = w * target、重みがなければ softmax - target）
+        // 平均なら 1 / batch_size 倍
+        let probs = log_softmax_rows(y_pred).map(f32::exp);
+        let weighted_target = weight_classes(&self.smoothed(y_true), self.class_weights.as_ref());
+        let total_weight = weighted_target.sum_axis(1).into_reshape(&[y_pred.dim(0), 1]);
+        (&(&probs * &total_weight) - &weighted_target).scale(self.base.reduction_scale(y_pred.dim(0)))
+    }
+
+    fn forward_per_sample(&self, y_true: &Tensor, y_pred: &Tensor) -> Tensor {
+        let log_probs = log_softmax_rows(y_pred);
+        let weighted_target = weight_classes(&self.smoothed(y_true), self.class_weights.as_ref());
+        (&weighted_target * &log_probs).sum_axis(1).scale(-1.0)
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn build(&mut self) {}
+
+    fn reduction(&self) -> Reduction {
+        self.base.reduction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gradcheck::{check_loss, GradCheckOptions};
+    use crate::gradcheck::tests::{assert_passed, check_loss_reductions, random_tensor};
+    use crate::layers::base_layer::AbstractLayerTrait;
+    use crate::layers::softmax_layer::SoftmaxLayer;
+    use crate::losses::cross_entropy_loss::CrossEntropyLoss;
+
+    #[test]
+    fn softmax_cross_entropy_loss() {
+        let y_true = Tensor::new(vec![0.0, 1.0, 0.0, 0.0, 0.0, 1.0], vec![2, 3]);
+        let logits = random_tensor(&[2, 3], 35).scale(3.0);
+        let loss = SoftmaxCrossEntropyLoss::new("softmax_cross_entropy_loss".to_string());
+        assert_passed(&check_loss(&loss, &y_true, &logits, &GradCheckOptions::default()));
+        let smoothed = SoftmaxCrossEntropyLoss::new("softmax_cross_entropy_loss".to_string()).label_smoothing(0.1);
+        assert_passed(&check_loss(&smoothed, &y_true, &logits, &GradCheckOptions::default()));
+
+        // SoftmaxLayer + CrossEntropyLoss と同じ値と勾配になる
+        let mut softmax = SoftmaxLayer::new("softmax".to_string(), 3, 3);
+        let cross_entropy = CrossEntropyLoss::new("cross_entropy_loss".to_string());
+        let probs = softmax.forward(&logits);
+        assert!((loss.forward(&y_true, &logits).item() - cross_entropy.forward(&y_true, &probs).item()).abs() < 1e-5);
+        let unfused = softmax.backward(&cross_entropy.backward(&y_true, &probs));
+        for (a, b) in loss.backward(&y_true, &logits).as_slice().iter().zip(unfused.as_slice()) {
+            assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+        }
+
+        // クラス番号でも渡せる
+        assert_eq!(loss.forward_classes(&[1, 2], &logits).item(), loss.forward(&y_true, &logits).item());
+        assert_eq!(loss.backward_classes(&[1, 2], &logits).to_vec(), loss.backward(&y_true, &logits).to_vec());
+    }
+
+    #[test]
+    fn softmax_cross_entropy_loss_is_stable_for_large_logits() {
+        let loss = SoftmaxCrossEntropyLoss::new("softmax_cross_entropy_loss".to_string());
+        let logits = Tensor::new(vec![1000.0, -1000.0, 0.0], vec![1, 3]);
+        assert!((loss.forward_classes(&[1], &logits).item() - 2000.0).abs() < 1e-2);
+        assert_eq!(loss.forward_classes(&[0], &logits).item(), 0.0);
+        assert_eq!(loss.backward_classes(&[1], &logits).to_vec(), vec![1.0, -1.0, 0.0]);
+    }
+
+    #[test]
+    fn class_weighted_cross_entropy_losses() {
+        let classes = [1, 2, 0];
+        let y_true = SoftmaxCrossEntropyLoss::one_hot(&classes, 3);
+        let logits = random_tensor(&[3, 3], 40).scale(2.0);
+        let weights = vec![0.5, 2.0, 1.5];
+        check_loss_reductions(
+            |r| SoftmaxCrossEntropyLoss::new("softmax_ce".to_string()).class_weights(weights.clone()).label_smoothing(0.1).reduction(r),
+            &y_true, &logits, 1e-3,
+        );
+        let probs = log_softmax_rows(&logits).map(f32::exp);
+        let options = GradCheckOptions { epsilon: 1e-4, ..GradCheckOptions::default() };
+        let cross_entropy = CrossEntropyLoss::new("cross_entropy_loss".to_string()).class_weights(weights.clone());
+        assert_passed(&check_loss(&cross_entropy, &y_true, &probs, &options));
+
+        // one-hot の正解なら、各サンプルの損失は重みなしの損失に w[正解クラス] を掛けたもの
+        let weighted = SoftmaxCrossEntropyLoss::new("softmax_ce".to_string()).class_weights(weights.clone());
+        let plain = SoftmaxCrossEntropyLoss::new("softmax_ce".to_string());
+        let expected = plain.forward_per_sample(&y_true, &logits);
+        for ((a, b), &class) in weighted.forward_per_sample(&y_true, &logits).as_slice().iter().zip(expected.as_slice()).zip(&classes) {
+            assert!((a - weights[class] * b).abs() < 1e-5, "{} != {}", a, weights[class] * b);
+        }
+        assert!((cross_entropy.forward(&y_true, &probs).item() - weighted.forward(&y_true, &logits).item()).abs() < 1e-5);
+    }
+}
@@ -1,27 +1,21 @@
-mod layers;
-mod model;
-mod activation;
-mod data;
-mod losses;
-mod optimizers;
-mod trainer;
+use nn_rust::layers::base_layer::AbstractLayerTrait;
+use nn_rust::layers::utils::create_layers;
+use nn_rust::layers::utils::create_cnn_layers;
+use nn_rust::layers::utils::print_layers;
 
-use layers::base_layer::AbstractLayerTrait;
-use layers::utils::create_layers;
-use layers::utils::print_layers;
+use nn_rust::model::Model;
+use nn_rust::model::create_model;
 
-use model::Model;
-use model::create_model;
+use nn_rust::data::{DataSet, SplitOptions};
 
-use data::DataSet;
+use nn_rust::losses::base_loss::AbstractLossFunctionTrait;
+use nn_rust::losses::softmax_cross_entropy_loss::SoftmaxCrossEntropyLoss;
 
-use losses::base_loss::AbstractLossFunctionTrait;
-use losses::cross_entropy_loss::CrossEntropyLoss;
+use nn_rust::optimizers::base_optimizer::AbstractOptimizerTrait;
+use nn_rust::optimizers::sgd::{Sgd, SgdParams};
 
-use optimizers::base_optimizer::AbstractOptimizerTrait;
-use optimizers::sgd::{Sgd, SgdParams};
-
-use trainer::Trainer;
+use nn_rust::tensor::Tensor;
+use nn_rust::trainer::Trainer;
 
 fn main() {
 
@@ -34,12 +28,24 @@ fn main() {
     let width: usize = 28;
 
     // レイヤーをまとめる配列
-    let use_softmax: bool = true;
-    let layers: Vec<Box<dyn AbstractLayerTrait>> = create_layers(
-        vec![height * width, 1024, 1024, 10], 
-        "relu".to_string(), 
-        use_softmax
-    );
+    // softmax は損失関数（SoftmaxCrossEntropyLoss）の中で計算するので、モデルには入れない
+    let use_softmax: bool = false;
+    let use_cnn: bool = false;
+    let layers: Vec<Box<dyn AbstractLayerTrait>> = if use_cnn {
+        create_cnn_layers(
+            (1, height, width),
+            vec![8, 16],
+            vec![128, 10],
+            "relu".to_string(),
+            use_softmax
+        )
+    } else {
+        create_layers(
+            vec![height * width, 1024, 1024, 10], 
+            "relu".to_string(), 
+            use_softmax
+        )
+    };
     
     // & を付けることで layers の所有権を借用できる
     print_layers(&layers);
@@ -48,8 +54,14 @@ fn main() {
     let mut model: Model = create_model(layers);
     model.build();
 
-    // データを読み込む
-    let mnist_data: DataSet = DataSet::load_from_binary("data/mnist.bin").unwrap();
+    // データを読み込む（IDX ファイルがあればそれを使い、なければ load_mnist.py で作った mnist.bin を使う）
+    let idx_images = "data/train-images-idx3-ubyte.gz";
+    let idx_labels = "data/train-labels-idx1-ubyte.gz";
+    let mnist_data: DataSet = if std::path::Path::new(idx_images).exists() {
+        DataSet::load_idx(idx_images, idx_labels).unwrap()
+    } else {
+        DataSet::load_from_binary("data/mnist.bin").unwrap()
+    };
     println!("data: {:?}", mnist_data.get_image(0).expect("Failed to get image").len());
     println!("data: {:?}", mnist_data.get_label(0).expect("Failed to get label"));
 
@@ -57,24 +69,24 @@ fn main() {
     mnist_data.display_image(0);
 
     // 損失関数を作成
-    let mut loss_function: CrossEntropyLoss = CrossEntropyLoss::new("cross_entropy_loss".to_string());
+    let loss_function: SoftmaxCrossEntropyLoss = SoftmaxCrossEntropyLoss::new("softmax_cross_entropy_loss".to_string());
 
     let index: usize = 0;
-    let input: Vec<f32> = mnist_data.get_image(index).expect("Failed to get image").to_vec();
-    let label: u8 = mnist_data.get_label(index).expect("Failed to get label");
-    let mut true_labels = vec![0.0; 10];
-    true_labels[label as usize] = 1.0;
+    let input: Tensor = mnist_data.images.select_rows(&[index]);
+    let label: u32 = mnist_data.get_label(index).expect("Failed to get label");
+    let mut true_labels: Tensor = Tensor::zeros(&[1, 10]);
+    true_labels.as_mut_slice()[label as usize] = 1.0;
     
     // モデルを forward する
-    let output: Vec<f32> = model.forward(&input);
+    let output: Tensor = model.forward(&input);
 
     // 損失関数を forward する
-    let loss: f32 = loss_function.forward(&true_labels, &output);
+    let loss: f32 = loss_function.forward(&true_labels, &output).item();
     println!("loss: {:?}", loss);
 
     // 損失関数を backward する
-    let loss_grad: Vec<f32> = loss_function.backward(&true_labels, &output);
-    println!("loss_grad: {:?}", loss_grad);
+    let loss_grad: Tensor = loss_function.backward(&true_labels, &output);
+    println!("loss_grad: {:?}", loss_grad.as_slice());
 
     // モデルを backward する（勾配を計算して保存しただけ）
     model.backward(&loss_grad);
@@ -87,23 +99,19 @@ fn main() {
         .verbose(true)
     );
 
-    let weights_before: Vec<f32> = model.layers[1].w().clone();
+    let weights_before: Tensor = model.layers[1].w().expect("layer has no weights").clone();
 
     // オプティマイザーを update することでモデルのパラメータを更新
     optimizer.update(&mut model);
 
     // 更新できたか比較して確認
-    let weights_after: Vec<f32> = model.layers[1].w().clone();
-    let weight_diff: f32 = weights_before.iter()
-        .zip(weights_after.iter())
-        .map(|(b, a)| a - b)
-        .sum::<f32>()
-        .abs();
+    let weights_after: Tensor = model.layers[1].w().expect("layer has no weights").clone();
+    let weight_diff: f32 = (&weights_after - &weights_before).sum().abs();
     println!("weight difference: {:?}", weight_diff);
 
-    // Trainer を作成
-    let (train_dataset, test_dataset) = mnist_data.split_dataset(0.01);
-    let mut trainer: Trainer<Sgd, CrossEntropyLoss> = Trainer::new(
+    // Trainer を作成（クラスの割合をそろえて 8:2 に分ける）
+    let (train_dataset, test_dataset) = mnist_data.split_with(0.8, &SplitOptions::new().seed(42).stratified(true));
+    let mut trainer: Trainer<Sgd, SoftmaxCrossEntropyLoss> = Trainer::new(
         model,
         optimizer,
         loss_function,
@@ -115,4 +123,7 @@ fn main() {
         true
     );
     trainer.run();
+
+    // 学習済みモデルを保存
+    trainer.model.save("data/model.bin").expect("Failed to save model");
 }
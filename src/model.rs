@@ -1,12 +1,39 @@
+use std::path::Path;
+
+use crate::checkpoint::{read_checkpoint, write_checkpoint, CheckpointError, ModelCheckpoint};
 use crate::layers::base_layer::AbstractLayerTrait;
+use crate::tensor::Tensor;
 
 pub struct Model {
     pub layers: Vec<Box<dyn AbstractLayerTrait>>,
+    training: bool,
 }
 
 impl Model {
+    /// 作成直後は学習モード
     pub fn new(layers: Vec<Box<dyn AbstractLayerTrait>>) -> Self {
-        Self { layers }
+        Self { layers, training: true }
+    }
+
+    /// 学習モードにする（ドロップアウトなどが有効になる）
+    pub fn train(&mut self) {
+        self.set_training(true);
+    }
+
+    /// 推論モードにする（評価や予測の前に呼ぶ）
+    pub fn eval(&mut self) {
+        self.set_training(false);
+    }
+
+    pub fn is_training(&self) -> bool {
+        self.training
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.training = training;
+        for layer in &mut self.layers {
+            layer.set_training(training);
+        }
     }
 
     pub fn build(&mut self) {
@@ -15,25 +42,270 @@ impl Model {
         }
     }
 
-    pub fn forward(&mut self, x: &Vec<f32>) -> Vec<f32> {
-        let mut y: Vec<f32> = x.clone();
+    /// x は先頭の軸をバッチとするテンソル（[batch_size, ...]）
+    pub fn forward(&mut self, x: &Tensor) -> Tensor {
+        let mut y: Tensor = x.clone();
         for layer in &mut self.layers {
             y = layer.forward(&y);
         }
         y
     }
 
-    pub fn backward(&mut self, loss_grad: &[f32]) {
-        let mut grad = loss_grad.to_vec();
+    /// 各レイヤーの grad_w / grad_b に勾配を加算する
+    pub fn backward(&mut self, loss_grad: &Tensor) {
+        let mut grad = loss_grad.clone();
         
         // レイヤーを逆順に処理
         for layer in self.layers.iter_mut().rev() {
             grad = layer.backward(&grad);
         }
     }
+
+    pub fn zero_grad(&mut self) {
+        for layer in &mut self.layers {
+            layer.zero_grad();
+        }
+    }
+
+    /// 全レイヤーの grad_w / grad_b をまとめた L2 ノルム
+    pub fn grad_norm(&self) -> f32 {
+        let mut sum_square = 0.0;
+        for layer in &self.layers {
+            for grad in [layer.grad_w(), layer.grad_b()].into_iter().flatten() {
+                sum_square += grad.as_slice().iter().map(|g| g * g).sum::<f32>();
+            }
+        }
+        sum_square.sqrt()
+    }
+
+    /// 勾配の各要素を [-max_value, max_value] に収める
+    pub fn clip_grad_value(&mut self, max_value: f32) {
+        self.for_each_grad_mut(|g| *g = g.clamp(-max_value, max_value));
+    }
+
+    /// 全体の L2 ノルムが max_norm を超えていたら、全勾配を同じ比率で縮める
+    /// 戻り値はクリップ前のノルム
+    pub fn clip_grad_norm(&mut self, max_norm: f32) -> f32 {
+        let norm = self.grad_norm();
+        if norm > max_norm {
+            let scale = max_norm / norm;
+            self.for_each_grad_mut(|g| *g *= scale);
+        }
+        norm
+    }
+
+    fn for_each_grad_mut<F: Fn(&mut f32)>(&mut self, f: F) {
+        for layer in &mut self.layers {
+            if let Some(grad_w) = layer.grad_w_mut() {
+                grad_w.as_mut_slice().iter_mut().for_each(&f);
+            }
+            if let Some(grad_b) = layer.grad_b_mut() {
+                grad_b.as_mut_slice().iter_mut().for_each(&f);
+            }
+        }
+    }
+
+    /// レイヤー構成とパラメータをバージョン付きのバイナリファイルに保存する
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), CheckpointError> {
+        let checkpoint = ModelCheckpoint {
+            layers: self.layers.iter().map(|layer| layer.state()).collect(),
+        };
+        write_checkpoint(path, &checkpoint)
+    }
+
+    /// save() で保存したファイルからモデルを復元する
+    /// 重みは復元済みなので build() を呼ぶと初期化し直されてしまう点に注意
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, CheckpointError> {
+        let checkpoint = read_checkpoint(path)?;
+        let layers = checkpoint.layers
+            .into_iter()
+            .map(|state| state.into_layer())
+            .collect();
+        Ok(Self::new(layers))
+    }
 }
 
 // instance method but not need to use?
 pub fn create_model(layers: Vec<Box<dyn AbstractLayerTrait>>) -> Model {
     Model::new(layers)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkpoint::{LayerState, CHECKPOINT_MAGIC, CHECKPOINT_VERSION};
+    use crate::layers::batch_norm_layer::{BatchNormLayer, BatchNormParams};
+    use crate::layers::dropout_layer::DropoutLayer;
+    use crate::layers::fc_layer::FcLayer;
+    use crate::layers::layer_norm_layer::LayerNormLayer;
+    use crate::layers::softmax_layer::SoftmaxLayer;
+    use crate::optimizers::base_optimizer::AbstractOptimizerTrait;
+    use crate::optimizers::sgd::{Sgd, SgdParams};
+    use std::path::PathBuf;
+
+    /// grad_w = [3, 0, -4, 0]、grad_b = [0, 0] のモデル（ノルム 5）
+    fn model_with_gradients() -> Model {
+        let fc = FcLayer::from_parameters("fc".to_string(), 2, 2, "identity".to_string(), vec![0.0; 4], vec![0.0; 2]);
+        let mut model = Model::new(vec![Box::new(fc), Box::new(SoftmaxLayer::new("softmax".to_string(), 2, 2))]);
+        model.layers[0].grad_w_mut().unwrap().as_mut_slice().copy_from_slice(&[3.0, 0.0, -4.0, 0.0]);
+        model
+    }
+
+    #[test]
+    fn clip_grad_norm_scales_all_gradients() {
+        let mut model = model_with_gradients();
+        assert_eq!(model.grad_norm(), 5.0);
+        assert_eq!(model.clip_grad_norm(10.0), 5.0);
+        assert_eq!(model.layers[0].grad_w().unwrap().to_vec(), vec![3.0, 0.0, -4.0, 0.0]);
+
+        // 戻り値はクリップ前のノルム
+        assert_eq!(model.clip_grad_norm(1.0), 5.0);
+        let clipped = model.layers[0].grad_w().unwrap().to_vec();
+        assert!(clipped.iter().zip([0.6, 0.0, -0.8, 0.0]).all(|(a, e)| (a - e).abs() < 1e-6));
+        assert!((model.grad_norm() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn clip_grad_value_clamps_each_element() {
+        let mut model = model_with_gradients();
+        model.clip_grad_value(2.0);
+        assert_eq!(model.layers[0].grad_w().unwrap().to_vec(), vec![2.0, 0.0, -2.0, 0.0]);
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("nn_rust_model_{}_{}", std::process::id(), name))
+    }
+
+    fn fc_state(name: &str, i_size: usize, o_size: usize) -> LayerState {
+        let w = (0..i_size * o_size).map(|i| (i as f32 * 0.37).sin()).collect();
+        let b = (0..o_size).map(|i| i as f32 * 0.1).collect();
+        LayerState::Fc { name: name.to_string(), i_size, o_size, activation_type: "relu".to_string(), w, b }
+    }
+
+    /// 保存して読み込んだ結果（ファイルは消す）
+    fn save_and_load(model: &Model, name: &str) -> Model {
+        let path = temp_path(name);
+        model.save(&path).unwrap();
+        let loaded = Model::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        loaded.unwrap()
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut model = Model::new(vec![
+            fc_state("fc1", 3, 4).into_layer(),
+            fc_state("fc2", 4, 2).into_layer(),
+            LayerState::Softmax { name: "softmax".to_string(), i_size: 2, o_size: 2 }.into_layer(),
+        ]);
+        let mut loaded = save_and_load(&model, "round_trip");
+        let x = Tensor::new(vec![0.5, -1.0, 2.0, 1.5, 0.0, -0.5], vec![2, 3]);
+        assert_eq!(loaded.forward(&x), model.forward(&x));
+        let states = |model: &Model| model.layers.iter().map(|layer| layer.state()).collect::<Vec<_>>();
+        assert_eq!(states(&loaded), states(&model));
+    }
+
+    /// bytes を書いたファイルを読み込んだときのエラー
+    fn load_error(name: &str, bytes: &[u8]) -> CheckpointError {
+        let path = temp_path(name);
+        std::fs::write(&path, bytes).unwrap();
+        let result = Model::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        result.err().unwrap_or_else(|| panic!("{} should fail", name))
+    }
+
+    #[test]
+    fn load_rejects_corrupt_files() {
+        let path = temp_path("valid");
+        Model::new(vec![fc_state("fc", 3, 2).into_layer()]).save(&path).unwrap();
+        let valid = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(valid[..4], CHECKPOINT_MAGIC);
+
+        let mut bad_magic = valid.clone();
+        bad_magic[..4].copy_from_slice(b"NNRX");
+        assert!(matches!(load_error("magic", &bad_magic), CheckpointError::InvalidMagic(magic) if &magic == b"NNRX"));
+
+        let mut bad_version = valid.clone();
+        bad_version[4..8].copy_from_slice(&(CHECKPOINT_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            load_error("version", &bad_version),
+            CheckpointError::UnsupportedVersion { found, expected } if found == CHECKPOINT_VERSION + 1 && expected == CHECKPOINT_VERSION
+        ));
+
+        assert!(matches!(load_error("truncated", &valid[..valid.len() - 5]), CheckpointError::Deserialize(_)));
+        assert!(matches!(load_error("trailing", &[valid.as_slice(), &[0]].concat()), CheckpointError::Deserialize(_)));
+        let header_only = load_error("header", &valid[..6]);
+        assert!(matches!(&header_only, CheckpointError::Io(e) if e.kind() == std::io::ErrorKind::UnexpectedEof), "{}", header_only);
+    }
+
+    #[test]
+    fn load_rejects_inconsistent_layers() {
+        let cases = [
+            // 重みの数が i_size * o_size と合わない
+            ("weights", vec![LayerState::Fc { name: "fc".to_string(), i_size: 3, o_size: 2, activation_type: "relu".to_string(), w: vec![0.0; 5], b: vec![0.0; 2] }], 0),
+            // 前のレイヤーの出力サイズと入力サイズが合わない
+            ("chain", vec![fc_state("fc1", 3, 4), fc_state("fc2", 5, 2)], 1),
+            // 知らない活性化関数（identity として読み込まない）
+            ("activation", vec![fc_state("fc1", 3, 4), LayerState::Fc { name: "fc2".to_string(), i_size: 4, o_size: 2, activation_type: "tanh".to_string(), w: vec![0.0; 8], b: vec![0.0; 2] }], 1),
+        ];
+        for (name, layers, expected_index) in cases {
+            let path = temp_path(name);
+            write_checkpoint(&path, &ModelCheckpoint { layers }).unwrap();
+            let result = Model::load(&path);
+            std::fs::remove_file(&path).unwrap();
+            match result {
+                Err(CheckpointError::InvalidLayer { index, .. }) => assert_eq!(index, expected_index, "{}", name),
+                other => panic!("{}: expected InvalidLayer, got {:?}", name, other.err()),
+            }
+        }
+    }
+
+    #[test]
+    fn save_and_load_keeps_normalization_and_dropout_state() {
+        let mut model = Model::new(vec![
+            fc_state("fc", 3, 4).into_layer(),
+            Box::new(BatchNormLayer::new("batch_norm".to_string(), BatchNormParams::new(4).momentum(0.3))),
+            Box::new(DropoutLayer::new("dropout".to_string(), 4, 0.25, 7)),
+            Box::new(LayerNormLayer::new("layer_norm".to_string(), 4, 1e-4)),
+        ]);
+        model.build();
+        let mut optimizer = Sgd::new("sgd".to_string());
+        optimizer.build(SgdParams::new().learning_rate(0.1));
+
+        // 何ステップか学習して、running_mean / running_var と gamma / beta を初期値から動かす
+        model.train();
+        for step in 0..5 {
+            let x = Tensor::new((0..15).map(|i| ((i * 7 + step * 3) % 11) as f32 - 4.0).collect(), vec![5, 3]);
+            model.zero_grad();
+            let y = model.forward(&x);
+            model.backward(&y.map(|v| v - 1.0));
+            optimizer.update(&mut model);
+        }
+        let state = model.layers[1].state();
+        let LayerState::BatchNorm { running_mean, running_var, gamma, .. } = &state else { panic!("expected batch norm") };
+        assert!(running_mean.iter().any(|&m| m != 0.0) && running_var.iter().any(|&v| v != 1.0));
+        assert!(gamma.iter().any(|&g| g != 1.0));
+
+        let mut loaded = save_and_load(&model, "normalization");
+        for (original, restored) in model.layers.iter().zip(&loaded.layers) {
+            assert_eq!(restored.state(), original.state(), "{}", original.name());
+        }
+        model.eval();
+        loaded.eval();
+        let x = Tensor::new(vec![1.0, -2.0, 0.5, 3.0, 0.0, -1.0], vec![2, 3]);
+        assert_eq!(loaded.forward(&x), model.forward(&x));
+    }
+
+    #[test]
+    fn model_eval_disables_dropout() {
+        let mut model = Model::new(vec![Box::new(DropoutLayer::new("dropout".to_string(), 8, 0.9, 18))]);
+        let x = Tensor::new((1..=16).map(|i| i as f32).collect(), vec![2, 8]);
+        assert_ne!(model.forward(&x).to_vec(), x.to_vec());
+        model.eval();
+        assert!(!model.is_training());
+        assert_eq!(model.forward(&x).to_vec(), x.to_vec());
+        model.train();
+        assert_ne!(model.forward(&x).to_vec(), x.to_vec());
+    }
+}
@@ -1,2 +1,6 @@
+pub mod adagrad;
+pub mod adam;
+pub mod adamw;
 pub mod base_optimizer;
-pub mod sgd;
\ No newline at end of file
+pub mod rmsprop;
+pub mod sgd;
@@ -0,0 +1,101 @@
+use crate::checkpoint::{CheckpointError, OptimizerState};
+use crate::model::Model;
+use crate::optimizers::base_optimizer::{apply_updates, check_state_kind, AbstractOptimizer, AbstractOptimizerTrait, ParamBuffers};
+use crate::tensor::Tensor;
+
+/// Adagrad: s += g^2, delta = -lr * g / (sqrt(s) + eps)
+pub struct Adagrad {
+    base: AbstractOptimizer,
+    epsilon: f32,
+    step: u64,
+    // パラメータごとに [勾配の 2 乗の累積和]
+    buffers: ParamBuffers,
+}
+
+impl Adagrad {
+    pub const KIND: &'static str = "adagrad";
+
+    pub fn new(name: String) -> Self {
+        Self { base: AbstractOptimizer::new(name), epsilon: 1e-10, step: 0, buffers: ParamBuffers::new(1) }
+    }
+}
+
+#[derive(Default)]
+pub struct AdagradParams {
+    pub learning_rate: Option<f32>,
+    pub epsilon: Option<f32>,
+    pub verbose: Option<bool>,
+}
+
+impl AdagradParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 既定値は 0.01
+    pub fn learning_rate(mut self, learning_rate: f32) -> Self {
+        self.learning_rate = Some(learning_rate);
+        self
+    }
+
+    /// 既定値は 1e-10
+    pub fn epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = Some(epsilon);
+        self
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = Some(verbose);
+        self
+    }
+}
+
+impl AbstractOptimizerTrait for Adagrad {
+    type Params = AdagradParams;
+
+    fn update(&mut self, model: &mut Model) {
+        let learning_rate = self.base.learning_rate.unwrap();
+        let epsilon = self.epsilon;
+        let buffers = &mut self.buffers;
+        self.step += 1;
+        apply_updates(model, |key, _param, grad| {
+            let grad = grad.contiguous();
+            let [sum_square] = buffers.get_mut(key, grad.shape()) else { unreachable!() };
+            let mut delta = Vec::with_capacity(grad.len());
+            for (s, &g) in sum_square.as_mut_slice().iter_mut().zip(grad.as_slice()) {
+                *s += g * g;
+                delta.push(-learning_rate * g / (s.sqrt() + epsilon));
+            }
+            Tensor::new(delta, grad.shape().to_vec())
+        });
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn build(&mut self, params: Self::Params) {
+        self.base.learning_rate = Some(params.learning_rate.unwrap_or(1e-2));
+        self.base.verbose = params.verbose;
+        self.epsilon = params.epsilon.unwrap_or(1e-10);
+    }
+
+    fn learning_rate(&self) -> Option<f32> {
+        self.base.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.base.learning_rate = Some(learning_rate);
+    }
+
+    fn state(&self) -> OptimizerState {
+        OptimizerState { kind: Self::KIND.to_string(), step: self.step, buffers: self.buffers.to_state() }
+    }
+
+    fn set_state(&mut self, state: OptimizerState, model: &Model) -> Result<(), CheckpointError> {
+        check_state_kind(&state, Self::KIND)?;
+        self.buffers = ParamBuffers::from_state(1, state.buffers, model)?;
+        self.step = state.step;
+        Ok(())
+    }
+}
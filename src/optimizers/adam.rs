@@ -0,0 +1,165 @@
+use crate::checkpoint::{CheckpointError, OptimizerState, ParamKey};
+use crate::model::Model;
+use crate::optimizers::base_optimizer::{apply_updates, check_state_kind, AbstractOptimizer, AbstractOptimizerTrait, ParamBuffers};
+use crate::tensor::Tensor;
+
+/// Adam / AdamW で共通の 1 次・2 次モーメントとステップ数
+#[derive(Debug, Clone)]
+pub(crate) struct AdamMoments {
+    pub beta1: f32,
+    pub beta2: f32,
+    pub epsilon: f32,
+    pub step: u64,
+    // パラメータごとに [m, v]
+    pub buffers: ParamBuffers,
+}
+
+impl AdamMoments {
+    pub fn new() -> Self {
+        Self { beta1: 0.9, beta2: 0.999, epsilon: 1e-8, step: 0, buffers: ParamBuffers::new(2) }
+    }
+
+    /// モーメントを更新し、バイアス補正した -lr * m_hat / (sqrt(v_hat) + eps) を返す
+    pub fn delta(&mut self, key: ParamKey, grad: &Tensor, learning_rate: f32) -> Tensor {
+        let (beta1, beta2, epsilon) = (self.beta1, self.beta2, self.epsilon);
+        let bias_correction1 = 1.0 - beta1.powi(self.step as i32);
+        let bias_correction2 = 1.0 - beta2.powi(self.step as i32);
+
+        let grad = grad.contiguous();
+        let [m, v] = self.buffers.get_mut(key, grad.shape()) else { unreachable!() };
+        let mut delta = Vec::with_capacity(grad.len());
+        for ((m, v), &g) in m.as_mut_slice().iter_mut().zip(v.as_mut_slice()).zip(grad.as_slice()) {
+            *m = beta1 * *m + (1.0 - beta1) * g;
+            *v = beta2 * *v + (1.0 - beta2) * g * g;
+            delta.push(-learning_rate * (*m / bias_correction1) / ((*v / bias_correction2).sqrt() + epsilon));
+        }
+        Tensor::new(delta, grad.shape().to_vec())
+    }
+
+    pub fn state(&self, kind: &str) -> OptimizerState {
+        OptimizerState { kind: kind.to_string(), step: self.step, buffers: self.buffers.to_state() }
+    }
+
+    pub fn set_state(&mut self, kind: &str, state: OptimizerState, model: &Model) -> Result<(), CheckpointError> {
+        check_state_kind(&state, kind)?;
+        self.buffers = ParamBuffers::from_state(2, state.buffers, model)?;
+        self.step = state.step;
+        Ok(())
+    }
+}
+
+/// Adam（weight_decay は勾配に加える L2 正則化）
+pub struct Adam {
+    base: AbstractOptimizer,
+    moments: AdamMoments,
+    weight_decay: f32,
+}
+
+impl Adam {
+    pub const KIND: &'static str = "adam";
+
+    pub fn new(name: String) -> Self {
+        Self { base: AbstractOptimizer::new(name), moments: AdamMoments::new(), weight_decay: 0.0 }
+    }
+}
+
+#[derive(Default)]
+pub struct AdamParams {
+    pub learning_rate: Option<f32>,
+    pub beta1: Option<f32>,
+    pub beta2: Option<f32>,
+    pub epsilon: Option<f32>,
+    pub weight_decay: Option<f32>,
+    pub verbose: Option<bool>,
+}
+
+impl AdamParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 既定値は 0.001
+    pub fn learning_rate(mut self, learning_rate: f32) -> Self {
+        self.learning_rate = Some(learning_rate);
+        self
+    }
+
+    /// 既定値は 0.9
+    pub fn beta1(mut self, beta1: f32) -> Self {
+        self.beta1 = Some(beta1);
+        self
+    }
+
+    /// 既定値は 0.999
+    pub fn beta2(mut self, beta2: f32) -> Self {
+        self.beta2 = Some(beta2);
+        self
+    }
+
+    /// 既定値は 1e-8
+    pub fn epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = Some(epsilon);
+        self
+    }
+
+    /// 既定値は 0
+    pub fn weight_decay(mut self, weight_decay: f32) -> Self {
+        self.weight_decay = Some(weight_decay);
+        self
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = Some(verbose);
+        self
+    }
+}
+
+impl AbstractOptimizerTrait for Adam {
+    type Params = AdamParams;
+
+    fn update(&mut self, model: &mut Model) {
+        let learning_rate = self.base.learning_rate.unwrap();
+        let weight_decay = self.weight_decay;
+        let moments = &mut self.moments;
+        moments.step += 1;
+        apply_updates(model, |key, param, grad| {
+            if weight_decay == 0.0 {
+                moments.delta(key, grad, learning_rate)
+            } else {
+                // g + weight_decay * param
+                let grad = grad.zip_map(param, |g, p| g + weight_decay * p);
+                moments.delta(key, &grad, learning_rate)
+            }
+        });
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn build(&mut self, params: Self::Params) {
+        let defaults = AdamMoments::new();
+        self.base.learning_rate = Some(params.learning_rate.unwrap_or(1e-3));
+        self.base.verbose = params.verbose;
+        self.moments.beta1 = params.beta1.unwrap_or(defaults.beta1);
+        self.moments.beta2 = params.beta2.unwrap_or(defaults.beta2);
+        self.moments.epsilon = params.epsilon.unwrap_or(defaults.epsilon);
+        self.weight_decay = params.weight_decay.unwrap_or(0.0);
+    }
+
+    fn learning_rate(&self) -> Option<f32> {
+        self.base.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.base.learning_rate = Some(learning_rate);
+    }
+
+    fn state(&self) -> OptimizerState {
+        self.moments.state(Self::KIND)
+    }
+
+    fn set_state(&mut self, state: OptimizerState, model: &Model) -> Result<(), CheckpointError> {
+        self.moments.set_state(Self::KIND, state, model)
+    }
+}
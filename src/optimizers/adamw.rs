@@ -0,0 +1,116 @@
+use crate::checkpoint::{CheckpointError, OptimizerState};
+use crate::model::Model;
+use crate::optimizers::adam::AdamMoments;
+use crate::optimizers::base_optimizer::{apply_updates, AbstractOptimizer, AbstractOptimizerTrait};
+
+/// AdamW（weight decay を勾配ではなくパラメータに直接かける）
+pub struct AdamW {
+    base: AbstractOptimizer,
+    moments: AdamMoments,
+    weight_decay: f32,
+}
+
+impl AdamW {
+    pub const KIND: &'static str = "adamw";
+
+    pub fn new(name: String) -> Self {
+        Self { base: AbstractOptimizer::new(name), moments: AdamMoments::new(), weight_decay: 0.01 }
+    }
+}
+
+#[derive(Default)]
+pub struct AdamWParams {
+    pub learning_rate: Option<f32>,
+    pub beta1: Option<f32>,
+    pub beta2: Option<f32>,
+    pub epsilon: Option<f32>,
+    pub weight_decay: Option<f32>,
+    pub verbose: Option<bool>,
+}
+
+impl AdamWParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 既定値は 0.001
+    pub fn learning_rate(mut self, learning_rate: f32) -> Self {
+        self.learning_rate = Some(learning_rate);
+        self
+    }
+
+    /// 既定値は 0.9
+    pub fn beta1(mut self, beta1: f32) -> Self {
+        self.beta1 = Some(beta1);
+        self
+    }
+
+    /// 既定値は 0.999
+    pub fn beta2(mut self, beta2: f32) -> Self {
+        self.beta2 = Some(beta2);
+        self
+    }
+
+    /// 既定値は 1e-8
+    pub fn epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = Some(epsilon);
+        self
+    }
+
+    /// 既定値は 0.01
+    pub fn weight_decay(mut self, weight_decay: f32) -> Self {
+        self.weight_decay = Some(weight_decay);
+        self
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = Some(verbose);
+        self
+    }
+}
+
+impl AbstractOptimizerTrait for AdamW {
+    type Params = AdamWParams;
+
+    fn update(&mut self, model: &mut Model) {
+        let learning_rate = self.base.learning_rate.unwrap();
+        let weight_decay = self.weight_decay;
+        let moments = &mut self.moments;
+        moments.step += 1;
+        apply_updates(model, |key, param, grad| {
+            // -lr * (m_hat / (sqrt(v_hat) + eps) + weight_decay * param)
+            let delta = moments.delta(key, grad, learning_rate);
+            delta.zip_map(param, |d, p| d - learning_rate * weight_decay * p)
+        });
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn build(&mut self, params: Self::Params) {
+        let defaults = AdamMoments::new();
+        self.base.learning_rate = Some(params.learning_rate.unwrap_or(1e-3));
+        self.base.verbose = params.verbose;
+        self.moments.beta1 = params.beta1.unwrap_or(defaults.beta1);
+        self.moments.beta2 = params.beta2.unwrap_or(defaults.beta2);
+        self.moments.epsilon = params.epsilon.unwrap_or(defaults.epsilon);
+        self.weight_decay = params.weight_decay.unwrap_or(0.01);
+    }
+
+    fn learning_rate(&self) -> Option<f32> {
+        self.base.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.base.learning_rate = Some(learning_rate);
+    }
+
+    fn state(&self) -> OptimizerState {
+        self.moments.state(Self::KIND)
+    }
+
+    fn set_state(&mut self, state: OptimizerState, model: &Model) -> Result<(), CheckpointError> {
+        self.moments.set_state(Self::KIND, state, model)
+    }
+}
@@ -1,4 +1,11 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::checkpoint::{read_optimizer_state, write_optimizer_state, CheckpointError, OptimizerState, ParamBufferState, ParamKey};
 use crate::model::Model;
+use crate::tensor::Tensor;
+
+#[derive(Default)]
 pub struct OptimizerParams {
 }
 
@@ -14,7 +21,25 @@ pub trait AbstractOptimizerTrait {
     fn update(&mut self, model: &mut Model);
     fn name(&self) -> &str;
     fn build(&mut self, params: Self::Params);
-    
+
+    /// 現在の学習率（build() 前で未設定なら None）
+    fn learning_rate(&self) -> Option<f32>;
+    /// 学習率を変更する（スケジューラから呼ばれる）
+    fn set_learning_rate(&mut self, learning_rate: f32);
+
+    /// 学習を再開するための状態（ステップ数やパラメータごとのバッファ）
+    fn state(&self) -> OptimizerState;
+    /// state() で取り出した状態を戻す
+    /// 種類の違うオプティマイザの状態や、バッファの shape が model のパラメータと合わない状態はエラー
+    fn set_state(&mut self, state: OptimizerState, model: &Model) -> Result<(), CheckpointError>;
+
+    fn save_state<P: AsRef<Path>>(&self, path: P) -> Result<(), CheckpointError> where Self: Sized {
+        write_optimizer_state(path, &self.state())
+    }
+
+    fn load_state<P: AsRef<Path>>(&mut self, path: P, model: &Model) -> Result<(), CheckpointError> where Self: Sized {
+        self.set_state(read_optimizer_state(path)?, model)
+    }
 }
 
 #[derive(Debug)]
@@ -30,3 +55,277 @@ impl AbstractOptimizer {
     }
 }
 
+/// 各レイヤーの w / b ごとのバッファ（モーメントなど）
+///
+/// キーはレイヤーの番号と w / b の組。最初に使ったときにパラメータと同じ shape の 0 で作る。
+#[derive(Debug, Clone)]
+pub struct ParamBuffers {
+    num_slots: usize,
+    buffers: BTreeMap<ParamKey, Vec<Tensor>>,
+}
+
+impl ParamBuffers {
+    /// num_slots はパラメータ 1 つあたりのバッファ数（Adam なら m と v で 2）
+    pub fn new(num_slots: usize) -> Self {
+        Self { num_slots, buffers: BTreeMap::new() }
+    }
+
+    pub fn contains(&self, key: ParamKey) -> bool {
+        self.buffers.contains_key(&key)
+    }
+
+    pub fn get_mut(&mut self, key: ParamKey, shape: &[usize]) -> &mut [Tensor] {
+        let num_slots = self.num_slots;
+        let slots = self.buffers
+            .entry(key)
+            .or_insert_with(|| (0..num_slots).map(|_| Tensor::zeros(shape)).collect());
+        assert_eq!(slots[0].shape(), shape, "optimizer buffer for {:?} does not match the parameter shape", key);
+        slots
+    }
+
+    pub fn to_state(&self) -> Vec<ParamBufferState> {
+        self.buffers
+            .iter()
+            .map(|(&key, slots)| ParamBufferState {
+                key,
+                shape: slots[0].shape().to_vec(),
+                slots: slots.iter().map(|slot| slot.to_vec()).collect(),
+            })
+            .collect()
+    }
+
+    /// 保存したバッファを戻す（model にないパラメータや shape の違うバッファはエラー）
+    pub fn from_state(num_slots: usize, states: Vec<ParamBufferState>, model: &Model) -> Result<Self, CheckpointError> {
+        let mut buffers = Self::new(num_slots);
+        for state in states {
+            if state.slots.len() != num_slots {
+                return Err(CheckpointError::InvalidOptimizerState(format!(
+                    "{:?} has {} buffers, expected {}", state.key, state.slots.len(), num_slots
+                )));
+            }
+            let param = match state.key {
+                ParamKey::Weight(index) => model.layers.get(index).and_then(|layer| layer.w()),
+                ParamKey::Bias(index) => model.layers.get(index).and_then(|layer| layer.b()),
+            };
+            match param {
+                None => {
+                    return Err(CheckpointError::InvalidOptimizerState(format!(
+                        "{:?} does not exist in the model", state.key
+                    )));
+                }
+                Some(param) if param.shape() != state.shape.as_slice() => {
+                    return Err(CheckpointError::InvalidOptimizerState(format!(
+                        "buffer of {:?} has shape {:?}, but the parameter has shape {:?}", state.key, state.shape, param.shape()
+                    )));
+                }
+                Some(_) => {}
+            }
+            let slots = state.slots
+                .into_iter()
+                .map(|slot| Tensor::new(slot, state.shape.clone()))
+                .collect();
+            buffers.buffers.insert(state.key, slots);
+        }
+        Ok(buffers)
+    }
+}
+
+/// 保存された状態が同じ種類のオプティマイザのものかを確かめる
+pub fn check_state_kind(state: &OptimizerState, kind: &str) -> Result<(), CheckpointError> {
+    if state.kind != kind {
+        return Err(CheckpointError::InvalidOptimizerState(format!(
+            "state was saved by {}, not {}", state.kind, kind
+        )));
+    }
+    state.validate()
+}
+
+/// パラメータを持つ各レイヤーの w / b について f(キー, パラメータ, 勾配) で更新量を求め、足し込む
+///
+/// update_weights / update_biases は更新量を加算するので、f は降下方向（-lr * ...）を返す。
+pub fn apply_updates<F>(model: &mut Model, mut f: F)
+where
+    F: FnMut(ParamKey, &Tensor, &Tensor) -> Tensor,
+{
+    for (index, layer) in model.layers.iter_mut().enumerate() {
+        if let (Some(w), Some(grad_w)) = (layer.w(), layer.grad_w()) {
+            let delta_w = f(ParamKey::Weight(index), w, grad_w);
+            layer.update_weights(&delta_w);
+        }
+        if let (Some(b), Some(grad_b)) = (layer.b(), layer.grad_b()) {
+            let delta_b = f(ParamKey::Bias(index), b, grad_b);
+            layer.update_biases(&delta_b);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::fc_layer::FcLayer;
+    use crate::optimizers::adagrad::{Adagrad, AdagradParams};
+    use crate::optimizers::adam::{Adam, AdamParams};
+    use crate::optimizers::adamw::{AdamW, AdamWParams};
+    use crate::optimizers::rmsprop::{RmsProp, RmsPropParams};
+    use crate::optimizers::sgd::{Sgd, SgdParams};
+
+    /// y = x · [1, -2, 0.5] + 0.3 を学習する線形回帰
+    fn toy_problem() -> (Model, Tensor, Tensor) {
+        let layer = FcLayer::from_parameters("fc".to_string(), 3, 1, "identity".to_string(), vec![0.0; 3], vec![0.0]);
+        let x = Tensor::new(
+            vec![1.0, 0.0, 0.5, -1.0, 1.0, 0.0, 0.5, 0.5, -1.0, 0.0, -1.0, 1.0, 1.0, 1.0, 1.0, -0.5, 0.0, 0.5],
+            vec![6, 3],
+        );
+        let target = x.matmul(&Tensor::new(vec![1.0, -2.0, 0.5], vec![3, 1])).add_scalar(0.3);
+        (Model::new(vec![Box::new(layer)]), x, target)
+    }
+
+    /// 0.5 * mean((y - t)^2) を計算し、勾配をモデルに加算する
+    fn mse_step(model: &mut Model, x: &Tensor, target: &Tensor) -> f32 {
+        model.zero_grad();
+        let diff = &model.forward(x) - target;
+        let n = diff.len() as f32;
+        model.backward(&diff.scale(1.0 / n));
+        0.5 * (&diff * &diff).sum() / n
+    }
+
+    fn train<O: AbstractOptimizerTrait>(optimizer: &mut O, model: &mut Model, x: &Tensor, target: &Tensor, steps: usize) -> f32 {
+        let mut loss = 0.0;
+        for _ in 0..steps {
+            loss = mse_step(model, x, target);
+            optimizer.update(model);
+        }
+        loss
+    }
+
+    fn assert_loss_decreases<O: AbstractOptimizerTrait>(mut optimizer: O) {
+        let (mut model, x, target) = toy_problem();
+        let initial = mse_step(&mut model, &x, &target);
+        let last = train(&mut optimizer, &mut model, &x, &target, 200);
+        assert!(last < initial * 0.1, "{}: loss went from {} to {}", optimizer.name(), initial, last);
+    }
+
+    #[test]
+    fn optimizers_decrease_loss() {
+        let mut sgd = Sgd::new("sgd".to_string());
+        sgd.build(SgdParams::new().learning_rate(0.1));
+        assert_loss_decreases(sgd);
+
+        let mut momentum = Sgd::new("sgd_momentum".to_string());
+        momentum.build(SgdParams::new().learning_rate(0.05).momentum(0.9).dampening(0.1).weight_decay(1e-4));
+        assert_loss_decreases(momentum);
+
+        let mut nesterov = Sgd::new("sgd_nesterov".to_string());
+        nesterov.build(SgdParams::new().learning_rate(0.05).momentum(0.9).nesterov(true));
+        assert_loss_decreases(nesterov);
+
+        let mut adam = Adam::new("adam".to_string());
+        adam.build(AdamParams::new().learning_rate(0.05));
+        assert_loss_decreases(adam);
+
+        let mut adamw = AdamW::new("adamw".to_string());
+        adamw.build(AdamWParams::new().learning_rate(0.05));
+        assert_loss_decreases(adamw);
+
+        let mut rmsprop = RmsProp::new("rmsprop".to_string());
+        rmsprop.build(RmsPropParams::new().learning_rate(0.01));
+        assert_loss_decreases(rmsprop);
+
+        let mut adagrad = Adagrad::new("adagrad".to_string());
+        adagrad.build(AdagradParams::new().learning_rate(0.5));
+        assert_loss_decreases(adagrad);
+    }
+
+    #[test]
+    fn sgd_steps_against_the_gradient() {
+        let (mut model, x, target) = toy_problem();
+        let mut sgd = Sgd::new("sgd".to_string());
+        sgd.build(SgdParams::new().learning_rate(0.1).momentum(0.5));
+
+        // 1 ステップ目: w = 0 - lr * g1
+        mse_step(&mut model, &x, &target);
+        let g1 = model.layers[0].grad_w().unwrap().clone();
+        sgd.update(&mut model);
+        let w1 = model.layers[0].w().unwrap().clone();
+        assert_eq!(w1.to_vec(), g1.scale(-0.1).to_vec());
+
+        // 2 ステップ目: v = 0.5 * g1 + g2, w = w1 - lr * v
+        mse_step(&mut model, &x, &target);
+        let g2 = model.layers[0].grad_w().unwrap().clone();
+        sgd.update(&mut model);
+        let expected = &w1 - &(&g1.scale(0.5) + &g2).scale(0.1);
+        for (w, e) in model.layers[0].w().unwrap().as_slice().iter().zip(expected.as_slice()) {
+            assert!((w - e).abs() < 1e-6, "{} != {}", w, e);
+        }
+    }
+
+    #[test]
+    fn adam_first_step_moves_by_learning_rate() {
+        // バイアス補正後の最初の更新量はちょうど -lr * sign(g)
+        let (mut model, x, target) = toy_problem();
+        let mut adam = Adam::new("adam".to_string());
+        adam.build(AdamParams::new().learning_rate(0.1));
+        mse_step(&mut model, &x, &target);
+        let grad_w = model.layers[0].grad_w().unwrap().to_vec();
+        adam.update(&mut model);
+        for (w, g) in model.layers[0].w().unwrap().as_slice().iter().zip(grad_w) {
+            assert!((w + 0.1 * g.signum()).abs() < 1e-5, "w = {}, g = {}", w, g);
+        }
+    }
+
+    #[test]
+    fn resumed_optimizer_continues_exactly() {
+        let path = std::env::temp_dir().join(format!("nn_rust_optimizer_state_{}.bin", std::process::id()));
+        let (mut model, x, target) = toy_problem();
+        let mut adam = Adam::new("adam".to_string());
+        adam.build(AdamParams::new().learning_rate(0.05).weight_decay(0.01));
+        train(&mut adam, &mut model, &x, &target, 5);
+        adam.save_state(&path).unwrap();
+        let mut resumed_model = Model::new(model.layers.iter().map(|layer| layer.state().into_layer()).collect());
+
+        // 途中から再開したものと、そのまま続けたものが一致する
+        let mut resumed = Adam::new("adam".to_string());
+        resumed.build(AdamParams::new().learning_rate(0.05).weight_decay(0.01));
+        resumed.load_state(&path, &resumed_model).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        train(&mut adam, &mut model, &x, &target, 5);
+        train(&mut resumed, &mut resumed_model, &x, &target, 5);
+        assert_eq!(model.layers[0].state(), resumed_model.layers[0].state());
+        assert_eq!(adam.state(), resumed.state());
+
+        // 種類の違うオプティマイザの状態は読み込めない
+        let mut rmsprop = RmsProp::new("rmsprop".to_string());
+        assert!(matches!(rmsprop.set_state(adam.state(), &model), Err(CheckpointError::InvalidOptimizerState(_))));
+    }
+
+    #[test]
+    fn set_state_rejects_state_of_another_model() {
+        let (mut model, x, target) = toy_problem();
+        let mut sgd = Sgd::new("sgd".to_string());
+        sgd.build(SgdParams::new().learning_rate(0.1).momentum(0.9));
+        train(&mut sgd, &mut model, &x, &target, 2);
+        let state = sgd.state();
+
+        // 入力サイズが違うモデル（w の shape が [1, 4]）と、レイヤーの少ないモデル
+        let wider = Model::new(vec![Box::new(FcLayer::from_parameters(
+            "fc".to_string(), 4, 1, "identity".to_string(), vec![0.0; 4], vec![0.0],
+        ))]);
+        let empty = Model::new(Vec::new());
+        for (name, other) in [("wider", &wider), ("empty", &empty)] {
+            let mut resumed = Sgd::new("sgd".to_string());
+            resumed.build(SgdParams::new().learning_rate(0.1).momentum(0.9));
+            let result = resumed.set_state(state.clone(), other);
+            assert!(matches!(result, Err(CheckpointError::InvalidOptimizerState(_))), "{}", name);
+        }
+
+        // ファイルから読み込むときも同じ
+        let path = std::env::temp_dir().join(format!("nn_rust_optimizer_state_mismatch_{}.bin", std::process::id()));
+        sgd.save_state(&path).unwrap();
+        let mut resumed = Sgd::new("sgd".to_string());
+        let result = resumed.load_state(&path, &wider);
+        let matching = resumed.load_state(&path, &model);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(CheckpointError::InvalidOptimizerState(_))));
+        assert!(matching.is_ok());
+    }
+}
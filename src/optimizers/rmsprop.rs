@@ -0,0 +1,110 @@
+use crate::checkpoint::{CheckpointError, OptimizerState};
+use crate::model::Model;
+use crate::optimizers::base_optimizer::{apply_updates, check_state_kind, AbstractOptimizer, AbstractOptimizerTrait, ParamBuffers};
+use crate::tensor::Tensor;
+
+/// RMSprop: v = alpha * v + (1 - alpha) * g^2, delta = -lr * g / (sqrt(v) + eps)
+pub struct RmsProp {
+    base: AbstractOptimizer,
+    alpha: f32,
+    epsilon: f32,
+    step: u64,
+    // パラメータごとに [v]
+    buffers: ParamBuffers,
+}
+
+impl RmsProp {
+    pub const KIND: &'static str = "rmsprop";
+
+    pub fn new(name: String) -> Self {
+        Self { base: AbstractOptimizer::new(name), alpha: 0.99, epsilon: 1e-8, step: 0, buffers: ParamBuffers::new(1) }
+    }
+}
+
+#[derive(Default)]
+pub struct RmsPropParams {
+    pub learning_rate: Option<f32>,
+    pub alpha: Option<f32>,
+    pub epsilon: Option<f32>,
+    pub verbose: Option<bool>,
+}
+
+impl RmsPropParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 既定値は 0.01
+    pub fn learning_rate(mut self, learning_rate: f32) -> Self {
+        self.learning_rate = Some(learning_rate);
+        self
+    }
+
+    /// 2 乗平均の減衰率（既定値は 0.99）
+    pub fn alpha(mut self, alpha: f32) -> Self {
+        self.alpha = Some(alpha);
+        self
+    }
+
+    /// 既定値は 1e-8
+    pub fn epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = Some(epsilon);
+        self
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = Some(verbose);
+        self
+    }
+}
+
+impl AbstractOptimizerTrait for RmsProp {
+    type Params = RmsPropParams;
+
+    fn update(&mut self, model: &mut Model) {
+        let learning_rate = self.base.learning_rate.unwrap();
+        let (alpha, epsilon) = (self.alpha, self.epsilon);
+        let buffers = &mut self.buffers;
+        self.step += 1;
+        apply_updates(model, |key, _param, grad| {
+            let grad = grad.contiguous();
+            let [v] = buffers.get_mut(key, grad.shape()) else { unreachable!() };
+            let mut delta = Vec::with_capacity(grad.len());
+            for (v, &g) in v.as_mut_slice().iter_mut().zip(grad.as_slice()) {
+                *v = alpha * *v + (1.0 - alpha) * g * g;
+                delta.push(-learning_rate * g / (v.sqrt() + epsilon));
+            }
+            Tensor::new(delta, grad.shape().to_vec())
+        });
+    }
+
+    fn name(&self) -> &str {
+        &self.base.name
+    }
+
+    fn build(&mut self, params: Self::Params) {
+        self.base.learning_rate = Some(params.learning_rate.unwrap_or(1e-2));
+        self.base.verbose = params.verbose;
+        self.alpha = params.alpha.unwrap_or(0.99);
+        self.epsilon = params.epsilon.unwrap_or(1e-8);
+    }
+
+    fn learning_rate(&self) -> Option<f32> {
+        self.base.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.base.learning_rate = Some(learning_rate);
+    }
+
+    fn state(&self) -> OptimizerState {
+        OptimizerState { kind: Self::KIND.to_string(), step: self.step, buffers: self.buffers.to_state() }
+    }
+
+    fn set_state(&mut self, state: OptimizerState, model: &Model) -> Result<(), CheckpointError> {
+        check_state_kind(&state, Self::KIND)?;
+        self.buffers = ParamBuffers::from_state(1, state.buffers, model)?;
+        self.step = state.step;
+        Ok(())
+    }
+}
@@ -1,23 +1,53 @@
-use crate::optimizers::base_optimizer::{AbstractOptimizer, AbstractOptimizerTrait};
+use crate::checkpoint::{CheckpointError, OptimizerState};
+use crate::optimizers::base_optimizer::{apply_updates, check_state_kind, AbstractOptimizer, AbstractOptimizerTrait, ParamBuffers};
 use crate::model::Model;
+use crate::tensor::Tensor;
+
+/// 確率的勾配降下法（momentum / Nesterov / weight decay 付き）
+///
+/// g = grad + weight_decay * w
+/// v = momentum * v + (1 - dampening) * g（最初のステップは v = g）
+/// w -= lr * (nesterov なら g + momentum * v、そうでなければ v)
 pub struct Sgd {
     base: AbstractOptimizer,
+    momentum: f32,
+    dampening: f32,
+    nesterov: bool,
+    weight_decay: f32,
+    step: u64,
+    // パラメータごとに [velocity]（momentum が 0 のときは使わない）
+    velocities: ParamBuffers,
 }
 
 impl Sgd {
+    pub const KIND: &'static str = "sgd";
+
     pub fn new(name: String) -> Self {
-        Self { base: AbstractOptimizer::new(name) }
+        Self {
+            base: AbstractOptimizer::new(name),
+            momentum: 0.0,
+            dampening: 0.0,
+            nesterov: false,
+            weight_decay: 0.0,
+            step: 0,
+            velocities: ParamBuffers::new(1),
+        }
     }
 }
 
+#[derive(Default)]
 pub struct SgdParams {
     pub learning_rate: Option<f32>,
+    pub momentum: Option<f32>,
+    pub dampening: Option<f32>,
+    pub nesterov: Option<bool>,
+    pub weight_decay: Option<f32>,
     pub verbose: Option<bool>,
 }
 
 impl SgdParams {
     pub fn new() -> Self {
-        Self { learning_rate: None, verbose: None }
+        Self::default()
     }
 
     pub fn learning_rate(mut self, learning_rate: f32) -> Self {
@@ -25,6 +55,30 @@ impl SgdParams {
         self
     }
 
+    /// 既定値は 0（momentum なし）
+    pub fn momentum(mut self, momentum: f32) -> Self {
+        self.momentum = Some(momentum);
+        self
+    }
+
+    /// velocity に足す勾配を (1 - dampening) 倍する（既定値は 0）
+    pub fn dampening(mut self, dampening: f32) -> Self {
+        self.dampening = Some(dampening);
+        self
+    }
+
+    /// Nesterov の加速勾配（momentum > 0 かつ dampening = 0 が必要）
+    pub fn nesterov(mut self, nesterov: bool) -> Self {
+        self.nesterov = Some(nesterov);
+        self
+    }
+
+    /// 勾配に足す L2 正則化の係数（既定値は 0）
+    pub fn weight_decay(mut self, weight_decay: f32) -> Self {
+        self.weight_decay = Some(weight_decay);
+        self
+    }
+
     pub fn verbose(mut self, verbose: bool) -> Self {
         self.verbose = Some(verbose);
         self
@@ -35,18 +89,34 @@ impl AbstractOptimizerTrait for Sgd {
     type Params = SgdParams;
 
     fn update(&mut self, model: &mut Model) {
-        for layer in &mut model.layers {
-            let mut delta_w = vec![0.0; layer.w().len()];
-            let mut delta_b = vec![0.0; layer.b().len()];
-            for i in 0..layer.w().len() {
-                delta_w[i] = layer.grad_w()[i] * self.base.learning_rate.unwrap();
-            }
-            for i in 0..layer.b().len() {
-                delta_b[i] = layer.grad_b()[i] * self.base.learning_rate.unwrap();
+        let learning_rate = self.base.learning_rate.unwrap();
+        let (momentum, dampening, nesterov, weight_decay) = (self.momentum, self.dampening, self.nesterov, self.weight_decay);
+        let velocities = &mut self.velocities;
+        self.step += 1;
+        apply_updates(model, |key, param, grad| {
+            let grad = if weight_decay == 0.0 {
+                grad.clone()
+            } else {
+                grad.zip_map(param, |g, p| g + weight_decay * p)
+            };
+            if momentum == 0.0 {
+                return grad.scale(-learning_rate);
             }
-            layer.update_weights(&delta_w);
-            layer.update_biases(&delta_b);
-        }
+
+            let is_first_step = !velocities.contains(key);
+            let [velocity] = velocities.get_mut(key, grad.shape()) else { unreachable!() };
+            *velocity = if is_first_step {
+                grad.clone()
+            } else {
+                &velocity.scale(momentum) + &grad.scale(1.0 - dampening)
+            };
+            let direction: Tensor = if nesterov {
+                &grad + &velocity.scale(momentum)
+            } else {
+                velocity.clone()
+            };
+            direction.scale(-learning_rate)
+        });
     }
 
     fn name(&self) -> &str {
@@ -56,5 +126,32 @@ impl AbstractOptimizerTrait for Sgd {
     fn build(&mut self, params: Self::Params) {
         self.base.learning_rate = params.learning_rate;
         self.base.verbose = params.verbose;
+        self.momentum = params.momentum.unwrap_or(0.0);
+        self.dampening = params.dampening.unwrap_or(0.0);
+        self.nesterov = params.nesterov.unwrap_or(false);
+        self.weight_decay = params.weight_decay.unwrap_or(0.0);
+        assert!(
+            !self.nesterov || (self.momentum > 0.0 && self.dampening == 0.0),
+            "Nesterov momentum requires momentum > 0 and dampening = 0"
+        );
     }
-}
\ No newline at end of file
+
+    fn learning_rate(&self) -> Option<f32> {
+        self.base.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.base.learning_rate = Some(learning_rate);
+    }
+
+    fn state(&self) -> OptimizerState {
+        OptimizerState { kind: Self::KIND.to_string(), step: self.step, buffers: self.velocities.to_state() }
+    }
+
+    fn set_state(&mut self, state: OptimizerState, model: &Model) -> Result<(), CheckpointError> {
+        check_state_kind(&state, Self::KIND)?;
+        self.velocities = ParamBuffers::from_state(1, state.buffers, model)?;
+        self.step = state.step;
+        Ok(())
+    }
+}
@@ -0,0 +1,7 @@
+pub mod base_scheduler;
+pub mod cosine_annealing_warm_restarts;
+pub mod exponential_decay;
+pub mod linear_warmup;
+pub mod one_cycle;
+pub mod reduce_on_plateau;
+pub mod step_decay;
@@ -0,0 +1,87 @@
+/// スケジューラを進めるタイミング
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchedulerInterval {
+    /// オプティマイザの更新（バッチ）ごと
+    Step,
+    /// エポックの終わり（検証のあと）ごと
+    Epoch,
+}
+
+/// 学習率スケジューラの共通インターフェース
+///
+/// `learning_rate()` は現在の学習率、`step()` はスケジュールを 1 つ進めて新しい学習率を返す。
+/// Trainer は学習の最初に `learning_rate()` をオプティマイザに設定し、
+/// `interval()` のタイミングで `step()` を呼んで学習率を更新する。
+pub trait LrScheduler {
+    /// metric は検証データの損失（ReduceOnPlateau のように指標を見るスケジューラだけが使う）
+    fn step(&mut self, metric: Option<f32>) -> f32;
+    fn learning_rate(&self) -> f32;
+    fn interval(&self) -> SchedulerInterval;
+    fn name(&self) -> &str;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedulers::cosine_annealing_warm_restarts::CosineAnnealingWarmRestarts;
+    use crate::schedulers::exponential_decay::ExponentialDecay;
+    use crate::schedulers::linear_warmup::LinearWarmup;
+    use crate::schedulers::one_cycle::OneCycle;
+    use crate::schedulers::reduce_on_plateau::ReduceOnPlateau;
+    use crate::schedulers::step_decay::StepDecay;
+
+    /// 最初の学習率と、そのあと steps 回進めたときの学習率
+    fn schedule(scheduler: &mut dyn LrScheduler, steps: usize) -> Vec<f32> {
+        let mut lrs = vec![scheduler.learning_rate()];
+        lrs.extend((0..steps).map(|_| scheduler.step(None)));
+        lrs
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-5, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn step_and_exponential_decay() {
+        assert_close(&schedule(&mut StepDecay::new(1.0, 2, 0.5), 4), &[1.0, 1.0, 0.5, 0.5, 0.25]);
+        assert_close(&schedule(&mut ExponentialDecay::new(1.0, 0.5), 3), &[1.0, 0.5, 0.25, 0.125]);
+    }
+
+    #[test]
+    fn cosine_annealing_restarts() {
+        let mut scheduler = CosineAnnealingWarmRestarts::new(1.0, 4).min_lr(0.0).t_mult(2);
+        let lrs = schedule(&mut scheduler, 6);
+        assert_close(&lrs[..5], &[1.0, 0.853_553_4, 0.5, 0.146_446_6, 1.0]);
+        // 2 周期目は長さ 8
+        assert_close(&lrs[5..], &[0.961_939_8, 0.853_553_4]);
+    }
+
+    #[test]
+    fn linear_warmup_then_decay() {
+        assert_close(&schedule(&mut LinearWarmup::new(1.0, 4), 5), &[0.0, 0.25, 0.5, 0.75, 1.0, 1.0]);
+        let mut scheduler = LinearWarmup::new(1.0, 2).start_factor(0.5).then(Box::new(ExponentialDecay::new(1.0, 0.5)));
+        assert_close(&schedule(&mut scheduler, 4), &[0.5, 0.75, 1.0, 0.5, 0.25]);
+    }
+
+    #[test]
+    fn one_cycle() {
+        let lrs = schedule(&mut OneCycle::new(1.0, 10), 12);
+        assert_close(&[lrs[0], lrs[3], lrs[10], lrs[12]], &[0.04, 1.0, 4e-6, 4e-6]);
+        assert!(lrs[..4].windows(2).all(|w| w[0] < w[1]));
+        assert!(lrs[3..11].windows(2).all(|w| w[0] > w[1]));
+    }
+
+    #[test]
+    fn reduce_on_plateau() {
+        let mut scheduler = ReduceOnPlateau::new(1.0).patience(1).factor(0.5).cooldown(1);
+        let lrs: Vec<f32> = [1.0, 0.9, 0.95, 0.95, 0.95, 0.95, 0.95]
+            .iter()
+            .map(|&loss| scheduler.step(Some(loss)))
+            .collect();
+        // 2 回続けて改善しなければ半分にし、その次の 1 回は様子を見る
+        assert_close(&lrs, &[1.0, 1.0, 1.0, 0.5, 0.5, 0.5, 0.25]);
+    }
+}
@@ -0,0 +1,67 @@
+use std::f32::consts::PI;
+
+use crate::schedulers::base_scheduler::{LrScheduler, SchedulerInterval};
+
+/// コサインアニーリング（SGDR）
+///
+/// 周期の中で lr = min_lr + (base_lr - min_lr) * (1 + cos(pi * t_cur / t_i)) / 2 と下げ、
+/// 周期が終わると base_lr に戻す。周期の長さは t_0 から始めて毎回 t_mult 倍になる。
+#[derive(Debug, Clone)]
+pub struct CosineAnnealingWarmRestarts {
+    base_lr: f32,
+    min_lr: f32,
+    t_mult: usize,
+    interval: SchedulerInterval,
+    // 現在の周期の長さと、その中での位置
+    t_i: usize,
+    t_cur: usize,
+}
+
+impl CosineAnnealingWarmRestarts {
+    /// 既定ではバッチごとに進め、min_lr = 0、t_mult = 1
+    pub fn new(base_lr: f32, t_0: usize) -> Self {
+        assert!(t_0 > 0, "t_0 must be positive");
+        Self { base_lr, min_lr: 0.0, t_mult: 1, interval: SchedulerInterval::Step, t_i: t_0, t_cur: 0 }
+    }
+
+    pub fn min_lr(mut self, min_lr: f32) -> Self {
+        self.min_lr = min_lr;
+        self
+    }
+
+    pub fn t_mult(mut self, t_mult: usize) -> Self {
+        assert!(t_mult > 0, "t_mult must be positive");
+        self.t_mult = t_mult;
+        self
+    }
+
+    pub fn interval(mut self, interval: SchedulerInterval) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+impl LrScheduler for CosineAnnealingWarmRestarts {
+    fn step(&mut self, _metric: Option<f32>) -> f32 {
+        self.t_cur += 1;
+        if self.t_cur >= self.t_i {
+            // リスタート
+            self.t_cur = 0;
+            self.t_i *= self.t_mult;
+        }
+        self.learning_rate()
+    }
+
+    fn learning_rate(&self) -> f32 {
+        let progress = self.t_cur as f32 / self.t_i as f32;
+        self.min_lr + (self.base_lr - self.min_lr) * (1.0 + (PI * progress).cos()) / 2.0
+    }
+
+    fn interval(&self) -> SchedulerInterval {
+        self.interval
+    }
+
+    fn name(&self) -> &str {
+        "cosine_annealing_warm_restarts"
+    }
+}
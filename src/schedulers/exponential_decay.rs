@@ -0,0 +1,41 @@
+use crate::schedulers::base_scheduler::{LrScheduler, SchedulerInterval};
+
+/// 毎回学習率を gamma 倍する: lr = base_lr * gamma^t
+#[derive(Debug, Clone)]
+pub struct ExponentialDecay {
+    base_lr: f32,
+    gamma: f32,
+    interval: SchedulerInterval,
+    t: usize,
+}
+
+impl ExponentialDecay {
+    /// 既定ではエポックごとに進める
+    pub fn new(base_lr: f32, gamma: f32) -> Self {
+        Self { base_lr, gamma, interval: SchedulerInterval::Epoch, t: 0 }
+    }
+
+    pub fn interval(mut self, interval: SchedulerInterval) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+impl LrScheduler for ExponentialDecay {
+    fn step(&mut self, _metric: Option<f32>) -> f32 {
+        self.t += 1;
+        self.learning_rate()
+    }
+
+    fn learning_rate(&self) -> f32 {
+        self.base_lr * self.gamma.powi(self.t as i32)
+    }
+
+    fn interval(&self) -> SchedulerInterval {
+        self.interval
+    }
+
+    fn name(&self) -> &str {
+        "exponential_decay"
+    }
+}
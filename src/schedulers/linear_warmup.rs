@@ -0,0 +1,67 @@
+use crate::schedulers::base_scheduler::{LrScheduler, SchedulerInterval};
+
+/// 最初の warmup_steps 回で学習率を base_lr * start_factor から base_lr まで直線的に上げる
+///
+/// `then` で後続のスケジューラを指定すると、ウォームアップ後はそちらに任せる
+/// （指定しなければ base_lr のまま）。後続のスケジューラもこのスケジューラと同じタイミングで進む。
+pub struct LinearWarmup {
+    base_lr: f32,
+    warmup_steps: usize,
+    start_factor: f32,
+    after: Option<Box<dyn LrScheduler>>,
+    interval: SchedulerInterval,
+    t: usize,
+}
+
+impl LinearWarmup {
+    /// 既定ではバッチごとに進め、start_factor = 0
+    pub fn new(base_lr: f32, warmup_steps: usize) -> Self {
+        Self { base_lr, warmup_steps, start_factor: 0.0, after: None, interval: SchedulerInterval::Step, t: 0 }
+    }
+
+    pub fn start_factor(mut self, start_factor: f32) -> Self {
+        self.start_factor = start_factor;
+        self
+    }
+
+    pub fn then(mut self, after: Box<dyn LrScheduler>) -> Self {
+        self.after = Some(after);
+        self
+    }
+
+    pub fn interval(mut self, interval: SchedulerInterval) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+impl LrScheduler for LinearWarmup {
+    fn step(&mut self, metric: Option<f32>) -> f32 {
+        if self.t >= self.warmup_steps {
+            if let Some(after) = &mut self.after {
+                return after.step(metric);
+            }
+        }
+        self.t += 1;
+        self.learning_rate()
+    }
+
+    fn learning_rate(&self) -> f32 {
+        if self.t < self.warmup_steps {
+            let progress = self.t as f32 / self.warmup_steps as f32;
+            return self.base_lr * (self.start_factor + (1.0 - self.start_factor) * progress);
+        }
+        match &self.after {
+            Some(after) => after.learning_rate(),
+            None => self.base_lr,
+        }
+    }
+
+    fn interval(&self) -> SchedulerInterval {
+        self.interval
+    }
+
+    fn name(&self) -> &str {
+        "linear_warmup"
+    }
+}
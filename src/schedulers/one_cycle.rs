@@ -0,0 +1,74 @@
+use std::f32::consts::PI;
+
+use crate::schedulers::base_scheduler::{LrScheduler, SchedulerInterval};
+
+/// 1cycle 方策
+///
+/// 最初の pct_start * total_steps 回で max_lr / div_factor から max_lr までコサインで上げ、
+/// 残りで max_lr / (div_factor * final_div_factor) までコサインで下げる。バッチごとに進める。
+#[derive(Debug, Clone)]
+pub struct OneCycle {
+    max_lr: f32,
+    total_steps: usize,
+    pct_start: f32,
+    div_factor: f32,
+    final_div_factor: f32,
+    t: usize,
+}
+
+/// start から end へコサインで補間する（progress は 0..=1）
+fn cosine_interpolate(start: f32, end: f32, progress: f32) -> f32 {
+    end + (start - end) * (1.0 + (PI * progress).cos()) / 2.0
+}
+
+impl OneCycle {
+    /// 既定値は pct_start = 0.3、div_factor = 25、final_div_factor = 1e4
+    pub fn new(max_lr: f32, total_steps: usize) -> Self {
+        assert!(total_steps > 0, "total_steps must be positive");
+        Self { max_lr, total_steps, pct_start: 0.3, div_factor: 25.0, final_div_factor: 1e4, t: 0 }
+    }
+
+    pub fn pct_start(mut self, pct_start: f32) -> Self {
+        assert!((0.0..=1.0).contains(&pct_start), "pct_start must be in [0, 1]");
+        self.pct_start = pct_start;
+        self
+    }
+
+    pub fn div_factor(mut self, div_factor: f32) -> Self {
+        self.div_factor = div_factor;
+        self
+    }
+
+    pub fn final_div_factor(mut self, final_div_factor: f32) -> Self {
+        self.final_div_factor = final_div_factor;
+        self
+    }
+}
+
+impl LrScheduler for OneCycle {
+    fn step(&mut self, _metric: Option<f32>) -> f32 {
+        // total_steps を過ぎたら最小値のまま
+        self.t = (self.t + 1).min(self.total_steps);
+        self.learning_rate()
+    }
+
+    fn learning_rate(&self) -> f32 {
+        let initial_lr = self.max_lr / self.div_factor;
+        let min_lr = initial_lr / self.final_div_factor;
+        let warmup_steps = (self.pct_start * self.total_steps as f32) as usize;
+        if self.t < warmup_steps {
+            cosine_interpolate(initial_lr, self.max_lr, self.t as f32 / warmup_steps as f32)
+        } else {
+            let anneal_steps = (self.total_steps - warmup_steps).max(1);
+            cosine_interpolate(self.max_lr, min_lr, (self.t - warmup_steps) as f32 / anneal_steps as f32)
+        }
+    }
+
+    fn interval(&self) -> SchedulerInterval {
+        SchedulerInterval::Step
+    }
+
+    fn name(&self) -> &str {
+        "one_cycle"
+    }
+}
@@ -0,0 +1,114 @@
+use crate::schedulers::base_scheduler::{LrScheduler, SchedulerInterval};
+
+/// 指標が改善しないときに学習率を下げる
+///
+/// 指標（既定では検証データの損失、小さいほど良い）が patience 回続けて
+/// threshold（相対値）以上改善しなければ学習率を factor 倍する。下げたあと cooldown 回は様子を見る。
+/// エポックごとに進める。
+#[derive(Debug, Clone)]
+pub struct ReduceOnPlateau {
+    lr: f32,
+    factor: f32,
+    patience: usize,
+    threshold: f32,
+    cooldown: usize,
+    min_lr: f32,
+    maximize: bool,
+    best: Option<f32>,
+    num_bad_epochs: usize,
+    cooldown_counter: usize,
+}
+
+impl ReduceOnPlateau {
+    /// 既定値は factor = 0.1、patience = 10、threshold = 1e-4、cooldown = 0、min_lr = 0
+    pub fn new(lr: f32) -> Self {
+        Self {
+            lr,
+            factor: 0.1,
+            patience: 10,
+            threshold: 1e-4,
+            cooldown: 0,
+            min_lr: 0.0,
+            maximize: false,
+            best: None,
+            num_bad_epochs: 0,
+            cooldown_counter: 0,
+        }
+    }
+
+    pub fn factor(mut self, factor: f32) -> Self {
+        assert!(factor > 0.0 && factor < 1.0, "factor must be in (0, 1)");
+        self.factor = factor;
+        self
+    }
+
+    pub fn patience(mut self, patience: usize) -> Self {
+        self.patience = patience;
+        self
+    }
+
+    pub fn threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn cooldown(mut self, cooldown: usize) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    pub fn min_lr(mut self, min_lr: f32) -> Self {
+        self.min_lr = min_lr;
+        self
+    }
+
+    /// 大きいほど良い指標（正解率など）を見るとき
+    pub fn maximize(mut self, maximize: bool) -> Self {
+        self.maximize = maximize;
+        self
+    }
+
+    fn is_improvement(&self, metric: f32) -> bool {
+        match self.best {
+            None => true,
+            Some(best) if self.maximize => metric > best * (1.0 + self.threshold),
+            Some(best) => metric < best * (1.0 - self.threshold),
+        }
+    }
+}
+
+impl LrScheduler for ReduceOnPlateau {
+    fn step(&mut self, metric: Option<f32>) -> f32 {
+        let metric = metric.expect("ReduceOnPlateau needs a metric");
+        if self.is_improvement(metric) {
+            self.best = Some(metric);
+            self.num_bad_epochs = 0;
+        } else {
+            self.num_bad_epochs += 1;
+        }
+
+        if self.cooldown_counter > 0 {
+            self.cooldown_counter -= 1;
+            self.num_bad_epochs = 0;
+        }
+
+        if self.num_bad_epochs > self.patience {
+            self.lr = (self.lr * self.factor).max(self.min_lr);
+            self.cooldown_counter = self.cooldown;
+            self.num_bad_epochs = 0;
+        }
+        self.lr
+    }
+
+    fn learning_rate(&self) -> f32 {
+        self.lr
+    }
+
+    fn interval(&self) -> SchedulerInterval {
+        SchedulerInterval::Epoch
+    }
+
+    fn name(&self) -> &str {
+        "reduce_on_plateau"
+    }
+}
@@ -0,0 +1,43 @@
+use crate::schedulers::base_scheduler::{LrScheduler, SchedulerInterval};
+
+/// step_size 回ごとに学習率を gamma 倍する: lr = base_lr * gamma^(t / step_size)
+#[derive(Debug, Clone)]
+pub struct StepDecay {
+    base_lr: f32,
+    step_size: usize,
+    gamma: f32,
+    interval: SchedulerInterval,
+    t: usize,
+}
+
+impl StepDecay {
+    /// 既定ではエポックごとに進める
+    pub fn new(base_lr: f32, step_size: usize, gamma: f32) -> Self {
+        assert!(step_size > 0, "step_size must be positive");
+        Self { base_lr, step_size, gamma, interval: SchedulerInterval::Epoch, t: 0 }
+    }
+
+    pub fn interval(mut self, interval: SchedulerInterval) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+impl LrScheduler for StepDecay {
+    fn step(&mut self, _metric: Option<f32>) -> f32 {
+        self.t += 1;
+        self.learning_rate()
+    }
+
+    fn learning_rate(&self) -> f32 {
+        self.base_lr * self.gamma.powi((self.t / self.step_size) as i32)
+    }
+
+    fn interval(&self) -> SchedulerInterval {
+        self.interval
+    }
+
+    fn name(&self) -> &str {
+        "step_decay"
+    }
+}
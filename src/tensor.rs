@@ -0,0 +1,607 @@
+use std::borrow::Cow;
+use std::ops::{Add, AddAssign, Div, Mul, Sub};
+use std::sync::Arc;
+
+/// 行優先の多次元配列
+///
+/// データ（Arc で共有する）と、shape と strides（要素単位）を持つ。
+/// clone / reshape / transpose / permute はデータを共有し、書き込むときに初めてコピーする。
+/// transpose / permute は strides を入れ替えるだけなので、結果は非連続になることがある。
+/// 非連続なテンソルに対する演算は内部で連続化してから行う。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tensor {
+    data: Arc<Vec<f32>>,
+    shape: Vec<usize>,
+    strides: Vec<usize>,
+}
+
+fn contiguous_strides(shape: &[usize]) -> Vec<usize> {
+    let mut strides = vec![1; shape.len()];
+    for axis in (0..shape.len().saturating_sub(1)).rev() {
+        strides[axis] = strides[axis + 1] * shape[axis + 1];
+    }
+    strides
+}
+
+/// numpy と同じ規則で 2 つの shape をブロードキャストした結果を返す
+fn broadcast_shape(a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    let ndim = a.len().max(b.len());
+    let mut shape = vec![0; ndim];
+    for i in 0..ndim {
+        let da = if i < ndim - a.len() { 1 } else { a[i - (ndim - a.len())] };
+        let db = if i < ndim - b.len() { 1 } else { b[i - (ndim - b.len())] };
+        shape[i] = match (da, db) {
+            (x, y) if x == y => x,
+            (1, y) => y,
+            (x, 1) => x,
+            _ => return None,
+        };
+    }
+    Some(shape)
+}
+
+/// shape / strides のテンソルの要素オフセットを論理的な（行優先の）順序で列挙する
+fn offsets(shape: &[usize], strides: &[usize]) -> Vec<usize> {
+    let numel: usize = shape.iter().product();
+    let mut offsets = Vec::with_capacity(numel);
+    let mut index = vec![0usize; shape.len()];
+    for _ in 0..numel {
+        offsets.push(index.iter().zip(strides.iter()).map(|(i, s)| i * s).sum());
+        for axis in (0..shape.len()).rev() {
+            index[axis] += 1;
+            if index[axis] < shape[axis] {
+                break;
+            }
+            index[axis] = 0;
+        }
+    }
+    offsets
+}
+
+impl Tensor {
+    pub fn new(data: Vec<f32>, shape: Vec<usize>) -> Self {
+        let numel: usize = shape.iter().product();
+        assert_eq!(data.len(), numel, "data length {} does not match shape {:?}", data.len(), shape);
+        let strides = contiguous_strides(&shape);
+        Self { data: Arc::new(data), shape, strides }
+    }
+
+    pub fn zeros(shape: &[usize]) -> Self {
+        Self::full(shape, 0.0)
+    }
+
+    pub fn ones(shape: &[usize]) -> Self {
+        Self::full(shape, 1.0)
+    }
+
+    pub fn full(shape: &[usize], value: f32) -> Self {
+        let numel: usize = shape.iter().product();
+        Self::new(vec![value; numel], shape.to_vec())
+    }
+
+    pub fn scalar(value: f32) -> Self {
+        Self::new(vec![value], Vec::new())
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn strides(&self) -> &[usize] {
+        &self.strides
+    }
+
+    pub fn ndim(&self) -> usize {
+        self.shape.len()
+    }
+
+    pub fn dim(&self, axis: usize) -> usize {
+        self.shape[axis]
+    }
+
+    /// 要素数
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn is_contiguous(&self) -> bool {
+        self.strides == contiguous_strides(&self.shape)
+    }
+
+    /// 連続なテンソルのデータを行優先のスライスとして返す
+    pub fn as_slice(&self) -> &[f32] {
+        assert!(self.is_contiguous(), "as_slice() on a non-contiguous tensor; call contiguous() first");
+        &self.data
+    }
+
+    /// 書き込み用のスライス（データを他のテンソルと共有していればコピーしてから返す）
+    pub fn as_mut_slice(&mut self) -> &mut [f32] {
+        self.make_contiguous();
+        Arc::make_mut(&mut self.data).as_mut_slice()
+    }
+
+    pub fn to_vec(&self) -> Vec<f32> {
+        self.contiguous().into_vec()
+    }
+
+    pub fn into_vec(mut self) -> Vec<f32> {
+        self.make_contiguous();
+        Arc::try_unwrap(self.data).unwrap_or_else(|shared| shared.as_ref().clone())
+    }
+
+    /// 多次元インデックスで要素を取り出す
+    pub fn get(&self, index: &[usize]) -> f32 {
+        assert_eq!(index.len(), self.ndim(), "index rank does not match tensor rank");
+        let offset: usize = index.iter()
+            .zip(self.shape.iter().zip(self.strides.iter()))
+            .map(|(&i, (&dim, &stride))| {
+                assert!(i < dim, "index {:?} out of bounds for shape {:?}", index, self.shape);
+                i * stride
+            })
+            .sum();
+        self.data[offset]
+    }
+
+    /// 要素が 1 つだけのテンソル（スカラーの損失など）の値
+    pub fn item(&self) -> f32 {
+        assert_eq!(self.len(), 1, "item() on a tensor of shape {:?}", self.shape);
+        self.data[0]
+    }
+
+    pub fn contiguous(&self) -> Tensor {
+        if self.is_contiguous() {
+            return self.clone();
+        }
+        let data = offsets(&self.shape, &self.strides).into_iter().map(|o| self.data[o]).collect();
+        Tensor::new(data, self.shape.clone())
+    }
+
+    /// 連続ならそのまま借用し、非連続ならコピーして連続化する
+    fn contiguous_cow(&self) -> Cow<'_, Tensor> {
+        if self.is_contiguous() {
+            Cow::Borrowed(self)
+        } else {
+            Cow::Owned(self.contiguous())
+        }
+    }
+
+    fn make_contiguous(&mut self) {
+        if !self.is_contiguous() {
+            *self = self.contiguous();
+        }
+    }
+
+    pub fn reshape(&self, shape: &[usize]) -> Tensor {
+        self.clone().into_reshape(shape)
+    }
+
+    pub fn into_reshape(mut self, shape: &[usize]) -> Tensor {
+        let numel: usize = shape.iter().product();
+        assert_eq!(numel, self.len(), "cannot reshape {:?} into {:?}", self.shape, shape);
+        self.make_contiguous();
+        self.shape = shape.to_vec();
+        self.strides = contiguous_strides(shape);
+        self
+    }
+
+    /// 先頭の軸を残して残りを 1 次元にまとめる（[batch, ...] -> [batch, features]）
+    pub fn flatten_batch(&self) -> Tensor {
+        if self.ndim() == 0 {
+            return self.reshape(&[1, 1]);
+        }
+        let features: usize = self.shape[1..].iter().product();
+        self.reshape(&[self.shape[0], features])
+    }
+
+    /// 軸の並べ替え（データは共有したまま strides だけを入れ替える）
+    pub fn permute(&self, axes: &[usize]) -> Tensor {
+        assert_eq!(axes.len(), self.ndim(), "permute needs one entry per axis");
+        let mut seen = vec![false; self.ndim()];
+        for &a in axes {
+            assert!(a < self.ndim() && !seen[a], "invalid permutation {:?}", axes);
+            seen[a] = true;
+        }
+        Tensor {
+            data: Arc::clone(&self.data),
+            shape: axes.iter().map(|&a| self.shape[a]).collect(),
+            strides: axes.iter().map(|&a| self.strides[a]).collect(),
+        }
+    }
+
+    /// 最後の 2 軸を入れ替える
+    pub fn transpose(&self) -> Tensor {
+        assert!(self.ndim() >= 2, "transpose needs at least 2 dimensions");
+        let mut axes: Vec<usize> = (0..self.ndim()).collect();
+        axes.swap(self.ndim() - 2, self.ndim() - 1);
+        self.permute(&axes)
+    }
+
+    /// 先頭の軸に沿った i 番目の要素（[batch, features] なら 1 サンプル分）
+    pub fn row(&self, i: usize) -> &[f32] {
+        let row_len = self.row_len();
+        &self.as_slice()[i * row_len..(i + 1) * row_len]
+    }
+
+    pub fn rows(&self) -> std::slice::ChunksExact<'_, f32> {
+        self.as_slice().chunks_exact(self.row_len().max(1))
+    }
+
+    fn row_len(&self) -> usize {
+        assert!(self.ndim() >= 1, "row access needs at least 1 dimension");
+        self.shape[1..].iter().product()
+    }
+
+    /// 先頭の軸から指定したインデックスの行を集める
+    pub fn select_rows(&self, indices: &[usize]) -> Tensor {
+        let row_len = self.row_len();
+        let src = self.contiguous_cow();
+        let mut data = Vec::with_capacity(indices.len() * row_len);
+        for &i in indices {
+            data.extend_from_slice(src.row(i));
+        }
+        let mut shape = self.shape.clone();
+        shape[0] = indices.len();
+        Tensor::new(data, shape)
+    }
+
+    /// 先頭の軸の [start, end) を切り出す
+    pub fn slice_rows(&self, start: usize, end: usize) -> Tensor {
+        let row_len = self.row_len();
+        let src = self.contiguous_cow();
+        let data = src.data[start * row_len..end * row_len].to_vec();
+        let mut shape = self.shape.clone();
+        shape[0] = end - start;
+        Tensor::new(data, shape)
+    }
+
+    /// 2 次元同士の行列積 [m, k] x [k, n] -> [m, n]
+    ///
+    /// transpose() で作った転置ビューはコピーせずにそのまま計算する。
+    pub fn matmul(&self, other: &Tensor) -> Tensor {
+        assert!(self.ndim() == 2 && other.ndim() == 2, "matmul expects 2-D tensors, got {:?} and {:?}", self.shape, other.shape);
+        let (m, k) = (self.shape[0], self.shape[1]);
+        let (k2, n) = (other.shape[0], other.shape[1]);
+        assert_eq!(k, k2, "matmul shape mismatch: {:?} x {:?}", self.shape, other.shape);
+
+        let mut out = vec![0.0; m * n];
+        let a_row_major = self.strides == [k, 1];
+        let a_transposed = self.strides == [1, m];
+        let b_row_major = other.strides == [n, 1];
+        let b_transposed = other.strides == [1, k];
+
+        if a_row_major && b_transposed {
+            // C[i, j] = A[i, :] . B^T[j, :]
+            for (a_row, c_row) in self.data.chunks_exact(k.max(1)).zip(out.chunks_exact_mut(n.max(1))) {
+                for (c, b_col) in c_row.iter_mut().zip(other.data.chunks_exact(k.max(1))) {
+                    *c = a_row.iter().zip(b_col.iter()).map(|(a, b)| a * b).sum();
+                }
+            }
+        } else if a_transposed && b_row_major {
+            // C[i, :] += A^T[k, i] * B[k, :]
+            for (a_col, b_row) in self.data.chunks_exact(m.max(1)).zip(other.data.chunks_exact(n.max(1))) {
+                for (&a, c_row) in a_col.iter().zip(out.chunks_exact_mut(n.max(1))) {
+                    for (c, b) in c_row.iter_mut().zip(b_row.iter()) {
+                        *c += a * b;
+                    }
+                }
+            }
+        } else {
+            // C[i, :] += A[i, k] * B[k, :]
+            let a = self.contiguous_cow();
+            let b = other.contiguous_cow();
+            for (a_row, c_row) in a.data.chunks_exact(k.max(1)).zip(out.chunks_exact_mut(n.max(1))) {
+                for (&a_ik, b_row) in a_row.iter().zip(b.data.chunks_exact(n.max(1))) {
+                    for (c, b) in c_row.iter_mut().zip(b_row.iter()) {
+                        *c += a_ik * b;
+                    }
+                }
+            }
+        }
+        Tensor::new(out, vec![m, n])
+    }
+
+    pub fn map<F: Fn(f32) -> f32>(&self, f: F) -> Tensor {
+        let src = self.contiguous_cow();
+        Tensor::new(src.data.iter().map(|&x| f(x)).collect(), self.shape.clone())
+    }
+
+    /// ブロードキャストしながら要素ごとに f を適用する
+    pub fn zip_map<F: Fn(f32, f32) -> f32>(&self, other: &Tensor, f: F) -> Tensor {
+        if self.shape == other.shape && self.is_contiguous() && other.is_contiguous() {
+            let data = self.data.iter().zip(other.data.iter()).map(|(&a, &b)| f(a, b)).collect();
+            return Tensor::new(data, self.shape.clone());
+        }
+        let shape = broadcast_shape(&self.shape, &other.shape)
+            .unwrap_or_else(|| panic!("cannot broadcast {:?} with {:?}", self.shape, other.shape));
+        let a = offsets(&shape, &self.broadcast_strides(&shape));
+        let b = offsets(&shape, &other.broadcast_strides(&shape));
+        let data = a.into_iter()
+            .zip(b)
+            .map(|(oa, ob)| f(self.data[oa], other.data[ob]))
+            .collect();
+        Tensor::new(data, shape)
+    }
+
+    /// numpy の規則で shape まで広げた連続なテンソルを作る
+    pub fn broadcast_to(&self, shape: &[usize]) -> Tensor {
+        assert_eq!(
+            broadcast_shape(&self.shape, shape).as_deref(),
+            Some(shape),
+            "cannot broadcast {:?} to {:?}", self.shape, shape
+        );
+        let data = offsets(shape, &self.broadcast_strides(shape))
+            .into_iter()
+            .map(|o| self.data[o])
+            .collect();
+        Tensor::new(data, shape.to_vec())
+    }
+
+    /// shape にブロードキャストしたときの strides（長さ 1 の軸は stride 0）
+    fn broadcast_strides(&self, shape: &[usize]) -> Vec<usize> {
+        let offset = shape.len() - self.ndim();
+        let mut strides = vec![0; shape.len()];
+        for axis in 0..self.ndim() {
+            if self.shape[axis] == shape[axis + offset] {
+                strides[axis + offset] = self.strides[axis];
+            }
+        }
+        strides
+    }
+
+    pub fn scale(&self, factor: f32) -> Tensor {
+        self.map(|x| x * factor)
+    }
+
+    pub fn add_scalar(&self, value: f32) -> Tensor {
+        self.map(|x| x + value)
+    }
+
+    pub fn fill(&mut self, value: f32) {
+        self.as_mut_slice().iter_mut().for_each(|x| *x = value);
+    }
+
+    pub fn sum(&self) -> f32 {
+        self.data.iter().sum()
+    }
+
+    pub fn mean(&self) -> f32 {
+        if self.is_empty() { 0.0 } else { self.sum() / self.len() as f32 }
+    }
+
+    pub fn max(&self) -> f32 {
+        self.data.iter().copied().fold(f32::NEG_INFINITY, f32::max)
+    }
+
+    pub fn min(&self) -> f32 {
+        self.data.iter().copied().fold(f32::INFINITY, f32::min)
+    }
+
+    /// axis を (outer, axis, inner) に分けて reduce する
+    fn reduce_axis<F: Fn(&mut f32, f32)>(&self, axis: usize, init: f32, f: F) -> Tensor {
+        assert!(axis < self.ndim(), "axis {} out of range for shape {:?}", axis, self.shape);
+        let src = self.contiguous_cow();
+        let outer: usize = self.shape[..axis].iter().product();
+        let n = self.shape[axis];
+        let inner: usize = self.shape[axis + 1..].iter().product();
+        let mut out = vec![init; outer * inner];
+        for o in 0..outer {
+            for k in 0..n {
+                let base = (o * n + k) * inner;
+                for (acc, &x) in out[o * inner..(o + 1) * inner].iter_mut().zip(&src.data[base..base + inner]) {
+                    f(acc, x);
+                }
+            }
+        }
+        let mut shape = self.shape.clone();
+        shape.remove(axis);
+        Tensor::new(out, shape)
+    }
+
+    /// axis に沿った和（その軸は取り除かれる）
+    pub fn sum_axis(&self, axis: usize) -> Tensor {
+        self.reduce_axis(axis, 0.0, |acc, x| *acc += x)
+    }
+
+    pub fn mean_axis(&self, axis: usize) -> Tensor {
+        let n = self.shape[axis].max(1) as f32;
+        self.sum_axis(axis).scale(1.0 / n)
+    }
+
+    pub fn max_axis(&self, axis: usize) -> Tensor {
+        self.reduce_axis(axis, f32::NEG_INFINITY, |acc, x| *acc = acc.max(x))
+    }
+
+    /// 2 次元テンソルの各行について最大値のインデックスを返す
+    pub fn argmax_rows(&self) -> Vec<usize> {
+        self.contiguous_cow()
+            .rows()
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .map(|(idx, _)| idx)
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+}
+
+impl AddAssign<&Tensor> for Tensor {
+    /// 右辺を self の shape にブロードキャストして加算する（勾配の蓄積用）
+    fn add_assign(&mut self, rhs: &Tensor) {
+        let shape = self.shape.clone();
+        assert_eq!(
+            broadcast_shape(&shape, &rhs.shape).as_deref(),
+            Some(shape.as_slice()),
+            "cannot accumulate {:?} into {:?}", rhs.shape, shape
+        );
+        let rhs_offsets = offsets(&shape, &rhs.broadcast_strides(&shape));
+        for (x, o) in self.as_mut_slice().iter_mut().zip(rhs_offsets) {
+            *x += rhs.data[o];
+        }
+    }
+}
+
+macro_rules! impl_binary_op {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait<&Tensor> for &Tensor {
+            type Output = Tensor;
+            fn $method(self, rhs: &Tensor) -> Tensor {
+                self.zip_map(rhs, |a, b| a $op b)
+            }
+        }
+
+        impl $trait<Tensor> for Tensor {
+            type Output = Tensor;
+            fn $method(self, rhs: Tensor) -> Tensor {
+                self.zip_map(&rhs, |a, b| a $op b)
+            }
+        }
+    };
+}
+
+impl_binary_op!(Add, add, +);
+impl_binary_op!(Sub, sub, -);
+impl_binary_op!(Mul, mul, *);
+impl_binary_op!(Div, div, /);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// [[1, 2, 3], [4, 5, 6]]
+    fn a() -> Tensor {
+        Tensor::new(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], vec![2, 3])
+    }
+
+    /// a と同じ値を持つ非連続なビュー
+    fn a_view() -> Tensor {
+        let view = Tensor::new(vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0], vec![3, 2]).transpose();
+        assert!(!view.is_contiguous());
+        view
+    }
+
+    #[test]
+    fn permute_and_transpose_only_change_strides() {
+        let t = Tensor::new((0..12).map(|i| i as f32).collect(), vec![2, 3, 2]);
+        let p = t.permute(&[2, 0, 1]);
+        assert_eq!((p.shape(), p.strides()), (&[2, 2, 3][..], &[1, 6, 2][..]));
+        assert_eq!(p.get(&[1, 0, 2]), t.get(&[0, 2, 1]));
+        assert_eq!(p.to_vec(), vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0, 1.0, 3.0, 5.0, 7.0, 9.0, 11.0]);
+
+        let at = a().transpose();
+        assert_eq!((at.shape(), at.strides()), (&[3, 2][..], &[1, 3][..]));
+        assert_eq!(at.get(&[2, 1]), 6.0);
+        assert_eq!(at.transpose(), a());
+    }
+
+    #[test]
+    fn writes_do_not_leak_into_shared_views() {
+        let original = a();
+        let mut view = original.transpose();
+        view.as_mut_slice()[0] = 100.0;
+        assert_eq!(view.to_vec(), vec![100.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+        assert_eq!(original, a());
+
+        let mut copy = original.clone();
+        copy.fill(0.0);
+        assert_eq!(original.to_vec(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn reshape_of_non_contiguous_view_uses_logical_order() {
+        let view = a().transpose();
+        assert_eq!(view.reshape(&[6]).to_vec(), vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+        let reshaped = view.reshape(&[2, 3]);
+        assert!(reshaped.is_contiguous());
+        assert_eq!(reshaped.row(1), &[5.0, 3.0, 6.0]);
+        assert_eq!(a_view().flatten_batch(), a());
+    }
+
+    #[test]
+    fn matmul_handles_transposed_views() {
+        // [[1, 2, 3], [4, 5, 6]] x [[1, 0], [0, 1], [2, -1]] = [[7, -1], [16, -1]]
+        let expected = Tensor::new(vec![7.0, -1.0, 16.0, -1.0], vec![2, 2]);
+        let b = Tensor::new(vec![1.0, 0.0, 0.0, 1.0, 2.0, -1.0], vec![3, 2]);
+        let b_view = Tensor::new(vec![1.0, 0.0, 2.0, 0.0, 1.0, -1.0], vec![2, 3]).transpose();
+        assert_eq!(b_view.strides(), &[1, 3]);
+
+        assert_eq!(a().matmul(&b), expected);
+        // A B^T（B が転置ビュー）
+        assert_eq!(a().matmul(&b_view), expected);
+        // A^T B（A が転置ビュー）
+        assert_eq!(a_view().strides(), &[1, 2]);
+        assert_eq!(a_view().matmul(&b), expected);
+        // 両方ビューなら連続化してから計算する
+        assert_eq!(a_view().matmul(&b_view), expected);
+        assert_eq!(a().matmul(&b).transpose().matmul(&Tensor::ones(&[2, 1])).to_vec(), vec![23.0, -2.0]);
+    }
+
+    #[test]
+    fn zip_map_broadcasts() {
+        let row = Tensor::new(vec![10.0, 20.0, 30.0], vec![3]);
+        assert_eq!((&a() + &row).to_vec(), vec![11.0, 22.0, 33.0, 14.0, 25.0, 36.0]);
+        let column = Tensor::new(vec![1.0, 2.0], vec![2, 1]);
+        let outer = &column * &Tensor::new(vec![1.0, 2.0, 3.0], vec![1, 3]);
+        assert_eq!((outer.shape(), outer.to_vec()), (&[2, 3][..], vec![1.0, 2.0, 3.0, 2.0, 4.0, 6.0]));
+        assert_eq!((&a_view() - &a()).to_vec(), vec![0.0; 6]);
+        assert_eq!((&a_view() / &Tensor::scalar(2.0)).to_vec(), vec![0.5, 1.0, 1.5, 2.0, 2.5, 3.0]);
+        assert_eq!(row.broadcast_to(&[2, 3]).to_vec(), vec![10.0, 20.0, 30.0, 10.0, 20.0, 30.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot broadcast [2, 3] with [2]")]
+    fn zip_map_rejects_incompatible_shapes() {
+        let _ = &a() + &Tensor::ones(&[2]);
+    }
+
+    #[test]
+    fn reductions_along_an_axis() {
+        assert_eq!(a().sum_axis(0).to_vec(), vec![5.0, 7.0, 9.0]);
+        assert_eq!(a().sum_axis(1).to_vec(), vec![6.0, 15.0]);
+        assert_eq!(a().mean_axis(1).to_vec(), vec![2.0, 5.0]);
+        assert_eq!(a().max_axis(0).to_vec(), vec![4.0, 5.0, 6.0]);
+        assert_eq!(a_view().sum_axis(1).to_vec(), vec![6.0, 15.0]);
+        assert_eq!(a_view().sum(), 21.0);
+
+        let t = Tensor::new((0..12).map(|i| i as f32).collect(), vec![2, 3, 2]);
+        let summed = t.sum_axis(1);
+        assert_eq!((summed.shape(), summed.to_vec()), (&[2, 2][..], vec![6.0, 9.0, 24.0, 27.0]));
+        assert_eq!(t.max_axis(2).to_vec(), vec![1.0, 3.0, 5.0, 7.0, 9.0, 11.0]);
+        assert_eq!(a_view().argmax_rows(), vec![2, 2]);
+    }
+
+    #[test]
+    fn select_and_slice_rows() {
+        let selected = a_view().select_rows(&[1, 0, 1]);
+        assert_eq!(selected.shape(), &[3, 3]);
+        assert_eq!(selected.to_vec(), vec![4.0, 5.0, 6.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(a_view().slice_rows(1, 2).to_vec(), vec![4.0, 5.0, 6.0]);
+        assert_eq!(a().select_rows(&[]).shape(), &[0, 3]);
+    }
+
+    #[test]
+    fn add_assign_broadcasts_into_self() {
+        let mut acc = Tensor::zeros(&[2, 3]);
+        acc += &Tensor::new(vec![1.0, 2.0, 3.0], vec![3]);
+        acc += &a_view();
+        assert_eq!(acc.to_vec(), vec![2.0, 4.0, 6.0, 5.0, 7.0, 9.0]);
+
+        // 非連続な self は連続化してから足す
+        let mut view = a_view();
+        view += &Tensor::ones(&[2, 1]);
+        assert!(view.is_contiguous());
+        assert_eq!(view.to_vec(), vec![2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot accumulate")]
+    fn add_assign_does_not_grow_self() {
+        let mut acc = Tensor::zeros(&[3]);
+        acc += &a();
+    }
+}
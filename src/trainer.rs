@@ -1,41 +1,60 @@
 use crate::model::Model;
 use crate::optimizers::base_optimizer::AbstractOptimizerTrait;
 use crate::losses::base_loss::AbstractLossFunctionTrait;
-use crate::data::DataSet;
-use rand::seq::SliceRandom;
-use rand::thread_rng;
+use crate::data::{DataLoader, DataSet, Dataset, SplitOptions, Targets};
+use crate::schedulers::base_scheduler::{LrScheduler, SchedulerInterval};
+use crate::tensor::Tensor;
 
-pub struct Trainer<O, L>
+/// 勾配クリッピングの方法
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientClipping {
+    /// 各要素を [-max, max] に収める
+    Value(f32),
+    /// 全レイヤーをまとめた L2 ノルムが max を超えたら縮める
+    Norm(f32),
+}
+
+pub struct Trainer<O, L, D = DataSet>
 where
     O: AbstractOptimizerTrait,
     L: AbstractLossFunctionTrait,
+    D: Dataset + Send + Sync + 'static,
 {
     pub model: Model,
     pub optimizer: O,
     pub loss_function: L,
-    pub train_dataset: DataSet,
-    pub test_dataset: DataSet,
+    /// 学習データのミニバッチを作る（順番の決め方や seed はここで変える。評価も同じバッチサイズで行う）
+    pub train_loader: DataLoader<D>,
+    pub test_dataset: D,
     // pub device: Device,
     pub epoch: usize,
-    pub batch_size: usize,
     pub verbose: bool,
     pub eval_limit: Option<usize>,
-    pub train_limit: Option<usize>,
+    pub scheduler: Option<Box<dyn LrScheduler>>,
+    pub gradient_clipping: Option<GradientClipping>,
+    /// 直近のバッチのクリップ前の勾配ノルム（勾配爆発の監視用）
+    pub last_grad_norm: Option<f32>,
+    /// 学習データのサンプルごとの重み（損失と勾配の両方に掛ける）
+    pub sample_weights: Option<Vec<f32>>,
+    /// 多ラベル分類で出力を 1 とみなす閾値（既定値 0.5、モデルがロジットを出すなら 0 にする）
+    pub multi_label_threshold: f32,
     // pub callbacks: Vec<Box<dyn Callback>>,
     // pub metrics: Vec<Box<dyn Metric>>,
     // pub visualization: bool,
 }
 
-impl<O, L> Trainer<O, L> where
+impl<O, L, D> Trainer<O, L, D> where
     O: AbstractOptimizerTrait,
     L: AbstractLossFunctionTrait,
+    D: Dataset + Send + Sync + 'static,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         model: Model, 
         optimizer: O, 
         loss_function: L, 
-        train_dataset: DataSet, 
-        test_dataset: DataSet, 
+        train_dataset: D, 
+        test_dataset: D, 
         epoch: usize, 
         batch_size: usize,
         verbose: bool,
@@ -43,31 +62,62 @@ impl<O, L> Trainer<O, L> where
     ) -> Self {
         let eval_limit = if debug { Some(1000) } else { None };
         let train_limit = if debug { Some(100) } else { None };
+        let train_loader = DataLoader::new(train_dataset, batch_size.max(1)).limit(train_limit);
         Self {
             model,
             optimizer,
             loss_function,
-            train_dataset,
+            train_loader,
             test_dataset,
             epoch,
-            batch_size,
             verbose,
             eval_limit,
-            train_limit,
+            scheduler: None,
+            gradient_clipping: None,
+            last_grad_norm: None,
+            sample_weights: None,
+            multi_label_threshold: 0.5,
+        }
+    }
+
+    /// 学習データのサンプルごとの重みを設定する（順番は学習データと同じ）
+    pub fn set_sample_weights(&mut self, sample_weights: Vec<f32>) {
+        assert_eq!(
+            sample_weights.len(), self.train_loader.dataset().len(),
+            "expected one weight per training sample"
+        );
+        self.sample_weights = Some(sample_weights);
+    }
+
+    /// optimizer.update の前に勾配をクリップする
+    pub fn set_gradient_clipping(&mut self, clipping: GradientClipping) {
+        self.gradient_clipping = Some(clipping);
+    }
+
+    /// 学習率スケジューラを設定する（run() の最初にスケジューラの学習率でオプティマイザを上書きする）
+    pub fn set_scheduler(&mut self, scheduler: Box<dyn LrScheduler>) {
+        self.scheduler = Some(scheduler);
+    }
+
+    /// interval のタイミングならスケジューラを進めて、オプティマイザの学習率を更新する
+    fn step_scheduler(&mut self, interval: SchedulerInterval, metric: Option<f32>) {
+        if let Some(scheduler) = &mut self.scheduler {
+            if scheduler.interval() == interval {
+                let learning_rate = scheduler.step(metric);
+                self.optimizer.set_learning_rate(learning_rate);
+            }
         }
     }
 
     pub fn run(&mut self) {
         
         let output_size = self.model.layers.last().unwrap().o_size();
-        let num_features = self.train_dataset.num_features;
 
-        let train_num_samples = self.train_limit
-            .unwrap_or(self.train_dataset.num_samples)
-            .min(self.train_dataset.num_samples);
+        let num_batches = self.train_loader.num_batches();
 
-        let batch_size = self.batch_size.max(1);
-        let num_batches = (train_num_samples + batch_size - 1) / batch_size;
+        if let Some(scheduler) = &self.scheduler {
+            self.optimizer.set_learning_rate(scheduler.learning_rate());
+        }
 
         for epoch in 0..self.epoch {
             if self.verbose {
@@ -76,88 +126,79 @@ impl<O, L> Trainer<O, L> where
                 println!("{}", "=".repeat(60));
             }
 
-            let mut indices: Vec<usize> = (0..train_num_samples).collect();
-            indices.shuffle(&mut thread_rng());
-
-            for batch_idx in 0..num_batches {
-                let start = batch_idx * batch_size;
-                let end = (start + batch_size).min(train_num_samples);
-                let current_batch_size = end - start;
-
-                let mut batch_grad_w: Vec<Vec<f32>> = self.model.layers
-                    .iter()
-                    .map(|layer| vec![0.0; layer.w().len()])
-                    .collect();
-                let mut batch_grad_b: Vec<Vec<f32>> = self.model.layers
-                    .iter()
-                    .map(|layer| vec![0.0; layer.b().len()])
-                    .collect();
-                let mut loss_sum: f32 = 0.0;
-                let mut last_output: Vec<f32> = Vec::new();
-                let mut last_label: Vec<f32> = Vec::new();
-
-                for i in start..end {
-                    let sample_idx = indices[i];
-                    // prepare input and label
-                    let input_start = sample_idx * num_features;
-                    let input_end = input_start + num_features;
-                    let input = self.train_dataset.images[input_start..input_end].to_vec();
-                    let mut label = vec![0.0; output_size];
-                    label[self.train_dataset.labels[sample_idx] as usize] = 1.0;
-
-                    // forward
-                    let output = self.model.forward(&input);
-
-                    // calculate loss
-                    let loss = self.loss_function.forward(&label, &output);
-                    loss_sum += loss;
-                    last_output = output.clone();
-                    last_label = label.clone();
-
-                    // backward
-                    let loss_grad = self.loss_function.backward(&label, &output);
-                    self.model.backward(&loss_grad);
-
-                    // accumulate gradients
-                    for (layer_idx, layer) in self.model.layers.iter().enumerate() {
-                        for j in 0..layer.grad_w().len() {
-                            batch_grad_w[layer_idx][j] += layer.grad_w()[j];
-                        }
-                        for j in 0..layer.grad_b().len() {
-                            batch_grad_b[layer_idx][j] += layer.grad_b()[j];
-                        }
-                    }
-                }
+            self.model.train();
 
-                let scale = 1.0 / current_batch_size as f32;
-                for (layer_idx, layer) in self.model.layers.iter_mut().enumerate() {
-                    {
-                        let grad_w = layer.grad_w_mut();
-                        for j in 0..grad_w.len() {
-                            grad_w[j] = batch_grad_w[layer_idx][j] * scale;
-                        }
+            // バッチは train_loader が別スレッドで先に作っておく
+            for (batch_idx, batch) in self.train_loader.iter().enumerate() {
+                let current_batch_size = batch.indices.len();
+                let (inputs, targets): (Tensor, Targets) = (batch.inputs, batch.targets);
+                let labels: Tensor = targets.to_tensor(output_size);
+
+                // forward
+                self.model.zero_grad();
+                let outputs = self.model.forward(&inputs);
+
+                // calculate loss (averaged over the batch) and backward
+                // (gradients are accumulated inside each layer)
+                // Reduction::None の損失は、表示用にサンプルごとの損失の平均を取る
+                let (loss, loss_grad) = match &self.sample_weights {
+                    Some(sample_weights) => {
+                        let weights = Tensor::new(
+                            batch.indices.iter().map(|&i| sample_weights[i]).collect(),
+                            vec![current_batch_size],
+                        );
+                        (
+                            self.loss_function.forward_weighted(&labels, &outputs, &weights).mean(),
+                            self.loss_function.backward_weighted(&labels, &outputs, &weights),
+                        )
                     }
-                    {
-                        let grad_b = layer.grad_b_mut();
-                        for j in 0..grad_b.len() {
-                            grad_b[j] = batch_grad_b[layer_idx][j] * scale;
-                        }
+                    None => (
+                        self.loss_function.forward(&labels, &outputs).mean(),
+                        self.loss_function.backward(&labels, &outputs),
+                    ),
+                };
+                self.model.backward(&loss_grad);
+
+                // clip (the norm is measured before clipping)
+                let grad_norm = match self.gradient_clipping {
+                    Some(GradientClipping::Norm(max_norm)) => self.model.clip_grad_norm(max_norm),
+                    Some(GradientClipping::Value(max_value)) => {
+                        let norm = self.model.grad_norm();
+                        self.model.clip_grad_value(max_value);
+                        norm
                     }
-                }
+                    None => self.model.grad_norm(),
+                };
+                self.last_grad_norm = Some(grad_norm);
 
                 // update
                 self.optimizer.update(&mut self.model);
+                self.step_scheduler(SchedulerInterval::Step, None);
 
                 // verbose output
                 if self.verbose && batch_idx % 10 == 0 {
-                    let avg_loss = loss_sum / current_batch_size as f32;
-                    self.verbose_output(epoch, batch_idx, num_batches, avg_loss, &last_output, &last_label);
+                    let last_row = current_batch_size - 1;
+                    self.verbose_output(
+                        epoch, batch_idx, num_batches, loss, grad_norm,
+                        outputs.row(last_row),
+                        labels.row(last_row),
+                        matches!(targets, Targets::Classes(_)),
+                    );
                 }
             }
 
-            let accuracy = self.evaluate_accuracy();
+            let (validation_loss, accuracy) = self.evaluate();
+            if self.verbose {
+                match accuracy {
+                    Some(accuracy) => println!("Validation loss: {:.6}, accuracy: {:.2}%", validation_loss, accuracy),
+                    None => println!("Validation loss: {:.6}", validation_loss),
+                }
+            }
+            self.step_scheduler(SchedulerInterval::Epoch, Some(validation_loss));
             if self.verbose {
-                println!("Validation accuracy: {:.2}%", accuracy);
+                if let Some(learning_rate) = self.optimizer.learning_rate() {
+                    println!("Learning rate: {:.6}", learning_rate);
+                }
             }
 
             if self.verbose {
@@ -166,45 +207,295 @@ impl<O, L> Trainer<O, L> where
         }
     }
 
-    fn verbose_output(&self, _epoch: usize, i: usize, num_steps: usize, loss: f32, output: &[f32], label: &[f32]) {
+    #[allow(clippy::too_many_arguments)]
+    fn verbose_output(&self, _epoch: usize, i: usize, num_steps: usize, loss: f32, grad_norm: f32, output: &[f32], label: &[f32], is_class: bool) {
         let progress = (i as f32 / num_steps as f32) * 100.0;
         println!("\n[Step {}/{} ({:.1}%)]", i + 1, num_steps, progress);
         println!("  Loss: {:.6}", loss);
+        println!("  Grad norm: {:.6}", grad_norm);
         
-        // Find predicted class
-        let predicted_class = output.iter()
-            .enumerate()
-            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-            .map(|(idx, _)| idx)
-            .unwrap();
-        let true_class = label.iter()
-            .position(|&x| x == 1.0)
-            .unwrap();
-        
-        println!("  True class: {}, Predicted class: {}", true_class, predicted_class);
-        println!("  Output probabilities: {:?}", 
+        if is_class {
+            let predicted_class = argmax(output);
+            let true_class = argmax(label);
+            println!("  True class: {}, Predicted class: {}", true_class, predicted_class);
+        } else {
+            println!("  Targets: {:?}",
+                label.iter().map(|&x| format!("{:.4}", x)).collect::<Vec<_>>());
+        }
+        println!("  Outputs: {:?}", 
             output.iter().map(|&x| format!("{:.4}", x)).collect::<Vec<_>>());
     }
 
-    fn evaluate_accuracy(&mut self) -> f32 {
-        let mut correct = 0usize;
-        let eval_samples = self.eval_limit.unwrap_or(self.test_dataset.num_samples).min(self.test_dataset.num_samples);
-        for idx in 0..eval_samples {
-            let input_start = idx * self.test_dataset.num_features;
-            let input_end = input_start + self.test_dataset.num_features;
-            let input = self.test_dataset.images[input_start..input_end].to_vec();
-            let label = self.test_dataset.labels[idx];
-            let output = self.model.forward(&input);
-            let predicted_class = output.iter()
-                .enumerate()
-                .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-                .map(|(idx, _)| idx)
-                .unwrap();
-            if predicted_class == label as usize {
-                correct += 1;
+    /// 推論モードでテストデータの (平均損失, 正解率 %) を求める（終わったら元のモードに戻す）
+    ///
+    /// 正解率はクラス番号ならサンプル単位、多ラベルならラベル単位（multi_label_threshold で 0 / 1 にする）。
+    /// 実数の正解（回帰）では None。
+    fn evaluate(&mut self) -> (f32, Option<f32>) {
+        let was_training = self.model.is_training();
+        self.model.eval();
+
+        let output_size = self.model.layers.last().unwrap().o_size();
+        let mut total_loss = 0.0;
+        let (mut correct, mut total) = (0usize, 0usize);
+        let mut has_accuracy = false;
+        let eval_samples = self.eval_limit.unwrap_or(self.test_dataset.len()).min(self.test_dataset.len());
+        // 学習と同じバッチサイズで評価する
+        let batch_size = self.train_loader.batch_size();
+
+        for start in (0..eval_samples).step_by(batch_size) {
+            let end = (start + batch_size).min(eval_samples);
+            let indices: Vec<usize> = (start..end).collect();
+            let (inputs, targets) = self.test_dataset.batch(&indices);
+            let outputs = self.model.forward(&inputs);
+            let labels = targets.to_tensor(output_size);
+            // reduction の設定によらず、サンプルごとの損失の和から平均を求める
+            total_loss += self.loss_function.forward_per_sample(&labels, &outputs).sum();
+            match &targets {
+                Targets::Classes(classes) => {
+                    for (predicted_class, &class) in outputs.argmax_rows().into_iter().zip(classes) {
+                        if predicted_class == class as usize {
+                            correct += 1;
+                        }
+                    }
+                    total += classes.len();
+                    has_accuracy = true;
+                }
+                Targets::MultiHot(_) => {
+                    let threshold = self.multi_label_threshold;
+                    for (&output, &label) in outputs.flatten_batch().as_slice().iter().zip(labels.as_slice()) {
+                        if (output > threshold) == (label > 0.5) {
+                            correct += 1;
+                        }
+                    }
+                    total += labels.len();
+                    has_accuracy = true;
+                }
+                Targets::Dense(_) => {}
             }
         }
+        if was_training {
+            self.model.train();
+        }
         let denom = if eval_samples == 0 { 1 } else { eval_samples };
-        (correct as f32 / denom as f32) * 100.0
+        let accuracy = has_accuracy.then(|| correct as f32 / total.max(1) as f32 * 100.0);
+        (total_loss / denom as f32, accuracy)
+    }
+}
+
+/// 交差検証の fold ごとの結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossValidation {
+    /// fold ごとの検証データの平均損失
+    pub losses: Vec<f32>,
+    /// fold ごとの正解率 %（実数の正解では None）
+    pub accuracies: Option<Vec<f32>>,
+}
+
+impl CrossValidation {
+    /// 損失の (平均, 標準偏差)
+    pub fn loss(&self) -> (f32, f32) {
+        mean_std(&self.losses)
+    }
+
+    /// 正解率の (平均, 標準偏差)
+    pub fn accuracy(&self) -> Option<(f32, f32)> {
+        self.accuracies.as_deref().map(mean_std)
+    }
+}
+
+impl<O, L> Trainer<O, L, DataSet> where
+    O: AbstractOptimizerTrait,
+    L: AbstractLossFunctionTrait,
+{
+    /// k 分割交差検証: fold ごとに make_trainer(train, validation) で作った Trainer を学習して評価する
+    ///
+    /// モデルとオプティマイザは fold ごとに作り直す必要があるので、Trainer ごと作る関数を渡す。
+    /// verbose なら fold ごとの結果と平均 ± 標準偏差を表示する。
+    pub fn cross_validate<F>(dataset: &DataSet, k: usize, options: &SplitOptions, verbose: bool, mut make_trainer: F) -> CrossValidation
+    where
+        F: FnMut(DataSet, DataSet) -> Self,
+    {
+        let mut losses = Vec::with_capacity(k);
+        let mut accuracies = Vec::with_capacity(k);
+        for (fold, (train, validation)) in dataset.k_fold(k, options).enumerate() {
+            let mut trainer = make_trainer(train, validation);
+            trainer.run();
+            let (loss, accuracy) = trainer.evaluate();
+            if verbose {
+                match accuracy {
+                    Some(accuracy) => println!("Fold {}/{}: loss: {:.6}, accuracy: {:.2}%", fold + 1, k, loss, accuracy),
+                    None => println!("Fold {}/{}: loss: {:.6}", fold + 1, k, loss),
+                }
+            }
+            losses.push(loss);
+            accuracies.extend(accuracy);
+        }
+
+        // 正解率はどの fold でも同じ種類の正解から求めるので、全部あるか全部ないか
+        let result = CrossValidation {
+            accuracies: (accuracies.len() == losses.len()).then_some(accuracies),
+            losses,
+        };
+        if verbose {
+            let (mean, std) = result.loss();
+            println!("Cross-validation loss: {:.6} ± {:.6}", mean, std);
+            if let Some((mean, std)) = result.accuracy() {
+                println!("Cross-validation accuracy: {:.2}% ± {:.2}%", mean, std);
+            }
+        }
+        result
+    }
+}
+
+/// (平均, 標準偏差)。標準偏差は n で割る（fold 数が少ないので不偏推定にはしない）
+fn mean_std(values: &[f32]) -> (f32, f32) {
+    let n = values.len().max(1) as f32;
+    let mean = values.iter().sum::<f32>() / n;
+    let variance = values.iter().map(|&x| (x - mean) * (x - mean)).sum::<f32>() / n;
+    (mean, variance.sqrt())
+}
+
+/// 最大値の位置
+fn argmax(values: &[f32]) -> usize {
+    values.iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(idx, _)| idx)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::fc_layer::FcLayer;
+    use crate::losses::binary_cross_entropy_loss::BinaryCrossEntropyWithLogitsLoss;
+    use crate::losses::mse_loss::MseLoss;
+    use crate::losses::softmax_cross_entropy_loss::SoftmaxCrossEntropyLoss;
+    use crate::optimizers::sgd::{Sgd, SgdParams};
+
+    fn sgd(learning_rate: f32) -> Sgd {
+        let mut optimizer = Sgd::new("sgd".to_string());
+        optimizer.build(SgdParams::new().learning_rate(learning_rate));
+        optimizer
+    }
+
+    fn linear_model(i_size: usize, o_size: usize, w: Vec<f32>) -> Model {
+        let fc = FcLayer::from_parameters("fc".to_string(), i_size, o_size, "identity".to_string(), w, vec![0.0; o_size]);
+        Model::new(vec![Box::new(fc)])
+    }
+
+    #[test]
+    fn class_targets_are_not_limited_to_u8() {
+        let labels = Targets::Classes(vec![299, 3]).to_tensor(300);
+        assert_eq!(labels.shape(), &[2, 300]);
+        assert_eq!(labels.row(0)[299], 1.0);
+        assert_eq!(labels.row(1)[3], 1.0);
+        assert_eq!(labels.sum(), 2.0);
+    }
+
+    #[test]
+    fn trains_on_dense_targets() {
+        // y = [x0 - x1, 2 * x1] を学習する回帰
+        let x = Tensor::new(vec![1.0, 0.0, 0.0, 1.0, 1.0, 1.0, -1.0, 0.5], vec![4, 2]);
+        let y = x.matmul(&Tensor::new(vec![1.0, 0.0, -1.0, 2.0], vec![2, 2]));
+        let dataset = || DataSet::new(x.clone(), Targets::Dense(y.clone()));
+        let mut trainer = Trainer::new(
+            linear_model(2, 2, vec![0.0; 4]), sgd(0.2), MseLoss::new("mse".to_string()),
+            dataset(), dataset(), 200, 4, false, false,
+        );
+        let (initial_loss, accuracy) = trainer.evaluate();
+        assert_eq!(accuracy, None);
+        trainer.run();
+        let (loss, _) = trainer.evaluate();
+        assert!(loss < initial_loss * 1e-3, "{} -> {}", initial_loss, loss);
+    }
+
+    #[test]
+    fn trains_on_any_dataset() {
+        use crate::data::{MmapDataset, Sample, Sampling, StreamingDataset, Target};
+
+        // trains_on_dense_targets と同じ回帰を、ストリームと mmap のデータで学習する
+        let x = Tensor::new(vec![1.0, 0.0, 0.0, 1.0, 1.0, 1.0, -1.0, 0.5], vec![4, 2]);
+        let y = x.matmul(&Tensor::new(vec![1.0, 0.0, -1.0, 2.0], vec![2, 2]));
+        let samples: Vec<Sample> = x.rows().zip(y.rows())
+            .map(|(x, y)| Sample { features: x.to_vec(), target: Target::Dense(y.to_vec()) })
+            .collect();
+        let stream = move || {
+            let samples = samples.clone();
+            StreamingDataset::new(4, vec![2], move || samples.clone().into_iter())
+        };
+        let path = std::env::temp_dir().join(format!("nn_rust_trainer_{}.nnrb", std::process::id()));
+        DataSet::new(x.clone(), Targets::Dense(y.clone())).save_binary(&path).unwrap();
+
+        let datasets: [(Box<dyn Dataset + Send + Sync>, Box<dyn Dataset + Send + Sync>); 2] = [
+            (Box::new(stream()), Box::new(stream())),
+            (Box::new(MmapDataset::open(&path).unwrap()), Box::new(MmapDataset::open(&path).unwrap())),
+        ];
+        for (train, test) in datasets {
+            let mut trainer = Trainer::new(
+                linear_model(2, 2, vec![0.0; 4]), sgd(0.2), MseLoss::new("mse".to_string()),
+                train, test, 200, 2, false, false,
+            );
+            // ストリームは先頭から順番にしか読めない
+            trainer.train_loader.set_sampling(Sampling::Sequential);
+            let (initial_loss, _) = trainer.evaluate();
+            trainer.run();
+            let (loss, _) = trainer.evaluate();
+            assert!(loss < initial_loss * 1e-3, "{} -> {}", initial_loss, loss);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn multi_hot_accuracy_is_per_label() {
+        // 出力 = 入力（ロジット）なので、閾値 0 で [1, 0, 1] と [0, 0, 1] になる
+        let x = Tensor::new(vec![2.0, -1.0, 3.0, -2.0, -1.0, 0.5], vec![2, 3]);
+        let targets = Targets::MultiHot(Tensor::new(vec![1.0, 0.0, 1.0, 1.0, 0.0, 0.0], vec![2, 3]));
+        let dataset = || DataSet::new(x.clone(), targets.clone());
+        let identity = vec![1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let mut trainer = Trainer::new(
+            linear_model(3, 3, identity), sgd(0.1), BinaryCrossEntropyWithLogitsLoss::new("bce".to_string()),
+            dataset(), dataset(), 0, 2, false, false,
+        );
+        trainer.multi_label_threshold = 0.0;
+        let (_, accuracy) = trainer.evaluate();
+        assert!((accuracy.unwrap() - 400.0 / 6.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn cross_validate_reports_mean_and_std() {
+        // クラス = 大きい方の特徴量。重みが最初から正しいので、学習率 0 ならどの fold も 100%
+        let x = Tensor::new((0..12).map(|i| if i % 4 < 2 { (i % 2) as f32 } else { 1.0 - (i % 2) as f32 }).collect(), vec![6, 2]);
+        let dataset = DataSet::new(x, Targets::Classes(vec![1, 0, 1, 0, 1, 0]));
+        let options = SplitOptions::new().seed(3).stratified(true);
+        let result = Trainer::cross_validate(&dataset, 3, &options, false, |train, validation| {
+            assert_eq!((train.num_samples(), validation.num_samples()), (4, 2));
+            Trainer::new(
+                linear_model(2, 2, vec![1.0, 0.0, 0.0, 1.0]), sgd(0.0), SoftmaxCrossEntropyLoss::new("ce".to_string()),
+                train, validation, 1, 2, false, false,
+            )
+        });
+        assert_eq!(result.losses.len(), 3);
+        assert_eq!(result.accuracies, Some(vec![100.0; 3]));
+        assert_eq!(result.accuracy(), Some((100.0, 0.0)));
+        assert!(result.loss().1 < 1e-6);
+        assert_eq!(mean_std(&[1.0, 3.0]), (2.0, 1.0));
+    }
+
+    #[test]
+    fn validation_loss_does_not_depend_on_reduction() {
+        use crate::losses::base_loss::Reduction;
+
+        let x = Tensor::new(vec![1.0, 0.0, 0.0, 1.0, 1.0, 1.0, -1.0, 0.5, 2.0, -1.0], vec![5, 2]);
+        let y = Tensor::new(vec![0.5, 1.0, -1.0, 0.0, 2.0, 1.5, 0.0, 0.0, 1.0, -2.0], vec![5, 2]);
+        let dataset = || DataSet::new(x.clone(), Targets::Dense(y.clone()));
+        let losses: Vec<f32> = [Reduction::Mean, Reduction::Sum].into_iter().map(|reduction| {
+            let mut trainer = Trainer::new(
+                linear_model(2, 2, vec![0.5, -1.0, 1.0, 0.25]), sgd(0.1), MseLoss::new("mse".to_string()).reduction(reduction),
+                dataset(), dataset(), 0, 2, false, false,
+            );
+            trainer.evaluate().0
+        }).collect();
+        assert!((losses[0] - losses[1]).abs() < 1e-6, "{:?}", losses);
     }
 }